@@ -0,0 +1,69 @@
+use std::fmt::Write;
+
+use fst_file::error::PositionError;
+use nom::error::VerboseErrorKind;
+use termion::{color, style};
+
+const ROW: usize = 16;
+
+/// Hexdump of the bytes around a parse error, the failing byte is marked with `^^`
+/// and highlighted when `color` is set
+pub fn render(error: &PositionError<VerboseErrorKind>, color: bool) -> String {
+    let mut text = String::new();
+    let Some(offset) = error.offset() else {
+        return text;
+    };
+    let excerpt = error.excerpt();
+    // addresses are file offsets when the data is in the file as is
+    let base = error.data_offset().unwrap_or(0);
+    let data = match (error.block(), error.data_offset()) {
+        (Some(block), Some(_)) => format!("{block}"),
+        (Some(block), None) => format!("the extracted data of {block}"),
+        (None, _) => "the data".to_string(),
+    };
+    let _ = writeln!(text, "bytes of {data} around offset {}:", base + offset);
+    if offset >= excerpt.start + excerpt.bytes.len() {
+        let _ = writeln!(text, "  the data ends before offset {}", base + offset);
+    }
+    for (row, bytes) in excerpt.bytes.chunks(ROW).enumerate() {
+        let start = excerpt.start + row * ROW;
+        let mut hex = String::new();
+        let mut ascii = String::new();
+        let mut marker = None;
+        for (i, byte) in bytes.iter().enumerate() {
+            if i == ROW / 2 {
+                hex.push(' ');
+            }
+            let failing = start + i == offset;
+            if failing {
+                marker = Some(hex.len() + 1);
+            }
+            if failing && color {
+                let _ = write!(
+                    hex,
+                    " {}{}{byte:02x}{}{}",
+                    style::Bold,
+                    color::Fg(color::Red),
+                    color::Fg(color::Reset),
+                    style::Reset
+                );
+            } else {
+                let _ = write!(hex, " {byte:02x}");
+            }
+            ascii.push(if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            });
+        }
+        // pad short rows so the ascii column stays aligned
+        let width = ROW * 3 + 1;
+        let visible = bytes.len() * 3 + usize::from(bytes.len() > ROW / 2);
+        let padding = " ".repeat(width.saturating_sub(visible));
+        let _ = writeln!(text, "  {:08x} {hex}{padding}  |{ascii}|", base + start);
+        if let Some(column) = marker {
+            let _ = writeln!(text, "  {:8} {}^^", "", " ".repeat(column));
+        }
+    }
+    text
+}
@@ -3,12 +3,31 @@ use std::{
     collections::HashMap,
     fmt,
     fs::{File, OpenOptions},
-    io::{IsTerminal, Read, Write},
+    io::{BufReader, BufWriter, Cursor, IsTerminal, Read, Write},
     path::PathBuf,
     sync::OnceLock,
 };
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::eyre;
+use fst_file::{
+    block_parsers::hierarchy::{
+        diff::diff,
+        export::{self, TreeOptions},
+        index::{PathIndex, Regex},
+        source::SourceLocations,
+    },
+    check::{check_file_with_options, CheckStatus},
+    data_types::WriterPackType,
+    error::PositionError,
+    options::ParseOptions,
+    transform::SignalSelector,
+    writer::{HierarchyCompression, WriterOptions},
+};
+use nom::error::VerboseErrorKind;
+use serde::Serialize;
+
+mod diagnostic;
 
 use termion::color;
 use tracing::{debug, debug_span, error, metadata::LevelFilter, trace};
@@ -57,6 +76,111 @@ struct CommonArgs {
     /// output format
     #[arg(short, long, value_enum, default_value_t)]
     format: OutputFormat,
+    #[command(flatten)]
+    parse: ParseArgs,
+}
+
+#[derive(Debug, Args)]
+struct ParseArgs {
+    /// fail on data that does not follow the format instead of warning about it
+    #[arg(long)]
+    strict: bool,
+}
+
+impl ParseArgs {
+    fn options(&self) -> ParseOptions {
+        if self.strict {
+            ParseOptions::strict()
+        } else {
+            ParseOptions::lenient()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum ArgHierarchyCompression {
+    #[default]
+    Gz,
+    Lz4,
+    Lz4Duo,
+}
+
+impl From<ArgHierarchyCompression> for HierarchyCompression {
+    fn from(value: ArgHierarchyCompression) -> HierarchyCompression {
+        match value {
+            ArgHierarchyCompression::Gz => HierarchyCompression::Gz,
+            ArgHierarchyCompression::Lz4 => HierarchyCompression::Lz4,
+            ArgHierarchyCompression::Lz4Duo => HierarchyCompression::Lz4Duo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum ArgPackType {
+    Zlib,
+    Fastlz,
+    #[default]
+    Lz4,
+}
+
+impl From<ArgPackType> for WriterPackType {
+    fn from(value: ArgPackType) -> WriterPackType {
+        match value {
+            ArgPackType::Zlib => WriterPackType::Zlib,
+            ArgPackType::Fastlz => WriterPackType::FaslLz,
+            ArgPackType::Lz4 => WriterPackType::Lz4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum MergeMode {
+    /// put every input under its own top scope
+    #[default]
+    SideBySide,
+    /// append the inputs as consecutive time segments of the same design
+    BackToBack,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportKind {
+    /// one line per variable with path, type, direction, width, handle and alias
+    Csv,
+    /// the same as csv with one json object per line
+    JsonLines,
+    /// graphviz graph of the scopes with their components
+    Dot,
+    /// indented tree of scopes and variables
+    Tree,
+}
+
+#[derive(Debug, Args)]
+struct WriterArgs {
+    /// size in bytes of the value changes collected before a value change block is written
+    #[arg(long, default_value_t = WriterOptions::default().block_size)]
+    block_size: usize,
+    /// longest time a value change block covers
+    #[arg(long)]
+    block_time_span: Option<u64>,
+    /// compression of the hierarchy block
+    #[arg(long, value_enum, default_value_t)]
+    hierarchy_compression: ArgHierarchyCompression,
+    /// compression of the value changes
+    #[arg(long, value_enum, default_value_t)]
+    pack_type: ArgPackType,
+}
+
+impl WriterArgs {
+    fn options(&self) -> WriterOptions {
+        WriterOptions {
+            block_size: self.block_size,
+            block_time_span: self.block_time_span,
+            hierarchy_compression: self.hierarchy_compression.into(),
+            pack_type: self.pack_type.into(),
+            writer: "fst-file-cli".to_string(),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -123,11 +247,170 @@ enum Commands {
         #[arg(short, long)]
         intermediate: bool,
     },
+    /// Find variables by their path.
+    /// The pattern is a path like `top.cpu.pc` or a glob like `top.*.pc` or `top.**.valid`.
+    Find {
+        #[command(flatten)]
+        common: CommonArgs,
+        /// path, glob pattern or regular expression
+        pattern: String,
+        /// treat the pattern as a regular expression
+        #[arg(short, long)]
+        regex: bool,
+        /// separator between scope names
+        #[arg(short, long, default_value_t = '.')]
+        separator: char,
+    },
+    /// Print the value changes of the variables matching a path or glob.
+    /// Values of enum variables are shown with the names of their literals.
+    Values {
+        #[command(flatten)]
+        common: CommonArgs,
+        /// path or glob pattern of the variables
+        pattern: String,
+    },
+    /// Print the source file and line where a scope or variable was declared and instantiated
+    Where {
+        #[command(flatten)]
+        common: CommonArgs,
+        /// path of the scope or variable
+        path: String,
+        /// separator between scope names
+        #[arg(short, long, default_value_t = '.')]
+        separator: char,
+    },
+    /// Export the hierarchy as a signal list, a graph of the scopes or a tree
+    Export {
+        /// input fst file
+        input_file: PathBuf,
+        #[arg(value_enum)]
+        kind: ExportKind,
+        /// deepest level of the tree, the top level scopes are at level 1
+        #[arg(long)]
+        max_depth: Option<usize>,
+        /// path or glob of the scopes to show in the tree
+        #[arg(long)]
+        filter: Option<String>,
+        #[command(flatten)]
+        parse: ParseArgs,
+    },
+    /// Check that the blocks of a FST file agree with each other.
+    /// Exits with 1 when a check fails and with 2 when the file can not be read.
+    Check {
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+    /// Compare the scopes and variables of two FST files.
+    /// Exits with 1 when they differ.
+    HierarchyDiff {
+        /// fst file before the change
+        old_file: PathBuf,
+        /// fst file after the change
+        new_file: PathBuf,
+        /// output format
+        #[arg(short, long, value_enum, default_value_t)]
+        format: OutputFormat,
+        #[command(flatten)]
+        parse: ParseArgs,
+    },
+    /// Convert a VCD file to FST
+    FromVcd {
+        /// input vcd file
+        input_file: PathBuf,
+        /// output fst file
+        output_file: PathBuf,
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
+    /// Write a new FST file that only holds the selected signals
+    Extract {
+        /// input fst file
+        input_file: PathBuf,
+        /// output fst file
+        output_file: PathBuf,
+        /// hierarchy paths or glob patterns of the signals to keep.
+        /// `*` matches inside a scope name and `**` across scopes.
+        patterns: Vec<String>,
+        /// file with one path or pattern per line
+        #[arg(short, long)]
+        patterns_file: Option<PathBuf>,
+        #[command(flatten)]
+        parse: ParseArgs,
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
+    /// Write a new FST file that only covers a time window
+    Crop {
+        /// input fst file
+        input_file: PathBuf,
+        /// output fst file
+        output_file: PathBuf,
+        /// start of the window
+        #[arg(long, default_value_t = 0)]
+        from: u64,
+        /// end of the window
+        #[arg(long, default_value_t = u64::MAX)]
+        to: u64,
+        #[command(flatten)]
+        parse: ParseArgs,
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
+    /// Write a copy of a FST file with different compression settings
+    Repack {
+        /// input fst file
+        input_file: PathBuf,
+        /// output fst file
+        output_file: PathBuf,
+        /// compress the whole output file with gzip
+        #[arg(long)]
+        gzip_wrapper: bool,
+        #[command(flatten)]
+        parse: ParseArgs,
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
+    /// Write a copy of a FST file with value change blocks of another size or time span
+    Rechunk {
+        /// input fst file
+        input_file: PathBuf,
+        /// output fst file
+        output_file: PathBuf,
+        /// size in bytes of the uncompressed value changes in each block
+        #[arg(long, default_value_t = WriterOptions::default().block_size)]
+        block_size: usize,
+        /// longest time a value change block covers
+        #[arg(long)]
+        block_time_span: Option<u64>,
+        /// compression of the value changes, defaults to the one of the input
+        #[arg(long, value_enum)]
+        pack_type: Option<ArgPackType>,
+        #[command(flatten)]
+        parse: ParseArgs,
+    },
+    /// Combine several FST files into one
+    Merge {
+        /// input fst files
+        #[arg(required = true)]
+        input_files: Vec<PathBuf>,
+        /// output fst file
+        #[arg(short, long = "output")]
+        output_file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t)]
+        mode: MergeMode,
+        /// names of the top scopes for side by side merges, defaults to the file names
+        #[arg(short, long = "name")]
+        names: Vec<String>,
+        #[command(flatten)]
+        parse: ParseArgs,
+        #[command(flatten)]
+        writer: WriterArgs,
+    },
 }
 
 impl CliArgs {
-    fn get_common(&self) -> &CommonArgs {
-        match &self.command {
+    fn get_common(&self) -> Option<&CommonArgs> {
+        Some(match &self.command {
             Commands::List { common, .. } => common,
             Commands::Show { common, .. } => common,
             Commands::DumpData { common, .. } => common,
@@ -138,7 +421,33 @@ impl CliArgs {
             Commands::Geometry { common, .. } => common,
             Commands::Blackout { common, .. } => common,
             Commands::Vcd { common, .. } => common,
-        }
+            Commands::Find { common, .. } => common,
+            Commands::Values { common, .. } => common,
+            Commands::Where { common, .. } => common,
+            Commands::Check { common } => common,
+            Commands::Export { .. }
+            | Commands::HierarchyDiff { .. }
+            | Commands::FromVcd { .. }
+            | Commands::Extract { .. }
+            | Commands::Crop { .. }
+            | Commands::Merge { .. }
+            | Commands::Repack { .. }
+            | Commands::Rechunk { .. } => return None,
+        })
+    }
+
+    fn get_parse(&self) -> Option<&ParseArgs> {
+        Some(match &self.command {
+            Commands::Export { parse, .. } => parse,
+            Commands::HierarchyDiff { parse, .. } => parse,
+            Commands::Extract { parse, .. } => parse,
+            Commands::Crop { parse, .. } => parse,
+            Commands::Repack { parse, .. } => parse,
+            Commands::Rechunk { parse, .. } => parse,
+            Commands::Merge { parse, .. } => parse,
+            Commands::FromVcd { .. } => return None,
+            _ => &self.get_common()?.parse,
+        })
     }
 }
 
@@ -153,6 +462,14 @@ enum OutputFormat {
     PrettyJson,
 }
 
+/// A value change printed by the values command
+#[derive(Debug, Serialize)]
+struct ValueChange {
+    time: u64,
+    path: String,
+    value: String,
+}
+
 static IS_TERMINAL: OnceLock<bool> = OnceLock::new();
 
 fn is_terminal() -> &'static bool {
@@ -186,18 +503,30 @@ fn main() -> color_eyre::Result<()> {
     trace!("start of cli");
     debug!("cli arguments {args:?}");
 
-    let common = args.get_common();
+    run(args).inspect_err(|report| {
+        let color = std::io::stderr().is_terminal();
+        for error in report.chain() {
+            if let Some(error) = error.downcast_ref::<PositionError<VerboseErrorKind>>() {
+                eprint!("{}", diagnostic::render(error, color));
+            }
+        }
+    })
+}
 
-    let mut file = File::open(&common.input_file)?;
+fn run(args: CliArgs) -> color_eyre::Result<()> {
     let mut contents = Vec::new();
-    file.read_to_end(&mut contents)?;
+    if let Some(common) = args.get_common() {
+        let mut file = File::open(&common.input_file)?;
+        file.read_to_end(&mut contents)?;
+    }
+    let options = args.get_parse().map(ParseArgs::options).unwrap_or_default();
 
     match args.command {
         Commands::List {
             common: CommonArgs { format, .. },
             ..
         } => {
-            let blocks = fst_file::parse_raw_block_information(&contents).unwrap();
+            let blocks = fst_file::parse_raw_block_information(&contents)?;
             match format {
                 OutputFormat::PlainText => {
                     for (idx, block) in blocks.iter().enumerate() {
@@ -255,9 +584,10 @@ fn main() -> color_eyre::Result<()> {
             output_file: output,
             ..
         } => {
-            let blocks = fst_file::parse_raw_block_information(&contents).unwrap();
+            let blocks = fst_file::parse_raw_block_information(&contents)?;
             let mut output_file = OpenOptions::new()
                 .create(true)
+                .truncate(true)
                 .write(true)
                 .open(output)
                 .unwrap();
@@ -268,14 +598,14 @@ fn main() -> color_eyre::Result<()> {
         }
         Commands::DumpAll { .. } => todo!(),
         Commands::Stats { .. } => {
-            let blocks = fst_file::parse_raw_block_information(&contents).unwrap();
+            let blocks = fst_file::parse_raw_block_information(&contents)?;
             let mut data = HashMap::new();
             for block in blocks.iter() {
                 let entry = data.entry(block.get_block().block_type).or_insert(0);
                 *entry += 1;
             }
             let mut v: Vec<_> = data.into_iter().collect();
-            v.sort_by(|(k1, _v1), (k2, _v2)| k1.cmp(k2));
+            v.sort_by_key(|(k, _v)| *k);
             let width = v.iter().map(|(k, _v)| k.to_string().len()).max().unwrap();
             let width = width.max(10);
             println!("{type_text:>width$} count", type_text = "block type",);
@@ -293,8 +623,10 @@ fn main() -> color_eyre::Result<()> {
             common: CommonArgs { format, .. },
             ..
         } => {
-            let blocks = fst_file::parse(&contents).unwrap();
-            let header_block = blocks.header.unwrap();
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let header_block = blocks
+                .header
+                .ok_or_else(|| eyre!("the file has no header block"))?;
             match header_block.get_content() {
                 Ok(content) => match format {
                     OutputFormat::PlainText => println!("{:#?}", content),
@@ -306,9 +638,7 @@ fn main() -> color_eyre::Result<()> {
                         println!("{}", serde_json::to_string_pretty(&content).unwrap())
                     }
                 },
-                Err(e) => {
-                    error!("Error while parsing header content {:?}", e)
-                }
+                Err(e) => return Err(e.into()),
             }
         }
         Commands::Hierarchy {
@@ -316,7 +646,7 @@ fn main() -> color_eyre::Result<()> {
             show_tokens,
             ..
         } => {
-            let blocks = fst_file::parse(&contents).unwrap();
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
             if let Some(hierarchy_block) = blocks.hierarchy {
                 if show_tokens {
                     match hierarchy_block.get_tokens() {
@@ -345,7 +675,7 @@ fn main() -> color_eyre::Result<()> {
                                 }
                             }
                         }
-                        Err(e) => error!("Error while parsing header content {:?}", e),
+                        Err(e) => return Err(e.into()),
                     }
                 } else {
                     match hierarchy_block.get_content() {
@@ -366,7 +696,7 @@ fn main() -> color_eyre::Result<()> {
                                 }
                             }
                         }
-                        Err(e) => error!("Error while parsing header content {:?}", e),
+                        Err(e) => return Err(e.into()),
                     }
                 }
             } else {
@@ -374,28 +704,31 @@ fn main() -> color_eyre::Result<()> {
             }
         }
         Commands::Geometry { .. } => {
-            let blocks = fst_file::parse(&contents).unwrap();
-            let Some(hierarchy_block) = blocks.geometry else {
-                panic!("Geometry block did not exist in file!");
-            };
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let hierarchy_block = blocks
+                .geometry
+                .ok_or_else(|| eyre!("the file has no geometry block"))?;
             match hierarchy_block.get_content() {
                 Ok(geom) => println!("{:?}", geom),
-                Err(e) => error!("Error while parsing header content {:?}", e),
+                Err(e) => return Err(e.into()),
             }
         }
         Commands::Blackout { .. } => {
-            let blocks = fst_file::parse(&contents).unwrap();
-            let Some(blackout_block) = blocks.blackout else {
-                panic!("Geometry block did not exist in file!");
-            };
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let blackout_block = blocks
+                .blackout
+                .ok_or_else(|| eyre!("the file has no blackout block"))?;
             match blackout_block.get_content() {
                 Ok(content) => println!("{:?}", content),
-                Err(e) => error!("Error while parsing header content {:?}", e),
+                Err(e) => return Err(e.into()),
             }
         }
         Commands::Vcd { intermediate, .. } => {
-            let blocks = fst_file::parse(&contents).unwrap();
-            let header_content = blocks.header.unwrap().get_content().unwrap();
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let header_content = blocks
+                .header
+                .ok_or_else(|| eyre!("the file has no header block"))?
+                .get_content()?;
             for vcd_block in blocks.value_change_data.iter() {
                 if intermediate {
                     match vcd_block.get_intermediate_content(&header_content) {
@@ -410,6 +743,396 @@ fn main() -> color_eyre::Result<()> {
                 }
             }
         }
+        Commands::Find {
+            common: CommonArgs { format, .. },
+            pattern,
+            regex,
+            separator,
+        } => {
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let hierarchy = blocks
+                .hierarchy
+                .ok_or_else(|| eyre!("the file has no hierarchy block"))?
+                .get_content()?;
+            let index = PathIndex::with_separator(&hierarchy, separator);
+            let found = if regex {
+                index.regex(&Regex::new(&pattern)?)
+            } else {
+                index.find(&pattern)
+            };
+            match format {
+                OutputFormat::PlainText => {
+                    for variable in found {
+                        let vcd = variable.variable.vcd();
+                        // the type in the source language, like a VHDL std_logic_vector
+                        let type_name = variable
+                            .variable
+                            .supplemental_type()
+                            .map(|supplemental| format!(" {}", supplemental.type_name))
+                            .unwrap_or_default();
+                        println!(
+                            "{bold}{}{reset} {:?} {:?} {:?} {}{type_name}",
+                            variable.path,
+                            variable.handle(),
+                            vcd.var_type(),
+                            vcd.direction(),
+                            vcd.length(),
+                            bold = termion::style::Bold.only_on_terminal(),
+                            reset = termion::style::Reset.only_on_terminal()
+                        );
+                    }
+                }
+                OutputFormat::Json => print!("{}", serde_json::to_string(&found)?),
+                OutputFormat::PrettyJson => println!("{}", serde_json::to_string_pretty(&found)?),
+            }
+        }
+        Commands::Values {
+            common: CommonArgs { format, .. },
+            pattern,
+        } => {
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let hierarchy = blocks
+                .hierarchy
+                .as_ref()
+                .ok_or_else(|| eyre!("the file has no hierarchy block"))?
+                .get_content()?;
+            let geometry = blocks
+                .geometry
+                .as_ref()
+                .ok_or_else(|| eyre!("the file has no geometry block"))?
+                .get_content()?;
+            let start_time = blocks
+                .header
+                .as_ref()
+                .ok_or_else(|| eyre!("the file has no header block"))?
+                .get_content()?
+                .start_time;
+            let index = PathIndex::new(&hierarchy);
+            let found = index.find(&pattern);
+            let mut changes = Vec::new();
+            for (block_index, block) in blocks.value_change_data.iter().enumerate() {
+                let value_changes = block.get_value_changes(&geometry, |handle| {
+                    found.iter().any(|variable| variable.handle() == handle)
+                })?;
+                for variable in &found {
+                    let Some(signal) = value_changes.get(variable.handle()) else {
+                        continue;
+                    };
+                    let initial = (block_index == 0 && !signal.initial.is_empty())
+                        .then_some((start_time, &signal.initial));
+                    for (time, value) in initial
+                        .into_iter()
+                        .chain(signal.changes.iter().map(|(time, value)| (*time, value)))
+                    {
+                        changes.push(ValueChange {
+                            time,
+                            path: variable.path.clone(),
+                            value: hierarchy
+                                .display_value(variable.variable, value)
+                                .into_owned(),
+                        });
+                    }
+                }
+            }
+            changes.sort_by_key(|change| change.time);
+            match format {
+                OutputFormat::PlainText => {
+                    for change in changes {
+                        println!(
+                            "{} {bold}{}{reset} {}",
+                            change.time,
+                            change.path,
+                            change.value,
+                            bold = termion::style::Bold.only_on_terminal(),
+                            reset = termion::style::Reset.only_on_terminal()
+                        );
+                    }
+                }
+                OutputFormat::Json => print!("{}", serde_json::to_string(&changes)?),
+                OutputFormat::PrettyJson => {
+                    println!("{}", serde_json::to_string_pretty(&changes)?)
+                }
+            }
+        }
+        Commands::Where {
+            common: CommonArgs { format, .. },
+            path,
+            separator,
+        } => {
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let hierarchy = blocks
+                .hierarchy
+                .ok_or_else(|| eyre!("the file has no hierarchy block"))?
+                .get_content()?;
+            let locations = SourceLocations::with_separator(&hierarchy, separator);
+            let found = locations.get(&path);
+            if found.is_empty() {
+                return Err(eyre!("no source location for {path}"));
+            }
+            match format {
+                OutputFormat::PlainText => {
+                    for location in found {
+                        let kind = if location.instantiation {
+                            " (instantiation)"
+                        } else {
+                            ""
+                        };
+                        println!("{}:{}{kind}", location.file, location.line);
+                    }
+                }
+                OutputFormat::Json => print!("{}", serde_json::to_string(found)?),
+                OutputFormat::PrettyJson => println!("{}", serde_json::to_string_pretty(found)?),
+            }
+        }
+        Commands::Export {
+            input_file,
+            kind,
+            max_depth,
+            filter,
+            ..
+        } => {
+            let contents = std::fs::read(input_file)?;
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let hierarchy = blocks
+                .hierarchy
+                .ok_or_else(|| eyre!("the file has no hierarchy block"))?
+                .get_content()?;
+            let mut output = BufWriter::new(std::io::stdout().lock());
+            match kind {
+                ExportKind::Csv => export::write_csv(&hierarchy, &mut output)?,
+                ExportKind::JsonLines => {
+                    for row in export::signal_rows(&hierarchy) {
+                        writeln!(output, "{}", serde_json::to_string(&row)?)?;
+                    }
+                }
+                ExportKind::Dot => export::write_dot(&hierarchy, &mut output)?,
+                ExportKind::Tree => {
+                    let options = TreeOptions {
+                        max_depth,
+                        filter,
+                        ..Default::default()
+                    };
+                    export::write_tree(&hierarchy, &options, &mut output)?
+                }
+            }
+            output.flush()?;
+        }
+        Commands::Check {
+            common: CommonArgs { format, .. },
+        } => {
+            let report = check_file_with_options(&contents, &options);
+            match format {
+                OutputFormat::PlainText => {
+                    for check in &report.checks {
+                        let status_color = match check.status {
+                            CheckStatus::Pass => color::Fg(color::Green).only_on_terminal(),
+                            CheckStatus::Fail => color::Fg(color::Red).only_on_terminal(),
+                            CheckStatus::Skip => color::Fg(color::Yellow).only_on_terminal(),
+                        };
+                        println!(
+                            "{status_color}{}{reset} {bold}{}{reset_style}: {}",
+                            check.status,
+                            check.name,
+                            check.message,
+                            reset = color::Fg(color::Reset).only_on_terminal(),
+                            bold = termion::style::Bold.only_on_terminal(),
+                            reset_style = termion::style::Reset.only_on_terminal()
+                        );
+                    }
+                    if report.passed() {
+                        println!("all checks passed");
+                    } else {
+                        println!("some checks failed");
+                    }
+                }
+                OutputFormat::Json => print!("{}", serde_json::to_string(&report)?),
+                OutputFormat::PrettyJson => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+            if !report.readable() {
+                std::process::exit(2);
+            } else if !report.passed() {
+                std::process::exit(1);
+            }
+        }
+        Commands::HierarchyDiff {
+            old_file,
+            new_file,
+            format,
+            ..
+        } => {
+            let mut hierarchies = Vec::new();
+            for file in [&old_file, &new_file] {
+                let contents = std::fs::read(file)?;
+                let blocks = fst_file::parse_with_options(&contents, &options)?;
+                let hierarchy = blocks
+                    .hierarchy
+                    .ok_or_else(|| eyre!("{} has no hierarchy block", file.display()))?
+                    .get_content()?;
+                hierarchies.push(hierarchy);
+            }
+            let changes = diff(&hierarchies[0], &hierarchies[1]);
+            match format {
+                OutputFormat::PlainText => {
+                    for change in &changes {
+                        println!("{change}");
+                    }
+                }
+                OutputFormat::Json => print!("{}", serde_json::to_string(&changes)?),
+                OutputFormat::PrettyJson => {
+                    println!("{}", serde_json::to_string_pretty(&changes)?)
+                }
+            }
+            if !changes.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Commands::FromVcd {
+            input_file,
+            output_file,
+            writer,
+        } => {
+            let input = BufReader::new(File::open(input_file)?);
+            let output = BufWriter::new(File::create(output_file)?);
+            fst_file::convert::vcd_to_fst(input, output, writer.options())?;
+        }
+        Commands::Extract {
+            input_file,
+            output_file,
+            mut patterns,
+            patterns_file,
+            writer,
+            ..
+        } => {
+            if let Some(patterns_file) = patterns_file {
+                let list = std::fs::read_to_string(patterns_file)?;
+                patterns.extend(
+                    list.lines()
+                        .map(str::trim)
+                        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                        .map(String::from),
+                );
+            }
+            let selector = SignalSelector::new(patterns);
+            if selector.is_empty() {
+                return Err(eyre!("no signal paths or patterns were given"));
+            }
+            let contents = std::fs::read(input_file)?;
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let output = BufWriter::new(File::create(output_file)?);
+            fst_file::transform::extract_signals(&blocks, &selector, output, writer.options())?;
+        }
+        Commands::Crop {
+            input_file,
+            output_file,
+            from,
+            to,
+            writer,
+            ..
+        } => {
+            let contents = std::fs::read(input_file)?;
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let output = BufWriter::new(File::create(output_file)?);
+            fst_file::transform::crop(&blocks, from, to, output, writer.options())?;
+        }
+        Commands::Repack {
+            input_file,
+            output_file,
+            gzip_wrapper,
+            writer,
+            ..
+        } => {
+            let contents = std::fs::read(&input_file)?;
+            let unwrapped = fst_file::transform::unwrap_gzip_wrapper(&contents)?;
+            let blocks = fst_file::parse_with_options(&unwrapped, &options)?;
+            let output = BufWriter::new(File::create(&output_file)?);
+            if gzip_wrapper {
+                let file = fst_file::transform::repack(
+                    &blocks,
+                    Cursor::new(Vec::new()),
+                    writer.options(),
+                )?
+                .into_inner();
+                fst_file::writer::write_gzip_wrapper(output, &file)?;
+            } else {
+                fst_file::transform::repack(&blocks, output, writer.options())?;
+            }
+            let before = contents.len() as u64;
+            let after = std::fs::metadata(&output_file)?.len();
+            println!("before: {before} bytes");
+            println!(
+                "after:  {after} bytes ({:.1}%)",
+                after as f64 / before as f64 * 100.0
+            );
+        }
+        Commands::Rechunk {
+            input_file,
+            output_file,
+            block_size,
+            block_time_span,
+            pack_type,
+            ..
+        } => {
+            let contents = std::fs::read(input_file)?;
+            let blocks = fst_file::parse_with_options(&contents, &options)?;
+            let pack_type = match pack_type {
+                Some(pack_type) => pack_type.into(),
+                None => fst_file::transform::pack_type(&blocks)?
+                    .unwrap_or(WriterOptions::default().pack_type),
+            };
+            let output = BufWriter::new(File::create(&output_file)?);
+            let writer_options = WriterOptions {
+                block_size,
+                block_time_span,
+                pack_type,
+                writer: "fst-file-cli".to_string(),
+                ..Default::default()
+            };
+            fst_file::transform::rechunk(&blocks, output, writer_options)?;
+            let rechunked = std::fs::read(&output_file)?;
+            let rechunked = fst_file::parse_with_options(&rechunked, &options)?;
+            println!(
+                "value change blocks: {} -> {}",
+                blocks.value_change_data.len(),
+                rechunked.value_change_data.len()
+            );
+        }
+        Commands::Merge {
+            output_file,
+            input_files,
+            mode,
+            mut names,
+            writer,
+            ..
+        } => {
+            let contents = input_files
+                .iter()
+                .map(std::fs::read)
+                .collect::<Result<Vec<_>, _>>()?;
+            let blocks = contents
+                .iter()
+                .map(|contents| fst_file::parse_with_options(contents, &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            let output = BufWriter::new(File::create(output_file)?);
+            match mode {
+                MergeMode::SideBySide => {
+                    if names.len() > input_files.len() {
+                        return Err(eyre!("more names than input files were given"));
+                    }
+                    for input_file in &input_files[names.len()..] {
+                        let name = input_file
+                            .file_stem()
+                            .ok_or_else(|| eyre!("{} has no file name", input_file.display()))?;
+                        names.push(name.to_string_lossy().into_owned());
+                    }
+                    let inputs: Vec<_> = names.iter().map(String::as_str).zip(&blocks).collect();
+                    fst_file::transform::merge_side_by_side(&inputs, output, writer.options())?;
+                }
+                MergeMode::BackToBack => {
+                    let inputs: Vec<_> = blocks.iter().collect();
+                    fst_file::transform::merge_back_to_back(&inputs, output, writer.options())?;
+                }
+            }
+        }
     }
     Ok(())
 }
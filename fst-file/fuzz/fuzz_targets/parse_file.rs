@@ -1,7 +1,68 @@
 #![no_main]
 
+use fst_file::{
+    block_parsers::hierarchy::{
+        diff::diff, source::SourceLocations, HierarchyContent, HierarchyVisitor, Scope, Variable,
+        VirtualBit,
+    },
+    error::FstResult,
+};
 use libfuzzer_sys::fuzz_target;
 
+/// Calls the helpers that interpret the names, lengths and attributes of the hierarchy
+struct Walk;
+
+impl HierarchyVisitor<'_> for Walk {
+    fn enter_scope(&mut self, scope: &Scope) {
+        for bus in scope.virtual_buses() {
+            let _ = bus.width();
+        }
+    }
+
+    fn variable(&mut self, variable: &Variable) {
+        let vcd = variable.vcd();
+        let _ = vcd.parsed_name();
+        let range = vcd.bit_range();
+        let _ = range.bit(range.width() - 1);
+        for bit in [range.msb, range.lsb, range.lsb.wrapping_sub(1)] {
+            let _ = VirtualBit::new(variable, bit);
+        }
+    }
+}
+
+fn walk(hierarchy: &HierarchyContent) {
+    hierarchy.visit(&mut Walk);
+    let _ = SourceLocations::new(hierarchy);
+    let _ = diff(hierarchy, hierarchy);
+}
+
+/// Run every block content decoder, errors are fine but panics are not
+fn decode(data: &[u8]) -> FstResult<()> {
+    let content = fst_file::parse(data)?;
+    let header = content.header.as_ref().map(|header| header.get_content());
+    if let Some(hierarchy) = &content.hierarchy {
+        let _ = hierarchy.get_tokens();
+        if let Ok(hierarchy) = hierarchy.get_content() {
+            walk(&hierarchy);
+        }
+    }
+    if let Some(blackout) = &content.blackout {
+        let _ = blackout.get_content();
+    }
+    let geometry = content.geometry.as_ref().map(|geometry| geometry.get_content());
+    for block in content.value_change_data.iter() {
+        let _ = block.get_time_range();
+        if let Some(Ok(header)) = &header {
+            let _ = block.get_intermediate_content(header);
+            let _ = block.get_content(header);
+        }
+        if let Some(Ok(geometry)) = &geometry {
+            let _ = block.get_value_changes(geometry, |_| true);
+        }
+    }
+    Ok(())
+}
+
 fuzz_target!(|data: &[u8]| {
-    let _ = fst_file::parse(data);
+    let _ = decode(data);
 });
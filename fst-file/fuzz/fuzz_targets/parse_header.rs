@@ -1,8 +1,11 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
-use fst_file::block_parsers::Block;
 
 fuzz_target!(|data: &[u8]| {
-    let _ = Block::parse_block_with_position(data);
+    if let Ok(content) = fst_file::parse(data) {
+        if let Some(header) = content.header {
+            let _ = header.get_content();
+        }
+    }
 });
@@ -1,9 +1,9 @@
 use nom::{
-    bytes::complete::take,
-    combinator::{eof, map, map_res},
+    combinator::{eof, map_res},
     error::VerboseErrorKind,
     multi::many_m_n,
-    Finish,
+    number::complete::be_u8,
+    Finish, Offset,
 };
 use serde::Serialize;
 use thiserror::Error;
@@ -11,6 +11,7 @@ use thiserror::Error;
 use crate::{
     data_types::VarInt,
     error::{BlockParseError, ParseResult, PositionError},
+    options::{within_limits, Allocation, Anomaly, AnomalyKind, LimitError, Limits},
     FstParsable,
 };
 
@@ -20,6 +21,10 @@ use super::Block;
 pub enum BlackoutParseError {
     #[error("parse error: {0}")]
     ParseError(#[from] PositionError<VerboseErrorKind>),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitError),
 }
 
 /// Blackout Block
@@ -33,10 +38,20 @@ impl BlackoutBlock {
 
     pub fn get_content(&self) -> Result<BlackoutContent, BlackoutParseError> {
         let data = self.0.get_data_raw();
-        Ok(BlackoutContent::parse(data)
+        let mut allocation = self.0.allocation();
+        let (content, anomalies) = BlackoutContent::parse_with_anomalies(data, &mut allocation)
             .finish()
             .map(|(_, v)| v)
-            .map_err(|e| PositionError::from_verbose_parse_error(e, data))?)
+            .map_err(|e| match allocation.exceeded() {
+                Some(limit) => BlackoutParseError::from(limit),
+                None => PositionError::from_verbose_parse_error(e, data)
+                    .in_block(self.0.location())
+                    .into(),
+            })?;
+        for anomaly in anomalies {
+            self.0.report(anomaly)?;
+        }
+        Ok(content)
     }
 }
 
@@ -53,24 +68,60 @@ pub struct BlackoutContent {
     records: Vec<BlackoutRecord>,
 }
 
-impl FstParsable for BlackoutRecord {
-    fn parse(input: &[u8]) -> ParseResult<BlackoutRecord> {
-        let (input, active) = map(take(1u8), |b: &[u8]| b[0] == 1)(input)?;
-        let (input, time_delta) = VarInt::parse(input)?;
-        Ok((input, BlackoutRecord { active, time_delta }))
+impl BlackoutContent {
+    /// Dump activity changes with their absolute time
+    pub fn dump_changes(&self) -> impl Iterator<Item = (bool, u64)> + '_ {
+        self.records.iter().scan(0u64, |time, record| {
+            *time = time.wrapping_add(record.time_delta.0);
+            Some((record.active, *time))
+        })
     }
 }
 
 impl FstParsable for BlackoutContent {
-    fn parse(input: &[u8]) -> ParseResult<BlackoutContent> {
+    fn parse(input: &[u8]) -> ParseResult<'_, BlackoutContent> {
+        let mut allocation = Allocation::new(Limits::default());
+        let (input, (content, _)) = BlackoutContent::parse_with_anomalies(input, &mut allocation)?;
+        Ok((input, content))
+    }
+}
+
+impl BlackoutContent {
+    /// The content and the activity bytes that are neither 0 nor 1, those count as inactive
+    fn parse_with_anomalies<'a>(
+        input: &'a [u8],
+        allocation: &mut Allocation,
+    ) -> ParseResult<'a, (Self, Vec<AnomalyKind>)> {
+        let data = input;
         let (input, count) = map_res(VarInt::parse, |v| {
             usize::try_from(v).map_err(|_e| (input, BlockParseError::LengthTooLargeForMachine))
         })(input)?;
-        let (input, records) = many_m_n(count, count, BlackoutRecord::parse)(input)?;
+        within_limits(
+            input,
+            allocation.elements::<BlackoutRecord>("blackout records", count),
+        )?;
+        let mut anomalies = Vec::new();
+        let (input, records) = many_m_n(count, count, |input| {
+            let offset = data.offset(input);
+            let (input, activity) = be_u8(input)?;
+            if activity > 1 {
+                anomalies.push(AnomalyKind::UnknownCode {
+                    what: "blackout activity",
+                    code: activity,
+                    offset,
+                });
+            }
+            let (input, time_delta) = VarInt::parse(input)?;
+            let record = BlackoutRecord {
+                active: activity == 1,
+                time_delta,
+            };
+            Ok((input, record))
+        })(input)?;
 
         let data = BlackoutContent { records };
 
         let (_input, _) = eof(input)?;
-        Ok((input, data))
+        Ok((input, (data, anomalies)))
     }
 }
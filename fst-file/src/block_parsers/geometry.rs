@@ -1,8 +1,8 @@
-use std::{borrow::Cow, io::Read};
+use std::borrow::Cow;
 
 use nom::{
-    bytes::complete::take,
-    error::{context, VerboseErrorKind},
+    combinator::rest,
+    error::{context, ErrorKind, ParseError, VerboseError, VerboseErrorKind},
     multi::many_m_n,
     number::complete::be_u64,
     Finish,
@@ -13,12 +13,15 @@ use tracing::debug;
 
 use crate::{
     as_usize,
-    data_types::VarInt,
+    data_types::{Handle, VarInt},
     error::{ParseResult, PositionError},
+    options::{
+        decompress_failed, within_limits, Allocation, Anomaly, AnomalyKind, LimitError, Limits,
+    },
     FstParsable,
 };
 
-use super::Block;
+use super::{Block, SizeMismatch};
 
 #[derive(Debug)]
 pub struct GeometryBlock(Block);
@@ -26,10 +29,75 @@ pub struct GeometryBlock(Block);
 #[derive(Debug, Serialize)]
 pub struct Geometry(Vec<VarInt>);
 
+/// How the value of a signal is stored, as described by the geometry block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SignalGeometry {
+    /// Vector of `n` bits, stored as one character per bit
+    Bits(u32),
+    /// 64 bit floating point value
+    Real,
+    /// Value with a length that can change every time (e.g. strings)
+    VariableLength,
+}
+
+impl SignalGeometry {
+    pub fn from_raw(value: u64) -> Self {
+        match value {
+            0 => SignalGeometry::Real,
+            0xFFFF_FFFF => SignalGeometry::VariableLength,
+            n => SignalGeometry::Bits(n as u32),
+        }
+    }
+
+    pub fn to_raw(self) -> u64 {
+        match self {
+            SignalGeometry::Bits(n) => n as u64,
+            SignalGeometry::Real => 0,
+            SignalGeometry::VariableLength => 0xFFFF_FFFF,
+        }
+    }
+
+    /// Number of bytes the value takes in the initial values of a value change data block
+    pub fn frame_size(self) -> usize {
+        match self {
+            SignalGeometry::Bits(n) => n as usize,
+            SignalGeometry::Real => 8,
+            SignalGeometry::VariableLength => 0,
+        }
+    }
+}
+
+impl Geometry {
+    /// Number of signals (handles)
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Geometry of the signal with `handle`
+    pub fn get(&self, handle: Handle) -> Option<SignalGeometry> {
+        self.0
+            .get(handle.index()?)
+            .map(|v| SignalGeometry::from_raw(v.0))
+    }
+
+    /// Geometry of every signal in handle order
+    pub fn iter(&self) -> impl Iterator<Item = SignalGeometry> + '_ {
+        self.0.iter().map(|v| SignalGeometry::from_raw(v.0))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum GeometryParseError {
     #[error("parse error {0}")]
     ParseError(#[from] PositionError<VerboseErrorKind>),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitError),
 }
 
 impl GeometryBlock {
@@ -37,39 +105,89 @@ impl GeometryBlock {
         Self(block)
     }
 
+    pub fn get_block(&self) -> &Block {
+        &self.0
+    }
+
+    /// The geometry, a signal list that does not have the declared size is reported
+    /// as an anomaly, see [crate::options::ParseOptions]
     pub fn get_content(&self) -> Result<Geometry, GeometryParseError> {
+        let (geometry, mismatches) = self.get_content_with_sizes()?;
+        for mismatch in mismatches {
+            self.0.report(AnomalyKind::SizeMismatch(mismatch))?;
+        }
+        Ok(geometry)
+    }
+
+    /// The geometry and the size of the uncompressed signal list when it does not match
+    /// the declared size, it is returned instead of reported
+    pub fn get_content_with_sizes(
+        &self,
+    ) -> Result<(Geometry, Vec<SizeMismatch>), GeometryParseError> {
         let data = self.0.get_data_raw();
-        Ok(Geometry::parse(data)
+        let mut allocation = self.0.allocation();
+        Geometry::parse_with_sizes(data, &mut allocation)
             .finish()
             .map(|(_, v)| v)
-            .map_err(|e| PositionError::from_verbose_parse_error(e, data))?)
+            .map_err(|e| match allocation.exceeded() {
+                Some(limit) => limit.into(),
+                None => PositionError::from_verbose_parse_error(e, data)
+                    .in_block(self.0.location())
+                    .into(),
+            })
     }
 }
 
 impl FstParsable for Geometry {
-    fn parse(input: &[u8]) -> ParseResult<Self> {
-        let original_input = input;
+    fn parse(input: &[u8]) -> ParseResult<'_, Self> {
+        let mut allocation = Allocation::new(Limits::default());
+        let (input, (geometry, _)) = Geometry::parse_with_sizes(input, &mut allocation)?;
+        Ok((input, geometry))
+    }
+}
+
+impl Geometry {
+    fn parse_with_sizes<'a>(
+        input: &'a [u8],
+        allocation: &mut Allocation,
+    ) -> ParseResult<'a, (Self, Vec<SizeMismatch>)> {
         let (input, uncompressed_length) = as_usize(be_u64)(input)?;
         let (input, count) = as_usize(be_u64)(input)?;
-        let (input, data_raw) = take(original_input.len() - 16)(input)?;
+        let (input, data_raw) = rest(input)?;
 
-        let data = if original_input.len() - 16 == uncompressed_length {
+        let data = if data_raw.len() == uncompressed_length {
             debug!("geometry is not compressed");
             Cow::Borrowed(data_raw)
         } else {
             debug!("geometry is compressed");
-            let mut decompressor = flate2::read::ZlibDecoder::new(data_raw);
-            let mut data_tmp = Vec::new();
-            decompressor.read_to_end(&mut data_tmp).unwrap();
+            let decompressor = flate2::read::ZlibDecoder::new(data_raw);
+            let data_tmp = allocation
+                .read_to_end("signal list", decompressor)
+                .map_err(|e| decompress_failed(data_raw, e))?;
             Cow::Owned(data_tmp)
         };
+        let mut mismatches = Vec::new();
+        SizeMismatch::compare(
+            "signal list",
+            uncompressed_length,
+            data.len(),
+            &mut mismatches,
+        );
 
+        // every entry takes at least one byte
+        if count > data.len() {
+            return Err(nom::Err::Error(VerboseError::from_error_kind(
+                data_raw,
+                ErrorKind::Count,
+            )));
+        }
+        within_limits(data_raw, allocation.elements::<VarInt>("signals", count))?;
         let (_, g) = context("inner data", |input| {
             many_m_n(count, count, VarInt::parse)(input)
         })(&data)
-        .expect("something went wrong while parsing geometry data");
+        .map_err(|_| nom::Err::Error(VerboseError::from_error_kind(data_raw, ErrorKind::Count)))?;
 
         let geometry = Geometry(g);
-        Ok((input, geometry))
+        Ok((input, (geometry, mismatches)))
     }
 }
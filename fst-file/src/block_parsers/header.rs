@@ -1,4 +1,4 @@
-use std::ffi::CStr;
+use std::ops::Range;
 
 use nom::{
     bytes::complete::take,
@@ -16,6 +16,7 @@ use crate::{
     as_usize,
     data_types::{FileType, TimeScale},
     error::{ParseResult, PositionError},
+    options::{Anomaly, AnomalyKind},
     FstParsable,
 };
 
@@ -42,8 +43,16 @@ pub struct HeaderBlockContent {
 pub enum HeaderParseError {
     #[error("parse error: {0}")]
     ParseError(#[from] PositionError<VerboseErrorKind>),
+    #[error("endianness test value {0} is not e, the file is not a little endian FST file")]
+    WrongEndianness(f64),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
 }
 
+/// Where the null terminated strings are in the header data
+const WRITER: Range<usize> = 65..193;
+const DATE: Range<usize> = 193..219;
+
 #[derive(Debug, Clone)]
 pub struct HeaderBlock(Block);
 
@@ -55,15 +64,32 @@ impl HeaderBlock {
     pub fn get_content(&self) -> Result<HeaderBlockContent, HeaderParseError> {
         let _span = debug_span!("get header content").entered();
         let data = self.0.get_data_raw();
-        Ok(HeaderBlockContent::parse(data)
+        let content = HeaderBlockContent::parse(data)
             .finish()
             .map(|(_, content)| content)
-            .map_err(|e| PositionError::from_verbose_parse_error(e, data))?)
+            .map_err(|e| {
+                PositionError::from_verbose_parse_error(e, data).in_block(self.0.location())
+            })?;
+        let difference = (content.real_endianness - std::f64::consts::E).abs();
+        if difference.is_nan() || difference >= f64::EPSILON {
+            return Err(HeaderParseError::WrongEndianness(content.real_endianness));
+        }
+        for (what, range) in [("writer", WRITER), ("date", DATE)] {
+            let text = &data[range.clone()];
+            let end = text.iter().position(|b| *b == 0).unwrap_or(text.len());
+            if let Err(e) = std::str::from_utf8(&text[..end]) {
+                self.0.report(AnomalyKind::InvalidText {
+                    what,
+                    offset: range.start + e.valid_up_to(),
+                })?;
+            }
+        }
+        Ok(content)
     }
 }
 
 impl FstParsable for HeaderBlockContent {
-    fn parse(input: &[u8]) -> ParseResult<HeaderBlockContent> {
+    fn parse(input: &[u8]) -> ParseResult<'_, HeaderBlockContent> {
         let (
             input,
             (
@@ -92,13 +118,12 @@ impl FstParsable for HeaderBlockContent {
             as_usize(be_u64),
             be_u64,
             TimeScale::parse,
-            c_str_with_size(128),
-            c_str_with_size(26),
+            c_str_with_size(WRITER.len()),
+            c_str_with_size(DATE.len()),
             take(93u8),
             FileType::parse,
             be_i64,
         ))(input)?;
-        assert!((real_endianness - std::f64::consts::E).abs() < std::f64::EPSILON);
         let data = HeaderBlockContent {
             start_time,
             end_time,
@@ -122,12 +147,7 @@ impl FstParsable for HeaderBlockContent {
 fn c_str_with_size<'a>(size: usize) -> impl Fn(&'a [u8]) -> ParseResult<'a, String> {
     move |input| {
         let (input, data) = take(size)(input)?;
-        let mut v = data.to_vec();
-        v.push(0);
-        let s = CStr::from_bytes_until_nul(&v)
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
-        Ok((input, s))
+        let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+        Ok((input, String::from_utf8_lossy(&data[..end]).into_owned()))
     }
 }
@@ -1,24 +1,19 @@
-use enum_primitive_derive::Primitive;
-use nom::{combinator::map_res, number::complete::be_u8};
-use num_traits::FromPrimitive;
-use serde::Serialize;
+use nom::{combinator::map, number::complete::be_u8};
 
-use crate::{block_parsers::hierarchy::HierarchyParseErrorKind, error::ParseResult, FstParsable};
+use crate::{error::ParseResult, FstParsable};
 
-/// Types of attributes in [crate::block_parsers::hierarchy]
-#[derive(Debug, Clone, PartialEq, Primitive, Serialize)]
-#[repr(u8)]
-pub enum AttributeType {
-    Misc = 0,
-    Array = 1,
-    Enum = 2,
-    Pack = 3,
+code_enum! {
+    /// Types of attributes in [crate::block_parsers::hierarchy]
+    pub enum AttributeType {
+        Misc = 0,
+        Array = 1,
+        Enum = 2,
+        Pack = 3,
+    }
 }
 
 impl FstParsable for AttributeType {
-    fn parse(input: &[u8]) -> ParseResult<Self> {
-        map_res(be_u8, |v| {
-            AttributeType::from_u8(v).ok_or((input, HierarchyParseErrorKind::WrongAttributeType(v)))
-        })(input)
+    fn parse(input: &[u8]) -> ParseResult<'_, Self> {
+        map(be_u8, AttributeType::from_u8)(input)
     }
 }
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use crate::block_parsers::value_change_data::{SignalValueChanges, ValueChanges};
+
+use super::{BitRange, Scope, VarType, Variable};
+
+/// A vector made of the 1 bit variables `a[0]`, `a[1]`, ... of a scope, see [Scope::virtual_buses]
+#[derive(Debug, Clone)]
+pub struct VirtualBus<'a> {
+    /// Name of the vector like `a[31:0]`
+    pub name: String,
+    pub range: BitRange,
+    /// The variable of every bit, from the most significant one
+    pub members: Vec<&'a Variable>,
+}
+
+/// One bit of a vector variable, see [VirtualBit::new]
+#[derive(Debug, Clone)]
+pub struct VirtualBit<'a> {
+    /// Name of the bit like `a[3]`
+    pub name: String,
+    pub variable: &'a Variable,
+    pub bit: i64,
+    /// position of the bit in the values of the variable
+    position: usize,
+}
+
+type Bits<'a> = Vec<(i64, &'a Variable)>;
+
+impl Scope {
+    /// Families of 1 bit variables that only differ in their last index, like a netlist writes the bits of a vector.
+    ///
+    /// The bits of a family have to be consecutive without gaps and duplicates.
+    pub fn virtual_buses(&self) -> Vec<VirtualBus<'_>> {
+        // bits with their variable by the base name and the other indices
+        let mut families: BTreeMap<(&str, Vec<i64>), Bits> = BTreeMap::new();
+        for variable in self.variables() {
+            let vcd = variable.vcd();
+            if vcd.length() != 1 || !is_bits(vcd.var_type()) {
+                continue;
+            }
+            let mut name = vcd.parsed_name();
+            let bit = match (name.range, name.indices.pop()) {
+                (None, Some(index)) => index,
+                (Some(range), None) if range.msb == range.lsb => range.msb,
+                _ => continue,
+            };
+            families
+                .entry((name.base, name.indices))
+                .or_default()
+                .push((bit, variable));
+        }
+
+        let mut buses = Vec::new();
+        for ((base, indices), mut bits) in families {
+            if bits.len() < 2 {
+                continue;
+            }
+            bits.sort_by(|(a, _), (b, _)| b.cmp(a));
+            let range = BitRange::new(bits[0].0, bits[bits.len() - 1].0);
+            if range.width() != bits.len() as u64
+                || bits.windows(2).any(|pair| pair[0].0 == pair[1].0)
+            {
+                continue;
+            }
+            let indices: String = indices.iter().map(|index| format!("[{index}]")).collect();
+            buses.push(VirtualBus {
+                name: format!("{base}{indices}[{}:{}]", range.msb, range.lsb),
+                range,
+                members: bits.into_iter().map(|(_, variable)| variable).collect(),
+            });
+        }
+        buses
+    }
+}
+
+impl VirtualBus<'_> {
+    pub fn width(&self) -> u64 {
+        self.range.width()
+    }
+
+    /// Merge the value changes of the members in `value_changes` into the values of the bus,
+    /// one change for every time at which the bus changes.
+    ///
+    /// [None] when a member was not selected when decoding the block.
+    pub fn value_changes(&self, value_changes: &ValueChanges) -> Option<SignalValueChanges> {
+        let members = self
+            .members
+            .iter()
+            .map(|member| value_changes.get(member.handle()))
+            .collect::<Option<Vec<_>>>()?;
+        let mut value: Vec<u8> = members
+            .iter()
+            .map(|member| member.initial.first().copied().unwrap_or(b'x'))
+            .collect();
+        let initial = value.clone();
+
+        let mut events: Vec<(u64, usize, u8)> = members
+            .iter()
+            .enumerate()
+            .flat_map(|(position, member)| {
+                member
+                    .changes
+                    .iter()
+                    .map(move |(time, bit)| (*time, position, bit.first().copied().unwrap_or(b'x')))
+            })
+            .collect();
+        // stable, so changes of a member at the same time stay in order
+        events.sort_by_key(|(time, _, _)| *time);
+
+        let mut changes: Vec<(u64, Vec<u8>)> = Vec::new();
+        for (time, position, bit) in events {
+            value[position] = bit;
+            match changes.last_mut() {
+                Some((last_time, last_value)) if *last_time == time => {
+                    last_value.clone_from(&value)
+                }
+                _ => changes.push((time, value.clone())),
+            }
+        }
+        // members that change to the value they had do not change the bus
+        let mut previous = initial.clone();
+        changes.retain(|(_, value)| {
+            let changed = *value != previous;
+            previous.clone_from(value);
+            changed
+        });
+        Some(SignalValueChanges { initial, changes })
+    }
+}
+
+impl<'a> VirtualBit<'a> {
+    /// Bit `bit` of `variable`, numbered by the range in its name or `[length-1:0]`.
+    ///
+    /// [None] when the variable has no such bit or is not a bit vector.
+    pub fn new(variable: &'a Variable, bit: i64) -> Option<Self> {
+        let vcd = variable.vcd();
+        if !is_bits(vcd.var_type()) {
+            return None;
+        }
+        let position = vcd
+            .bit_range()
+            .position(bit)
+            .filter(|&position| position < vcd.length())?;
+        let position = usize::try_from(position).ok()?;
+        let name = vcd.parsed_name();
+        let indices: String = name
+            .indices
+            .iter()
+            .map(|index| format!("[{index}]"))
+            .collect();
+        Some(Self {
+            name: format!("{}{indices}[{bit}]", name.base),
+            variable,
+            bit,
+            position,
+        })
+    }
+
+    /// The values of the bit in `value_changes`, only with the changes that change the bit.
+    ///
+    /// [None] when the variable was not selected when decoding the block.
+    pub fn value_changes(&self, value_changes: &ValueChanges) -> Option<SignalValueChanges> {
+        let signal = value_changes.get(self.variable.handle())?;
+        let bit_of = |value: &[u8]| value.get(self.position).copied().unwrap_or(b'x');
+        let mut last = bit_of(&signal.initial);
+        let initial = vec![last];
+        let mut changes = Vec::new();
+        for (time, value) in &signal.changes {
+            let bit = bit_of(value);
+            if bit != last {
+                changes.push((*time, vec![bit]));
+                last = bit;
+            }
+        }
+        Some(SignalValueChanges { initial, changes })
+    }
+}
+
+/// Whether values of the type are one character per bit
+fn is_bits(var_type: VarType) -> bool {
+    !matches!(
+        var_type,
+        VarType::VcdReal
+            | VarType::VcdRealParameter
+            | VarType::VcdRealtime
+            | VarType::SvShortReal
+            | VarType::GenString
+    )
+}
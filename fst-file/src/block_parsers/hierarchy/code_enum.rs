@@ -0,0 +1,37 @@
+/// Declare an enum of the one byte codes of a hierarchy field.
+///
+/// Codes it does not know, like those of newer writers, are kept as `Unknown(code)`
+/// so the rest of the record can still be parsed.
+macro_rules! code_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($variant:ident = $code:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, ::serde::Serialize)]
+        pub enum $name {
+            $($variant,)*
+            /// A code this version does not know
+            Unknown(u8),
+        }
+
+        impl $name {
+            pub fn from_u8(code: u8) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    code => Self::Unknown(code),
+                }
+            }
+
+            /// The code in the file
+            pub fn to_u8(self) -> u8 {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Unknown(code) => code,
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::Serialize;
+
+use super::{HierarchyContent, HierarchyNode, Scope, ScopeType, VarDir, VarType, Variable};
+
+/// A difference between two hierarchies, see [diff]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum HierarchyChange {
+    AddedScope {
+        path: String,
+    },
+    RemovedScope {
+        path: String,
+    },
+    AddedVariable {
+        path: String,
+    },
+    RemovedVariable {
+        path: String,
+    },
+    ScopeType {
+        path: String,
+        old: ScopeType,
+        new: ScopeType,
+    },
+    /// The component (module or entity name) of an instance changed
+    Component {
+        path: String,
+        old: String,
+        new: String,
+    },
+    VarType {
+        path: String,
+        old: VarType,
+        new: VarType,
+    },
+    Direction {
+        path: String,
+        old: VarDir,
+        new: VarDir,
+    },
+    /// The number of bits changed, `path` is the new path that can have another bit range
+    Width {
+        path: String,
+        old: u64,
+        new: u64,
+    },
+}
+
+impl HierarchyChange {
+    pub fn path(&self) -> &str {
+        match self {
+            HierarchyChange::AddedScope { path }
+            | HierarchyChange::RemovedScope { path }
+            | HierarchyChange::AddedVariable { path }
+            | HierarchyChange::RemovedVariable { path }
+            | HierarchyChange::ScopeType { path, .. }
+            | HierarchyChange::Component { path, .. }
+            | HierarchyChange::VarType { path, .. }
+            | HierarchyChange::Direction { path, .. }
+            | HierarchyChange::Width { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for HierarchyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyChange::AddedScope { path } => write!(f, "+ scope {path}"),
+            HierarchyChange::RemovedScope { path } => write!(f, "- scope {path}"),
+            HierarchyChange::AddedVariable { path } => write!(f, "+ {path}"),
+            HierarchyChange::RemovedVariable { path } => write!(f, "- {path}"),
+            HierarchyChange::ScopeType { path, old, new } => {
+                write!(f, "~ scope {path} type {old:?} -> {new:?}")
+            }
+            HierarchyChange::Component { path, old, new } => {
+                write!(f, "~ scope {path} component {old:?} -> {new:?}")
+            }
+            HierarchyChange::VarType { path, old, new } => {
+                write!(f, "~ {path} type {old:?} -> {new:?}")
+            }
+            HierarchyChange::Direction { path, old, new } => {
+                write!(f, "~ {path} direction {old:?} -> {new:?}")
+            }
+            HierarchyChange::Width { path, old, new } => {
+                write!(f, "~ {path} width {old} -> {new}")
+            }
+        }
+    }
+}
+
+/// Path of an entry and the number of entries before it with the same path,
+/// FST allows several scopes or variables with the same name in a scope
+type Key = (String, usize);
+
+/// The scopes and variables of a hierarchy by their path.
+/// Variables are keyed without their bit range, so a vector that changes its width
+/// is reported as a change and not as a removed and an added variable.
+#[derive(Default)]
+struct Entries<'a> {
+    scopes: BTreeMap<Key, &'a Scope>,
+    /// variables with their full path
+    variables: BTreeMap<Key, (String, &'a Variable)>,
+}
+
+/// Insert `value` after the entries that have the same `path`
+fn insert<T>(map: &mut BTreeMap<Key, T>, path: String, value: T) {
+    let occurrence = map
+        .range((path.clone(), 0)..=(path.clone(), usize::MAX))
+        .next_back()
+        .map_or(0, |((_, occurrence), _)| occurrence + 1);
+    map.insert((path, occurrence), value);
+}
+
+impl<'a> Entries<'a> {
+    fn new(content: &'a HierarchyContent) -> Self {
+        let mut entries = Entries::default();
+        for (path, node) in content.iter() {
+            match node {
+                HierarchyNode::Scope(scope) => {
+                    insert(&mut entries.scopes, path, scope);
+                }
+                HierarchyNode::Variable(variable) => {
+                    let name = variable.vcd().name();
+                    let parsed = variable.vcd().parsed_name();
+                    let indices: String = parsed
+                        .indices
+                        .iter()
+                        .map(|index| format!("[{index}]"))
+                        .collect();
+                    let key = format!(
+                        "{}{}{indices}",
+                        &path[..path.len() - name.len()],
+                        parsed.base
+                    );
+                    insert(&mut entries.variables, key, (path, variable));
+                }
+            }
+        }
+        entries
+    }
+}
+
+/// The scopes and variables that were added, removed or changed from `old` to `new`, ordered by path.
+///
+/// Scopes and variables are matched by their path, so a renamed scope is removed and added
+/// together with everything inside of it.
+/// When a path appears several times, its first scope or variable in `old` is matched
+/// with its first one in `new` and so on.
+/// Handles are not compared, they change whenever variables are added or removed.
+pub fn diff(old: &HierarchyContent, new: &HierarchyContent) -> Vec<HierarchyChange> {
+    let old = Entries::new(old);
+    let new = Entries::new(new);
+    let mut changes = Vec::new();
+
+    let scope_keys: BTreeSet<&Key> = old.scopes.keys().chain(new.scopes.keys()).collect();
+    for key in scope_keys {
+        let path = key.0.clone();
+        match (old.scopes.get(key), new.scopes.get(key)) {
+            (Some(_), None) => changes.push(HierarchyChange::RemovedScope { path }),
+            (None, Some(_)) => changes.push(HierarchyChange::AddedScope { path }),
+            (Some(old), Some(new)) => {
+                if old.scope_type() != new.scope_type() {
+                    changes.push(HierarchyChange::ScopeType {
+                        path: path.clone(),
+                        old: old.scope_type(),
+                        new: new.scope_type(),
+                    });
+                }
+                if old.component() != new.component() {
+                    changes.push(HierarchyChange::Component {
+                        path,
+                        old: old.component().to_string(),
+                        new: new.component().to_string(),
+                    });
+                }
+            }
+            (None, None) => unreachable!("path comes from one of the hierarchies"),
+        }
+    }
+
+    let variable_keys: BTreeSet<&Key> = old.variables.keys().chain(new.variables.keys()).collect();
+    for key in variable_keys {
+        match (old.variables.get(key), new.variables.get(key)) {
+            (Some((path, _)), None) => {
+                changes.push(HierarchyChange::RemovedVariable { path: path.clone() })
+            }
+            (None, Some((path, _))) => {
+                changes.push(HierarchyChange::AddedVariable { path: path.clone() })
+            }
+            (Some((_, old)), Some((path, new))) => {
+                let (old, new) = (old.vcd(), new.vcd());
+                if old.var_type() != new.var_type() {
+                    changes.push(HierarchyChange::VarType {
+                        path: path.clone(),
+                        old: old.var_type(),
+                        new: new.var_type(),
+                    });
+                }
+                if old.direction() != new.direction() {
+                    changes.push(HierarchyChange::Direction {
+                        path: path.clone(),
+                        old: old.direction(),
+                        new: new.direction(),
+                    });
+                }
+                if old.length() != new.length() {
+                    changes.push(HierarchyChange::Width {
+                        path: path.clone(),
+                        old: old.length(),
+                        new: new.length(),
+                    });
+                }
+            }
+            (None, None) => unreachable!("key comes from one of the hierarchies"),
+        }
+    }
+
+    // stable, so the changes of a path keep their order
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
@@ -0,0 +1,225 @@
+use std::borrow::Cow;
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// A literal of an [EnumTable]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnumLiteral {
+    pub name: String,
+    /// Value of the literal as it appears in the value changes, like `01`
+    pub value: String,
+}
+
+/// Names for the values of an enum type, written by Verilator and GHDL as a
+/// [super::MiscType::EnumTable] attribute.
+///
+/// The attribute name holds the type name, the number of literals,
+/// the literal names and then their values, all separated by spaces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnumTable {
+    name: String,
+    literals: Vec<EnumLiteral>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EnumTableError {
+    #[error("enum table {0:?} does not match its number of literals")]
+    WrongLiteralCount(String),
+    #[error("invalid escape sequence in {0:?}")]
+    InvalidEscape(String),
+}
+
+impl EnumTable {
+    pub fn new(name: String, literals: Vec<EnumLiteral>) -> Self {
+        Self { name, literals }
+    }
+
+    /// Parse the attribute name of an enum table definition
+    pub fn parse(text: &str) -> Result<Self, EnumTableError> {
+        let fields: Vec<&str> = text.split(' ').collect();
+        // the type name can contain spaces, so look for the count that fits the rest
+        let count_index = (1..fields.len())
+            .find(|&i| {
+                fields[i].parse::<usize>().is_ok_and(|count| {
+                    count > 0
+                        && count.checked_mul(2).and_then(|n| n.checked_add(i + 1))
+                            == Some(fields.len())
+                })
+            })
+            .ok_or_else(|| EnumTableError::WrongLiteralCount(text.to_string()))?;
+        let entries = &fields[count_index + 1..];
+        let (names, values) = entries.split_at(entries.len() / 2);
+        let literals = names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| {
+                Ok(EnumLiteral {
+                    name: unescape(name)?,
+                    value: unescape(value)?,
+                })
+            })
+            .collect::<Result<_, EnumTableError>>()?;
+        Ok(Self {
+            name: fields[..count_index].join(" "),
+            literals,
+        })
+    }
+
+    /// The text of the attribute name that defines this table
+    pub fn to_attribute_name(&self) -> String {
+        let mut text = format!("{} {}", self.name, self.literals.len());
+        for literal in &self.literals {
+            text.push(' ');
+            text.push_str(&escape(&literal.name));
+        }
+        for literal in &self.literals {
+            text.push(' ');
+            text.push_str(&escape(&literal.value));
+        }
+        text
+    }
+
+    /// Name of the enum type
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn literals(&self) -> &[EnumLiteral] {
+        &self.literals
+    }
+
+    /// Name of the literal with `value`.
+    /// Values that only differ in leading zeros are the same.
+    pub fn literal(&self, value: &[u8]) -> Option<&str> {
+        let trimmed = trim_zeros(value);
+        self.literals
+            .iter()
+            .find(|literal| literal.value.as_bytes() == value)
+            .or_else(|| {
+                self.literals
+                    .iter()
+                    .find(|literal| trim_zeros(literal.value.as_bytes()) == trimmed)
+            })
+            .map(|literal| literal.name.as_str())
+    }
+
+    /// The literal name of `value`, or the value itself when no literal has it
+    pub fn display<'a>(&'a self, value: &'a [u8]) -> Cow<'a, str> {
+        match self.literal(value) {
+            Some(name) => Cow::Borrowed(name),
+            None => String::from_utf8_lossy(value),
+        }
+    }
+}
+
+fn trim_zeros(value: &[u8]) -> &[u8] {
+    let start = value
+        .iter()
+        .position(|b| *b != b'0')
+        .unwrap_or(value.len().saturating_sub(1));
+    &value[start..]
+}
+
+/// Escape spaces and other characters that can not be part of a table entry, like fstapi does
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for b in text.bytes() {
+        match b {
+            0x07 => escaped.push_str("\\a"),
+            0x08 => escaped.push_str("\\b"),
+            0x0c => escaped.push_str("\\f"),
+            b'\n' => escaped.push_str("\\n"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\t' => escaped.push_str("\\t"),
+            0x0b => escaped.push_str("\\v"),
+            b'\'' | b'"' | b'\\' | b'?' => {
+                escaped.push('\\');
+                escaped.push(b as char);
+            }
+            b'!'..=b'~' => escaped.push(b as char),
+            _ => escaped.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    escaped
+}
+
+/// Undo [escape], also accepting octal escapes
+fn unescape(text: &str) -> Result<String, EnumTableError> {
+    let invalid = || EnumTableError::InvalidEscape(text.to_string());
+    let bytes = text.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            unescaped.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let escape = *bytes.get(i + 1).ok_or_else(invalid)?;
+        i += 2;
+        let b = match escape {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            b'x' | b'X' => {
+                let digits = text.get(i..i + 2).ok_or_else(invalid)?;
+                i += 2;
+                u8::from_str_radix(digits, 16).map_err(|_| invalid())?
+            }
+            b'0'..=b'7' => {
+                let digits = text.get(i - 1..i + 2).ok_or_else(invalid)?;
+                i += 2;
+                u8::from_str_radix(digits, 8).map_err(|_| invalid())?
+            }
+            other => other,
+        };
+        unescaped.push(b);
+    }
+    String::from_utf8(unescaped).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_table() {
+        let table = EnumTable::parse("state_t 3 IDLE BUSY DONE 00 01 10").unwrap();
+        assert_eq!(table.name(), "state_t");
+        assert_eq!(table.literals().len(), 3);
+        assert_eq!(table.literal(b"01"), Some("BUSY"));
+        assert_eq!(table.literal(b"0010"), Some("DONE"));
+        assert_eq!(table.literal(b"0"), Some("IDLE"));
+        assert_eq!(table.literal(b"11"), None);
+        assert_eq!(table.display(b"11"), "11");
+    }
+
+    #[test]
+    fn escaped_entries() {
+        let table = EnumTable::new(
+            "my type".to_string(),
+            vec![EnumLiteral {
+                name: "a b\\c".to_string(),
+                value: "1".to_string(),
+            }],
+        );
+        let text = table.to_attribute_name();
+        assert_eq!(text, "my type 1 a\\x20b\\\\c 1");
+        assert_eq!(EnumTable::parse(&text).unwrap(), table);
+        assert_eq!(unescape("\\101\\t").unwrap(), "A\t");
+        assert!(EnumTable::parse("t 2 A 0").is_err());
+        assert!(EnumTable::parse("t 1 A\\x4 0").is_err());
+    }
+
+    #[test]
+    fn huge_literal_count() {
+        // twice the count wraps around to zero
+        assert!(EnumTable::parse("t 9223372036854775808").is_err());
+        assert!(EnumTable::parse(&format!("t {}", usize::MAX)).is_err());
+    }
+}
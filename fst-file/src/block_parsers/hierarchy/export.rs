@@ -0,0 +1,265 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use serde::Serialize;
+
+use crate::{data_types::Handle, glob::glob_match};
+
+use super::{HierarchyContent, HierarchyNode, HierarchyVisitor, Scope, VarDir, VarType, Variable};
+
+/// A variable of the flat signal list, see [signal_rows]
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalRow {
+    pub path: String,
+    pub var_type: VarType,
+    pub direction: VarDir,
+    pub width: u64,
+    pub handle: Handle,
+    /// path of the first variable with the same handle when this one is an alias
+    pub alias_of: Option<String>,
+}
+
+/// All variables of the hierarchy in the order of [HierarchyContent::iter]
+pub fn signal_rows(content: &HierarchyContent) -> Vec<SignalRow> {
+    let mut first_paths: HashMap<Handle, String> = HashMap::new();
+    let mut rows = Vec::new();
+    for (path, node) in content.iter() {
+        let HierarchyNode::Variable(variable) = node else {
+            continue;
+        };
+        let vcd = variable.vcd();
+        let alias_of = match first_paths.get(&variable.handle()) {
+            Some(first) => Some(first.clone()),
+            None => {
+                first_paths.insert(variable.handle(), path.clone());
+                None
+            }
+        };
+        rows.push(SignalRow {
+            path,
+            var_type: vcd.var_type(),
+            direction: vcd.direction(),
+            width: vcd.length(),
+            handle: variable.handle(),
+            alias_of,
+        });
+    }
+    rows
+}
+
+/// Write the [signal_rows] as CSV with a header line
+pub fn write_csv(content: &HierarchyContent, mut output: impl Write) -> io::Result<()> {
+    writeln!(output, "path,type,direction,width,handle,alias_of")?;
+    for row in signal_rows(content) {
+        writeln!(
+            output,
+            "{},{:?},{:?},{},{},{}",
+            csv_field(&row.path),
+            row.var_type,
+            row.direction,
+            row.width,
+            row.handle.0,
+            csv_field(row.alias_of.as_deref().unwrap_or_default()),
+        )?;
+    }
+    Ok(())
+}
+
+/// Quote a field that contains a comma, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write the tree of scopes as a Graphviz graph.
+/// Every scope is a node labeled with its name and component.
+pub fn write_dot(content: &HierarchyContent, mut output: impl Write) -> io::Result<()> {
+    writeln!(output, "digraph hierarchy {{")?;
+    writeln!(output, "    node [shape=box];")?;
+    // scopes with the id of their node, parents before their children
+    let mut stack: Vec<(&Scope, Option<usize>)> = content
+        .roots()
+        .iter()
+        .rev()
+        .map(|root| (root, None))
+        .collect();
+    let mut next_id = 0;
+    while let Some((scope, parent)) = stack.pop() {
+        let id = next_id;
+        next_id += 1;
+        let mut label = dot_escape(scope.name());
+        if !scope.component().is_empty() {
+            label.push_str("\\n");
+            label.push_str(&dot_escape(scope.component()));
+        }
+        writeln!(output, "    s{id} [label=\"{label}\"];")?;
+        if let Some(parent) = parent {
+            writeln!(output, "    s{parent} -> s{id};")?;
+        }
+        stack.extend(scope.scopes().iter().rev().map(|child| (child, Some(id))));
+    }
+    writeln!(output, "}}")
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// What [write_tree] shows
+#[derive(Debug, Clone)]
+pub struct TreeOptions {
+    /// Deepest level that is shown, the top level scopes are at level 1
+    pub max_depth: Option<usize>,
+    /// Glob of the scope paths to show with their contents, their parents are shown as well
+    pub filter: Option<String>,
+    pub separator: char,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            filter: None,
+            separator: '.',
+        }
+    }
+}
+
+/// Write the hierarchy as an indented tree, one scope or variable per line
+pub fn write_tree(
+    content: &HierarchyContent,
+    options: &TreeOptions,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let shown = options.filter.as_ref().map(|filter| {
+        let mut marker = FilterMarker {
+            filter,
+            separator: options.separator,
+            path: Vec::new(),
+            open: Vec::new(),
+            matched: HashSet::new(),
+            parents: HashSet::new(),
+        };
+        content.visit(&mut marker);
+        (marker.matched, marker.parents)
+    });
+    let mut printer = TreePrinter {
+        options,
+        shown,
+        depth: 0,
+        inside_match: None,
+        text: String::new(),
+    };
+    content.visit(&mut printer);
+    output.write_all(printer.text.as_bytes())
+}
+
+/// Finds the scopes that match the filter of a tree and their parents
+struct FilterMarker<'a, 'f> {
+    filter: &'f str,
+    separator: char,
+    path: Vec<&'a str>,
+    open: Vec<&'a Scope>,
+    matched: HashSet<*const Scope>,
+    /// parents of matching scopes
+    parents: HashSet<*const Scope>,
+}
+
+impl<'a> HierarchyVisitor<'a> for FilterMarker<'a, '_> {
+    fn enter_scope(&mut self, scope: &'a Scope) {
+        self.path.push(scope.name());
+        self.open.push(scope);
+        let path = self.path.join(&self.separator.to_string());
+        if glob_match(self.filter, &path, self.separator) {
+            self.matched.insert(scope);
+            self.parents.extend(
+                self.open[..self.open.len() - 1]
+                    .iter()
+                    .map(|scope| *scope as *const Scope),
+            );
+        }
+    }
+
+    fn leave_scope(&mut self, _scope: &'a Scope) {
+        self.path.pop();
+        self.open.pop();
+    }
+}
+
+struct TreePrinter<'o> {
+    options: &'o TreeOptions,
+    /// scopes matching the filter and their parents when there is a filter
+    shown: Option<(HashSet<*const Scope>, HashSet<*const Scope>)>,
+    /// number of open scopes
+    depth: usize,
+    /// depth of the open scope that matched the filter
+    inside_match: Option<usize>,
+    text: String,
+}
+
+impl TreePrinter<'_> {
+    /// Whether an entry at the current depth is shown
+    fn visible(&self) -> bool {
+        self.options
+            .max_depth
+            .is_none_or(|max_depth| self.depth < max_depth)
+    }
+
+    fn line(&mut self, text: std::fmt::Arguments) {
+        let indent = "  ".repeat(self.depth);
+        let _ = writeln!(self.text, "{indent}{text}");
+    }
+}
+
+impl<'a> HierarchyVisitor<'a> for TreePrinter<'_> {
+    fn enter_scope(&mut self, scope: &'a Scope) {
+        let show = match &self.shown {
+            None => true,
+            Some(_) if self.inside_match.is_some() => true,
+            Some((matched, parents)) => {
+                let scope: *const Scope = scope;
+                if matched.contains(&scope) {
+                    self.inside_match = Some(self.depth);
+                }
+                self.inside_match.is_some() || parents.contains(&scope)
+            }
+        };
+        if show && self.visible() {
+            if scope.component().is_empty() {
+                self.line(format_args!("{}", scope.name()));
+            } else {
+                self.line(format_args!("{} ({})", scope.name(), scope.component()));
+            }
+        }
+        self.depth += 1;
+    }
+
+    fn leave_scope(&mut self, _scope: &'a Scope) {
+        self.depth -= 1;
+        if self.inside_match == Some(self.depth) {
+            self.inside_match = None;
+        }
+    }
+
+    fn variable(&mut self, variable: &'a Variable) {
+        if self.shown.is_some() && self.inside_match.is_none() {
+            return;
+        }
+        if self.visible() {
+            let vcd = variable.vcd();
+            self.line(format_args!(
+                "{} {:?} {:?} {}",
+                vcd.name(),
+                vcd.var_type(),
+                vcd.direction(),
+                vcd.length()
+            ));
+        }
+    }
+}
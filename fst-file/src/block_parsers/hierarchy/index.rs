@@ -0,0 +1,182 @@
+use std::{collections::HashMap, slice};
+
+pub use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    data_types::Handle,
+    glob::{glob_match, is_glob},
+};
+
+use super::{HierarchyContent, Scope, Variable};
+
+/// A variable found in a [PathIndex]
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedVariable<'a> {
+    /// Names of the scopes and the variable joined with the separator of the index
+    pub path: String,
+    pub variable: &'a Variable,
+}
+
+impl IndexedVariable<'_> {
+    pub fn handle(&self) -> Handle {
+        self.variable.handle()
+    }
+}
+
+/// Looks up the variables of a [HierarchyContent] by their path.
+///
+/// A path is made of the scope names and the variable name joined with a separator, `.` by default.
+/// The bit range of a variable (`pc [31:0]`) can be written with or without the space
+/// and can be left out in exact lookups.
+#[derive(Debug, Clone)]
+pub struct PathIndex<'a> {
+    separator: char,
+    variables: Vec<IndexedVariable<'a>>,
+    /// indices into `variables` by the normalized path
+    by_path: HashMap<String, Vec<usize>>,
+    /// indices into `variables` by the normalized path without the bit range
+    by_path_without_range: HashMap<String, Vec<usize>>,
+}
+
+impl<'a> PathIndex<'a> {
+    pub fn new(content: &'a HierarchyContent) -> Self {
+        Self::with_separator(content, '.')
+    }
+
+    pub fn with_separator(content: &'a HierarchyContent, separator: char) -> Self {
+        let mut index = Self {
+            separator,
+            variables: Vec::new(),
+            by_path: HashMap::new(),
+            by_path_without_range: HashMap::new(),
+        };
+        for variable in &content.variables {
+            index.add_variable(variable, variable.vcd.name().to_string());
+        }
+        for root in &content.scopes {
+            index.add_scope(root);
+        }
+        index
+    }
+
+    /// Add the variables of `root` and its child scopes, depth first without recursion
+    fn add_scope(&mut self, root: &'a Scope) {
+        let separator = self.separator.to_string();
+        // the scopes on the way down with their child scopes that are not added yet
+        let mut path: Vec<(&'a Scope, slice::Iter<'a, Scope>)> = Vec::new();
+        let mut next = Some(root);
+        loop {
+            if let Some(scope) = next {
+                path.push((scope, scope.scopes.iter()));
+                for variable in &scope.signals {
+                    let full_path = path
+                        .iter()
+                        .map(|(scope, _)| scope.name())
+                        .chain([variable.vcd.name()])
+                        .collect::<Vec<_>>()
+                        .join(&separator);
+                    self.add_variable(variable, full_path);
+                }
+            }
+            let Some((_, children)) = path.last_mut() else {
+                break;
+            };
+            next = children.next();
+            if next.is_none() {
+                path.pop();
+            }
+        }
+    }
+
+    fn add_variable(&mut self, variable: &'a Variable, path: String) {
+        let key = normalize(&path);
+        if let Some(without_range) = self.strip_range(&key) {
+            self.by_path_without_range
+                .entry(without_range.to_string())
+                .or_default()
+                .push(self.variables.len());
+        }
+        self.by_path
+            .entry(key)
+            .or_default()
+            .push(self.variables.len());
+        self.variables.push(IndexedVariable { path, variable });
+    }
+
+    /// The path without the bit range of the variable, if it has one
+    fn strip_range<'p>(&self, path: &'p str) -> Option<&'p str> {
+        let (without_range, range) = path.rsplit_once('[')?;
+        (range.ends_with(']') && !range.contains(self.separator)).then_some(without_range)
+    }
+
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+
+    pub fn len(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// All variables, the ones outside of any scope first
+    /// and the ones of a scope before the ones of its child scopes
+    pub fn iter(&self) -> impl Iterator<Item = &IndexedVariable<'a>> {
+        self.variables.iter()
+    }
+
+    fn collect(&self, indices: Option<&Vec<usize>>) -> Vec<&IndexedVariable<'a>> {
+        indices
+            .into_iter()
+            .flatten()
+            .map(|i| &self.variables[*i])
+            .collect()
+    }
+
+    /// Variables at exactly `path`.
+    /// When no variable has this path, the variables whose path without the bit range matches are returned.
+    pub fn get(&self, path: &str) -> Vec<&IndexedVariable<'a>> {
+        let key = normalize(path);
+        match self.by_path.get(&key) {
+            Some(indices) => self.collect(Some(indices)),
+            None => self.collect(self.by_path_without_range.get(&key)),
+        }
+    }
+
+    /// Variables whose path matches the glob `pattern`.
+    ///
+    /// `?` matches one character and `*` any number of characters inside a scope name,
+    /// `**` matches across scopes.
+    pub fn glob(&self, pattern: &str) -> Vec<&IndexedVariable<'a>> {
+        let pattern = normalize(pattern);
+        self.variables
+            .iter()
+            .filter(|variable| glob_match(&pattern, &normalize(&variable.path), self.separator))
+            .collect()
+    }
+
+    /// Variables whose path contains a match of `regex`
+    pub fn regex(&self, regex: &Regex) -> Vec<&IndexedVariable<'a>> {
+        self.variables
+            .iter()
+            .filter(|variable| regex.is_match(&variable.path))
+            .collect()
+    }
+
+    /// Variables matching `pattern`, which is a glob when it contains `*` or `?` and a path otherwise
+    pub fn find(&self, pattern: &str) -> Vec<&IndexedVariable<'a>> {
+        if is_glob(pattern) {
+            self.glob(pattern)
+        } else {
+            self.get(pattern)
+        }
+    }
+}
+
+/// Remove the space in front of bit ranges
+pub(super) fn normalize(path: &str) -> String {
+    path.replace(" [", "[")
+}
@@ -5,7 +5,7 @@ use serde::Serialize;
 
 use crate::{block_parsers::hierarchy::HierarchyParseErrorKind, error::ParseResult, FstParsable};
 
-#[derive(Debug, Clone, PartialEq, Primitive, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive, Serialize)]
 #[repr(u8)]
 pub enum MiscType {
     Comment = 0,
@@ -20,7 +20,7 @@ pub enum MiscType {
 }
 
 impl FstParsable for MiscType {
-    fn parse(input: &[u8]) -> ParseResult<Self> {
+    fn parse(input: &[u8]) -> ParseResult<'_, Self> {
         map_res(be_u8, |v| {
             MiscType::from_u8(v).ok_or((input, HierarchyParseErrorKind::WrongMiscType(v)))
         })(input)
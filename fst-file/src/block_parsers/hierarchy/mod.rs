@@ -1,33 +1,56 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
 use nom::{
     branch::alt,
     bytes::{
         complete::{tag, take},
         streaming::take_while_m_n,
     },
-    combinator::{eof, opt},
-    error::{ErrorKind, ParseError, VerboseError, VerboseErrorKind},
-    multi::many_till,
-    Finish, Offset,
+    error::VerboseErrorKind,
 };
 use serde::Serialize;
 use thiserror::Error;
-use tracing::{debug, debug_span, trace, warn};
+use tracing::{debug, debug_span, trace};
+
+#[macro_use]
+mod code_enum;
 
 mod attribute_type;
+mod bus;
+pub mod diff;
+mod enum_table;
+pub mod export;
+/// Lookup of variables by their path
+pub mod index;
 mod misc_type;
+mod name;
 mod scope_type;
+/// Source files and lines of scopes and variables
+pub mod source;
+mod stream;
+mod supplemental_type;
 mod var_dir;
+mod var_name;
 mod var_type;
+mod visit;
 
 pub use attribute_type::*;
+pub use bus::*;
+pub use enum_table::*;
 pub use misc_type::*;
+pub use name::*;
 pub use scope_type::*;
+pub use stream::*;
+pub use supplemental_type::*;
 pub use var_dir::*;
+pub use var_name::*;
 pub use var_type::*;
+pub use visit::*;
 
 use crate::{
-    data_types::{BlockType, VarInt},
+    data_types::{BlockType, Handle, VarInt},
     error::{ParseResult, PositionError},
+    options::Anomaly,
     FstParsable,
 };
 
@@ -37,7 +60,7 @@ use super::{Block, DecompressError};
 pub struct Vcd {
     var_type: VarType,
     direction: VarDir,
-    name: String,
+    name: Name,
     length_of_variable: VarInt,
     alias_variable_id: VarInt,
 }
@@ -45,16 +68,84 @@ pub struct Vcd {
 #[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct ScopeBegin {
     scope_type: ScopeType,
-    name: String,
-    component: String,
+    name: Name,
+    component: Name,
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct Attribute {
     attr_type: AttributeType,
     misc_type: MiscType,
-    name: String,
+    name: Name,
     value: VarInt,
+    /// source stems store the id of their path in place of the name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_id: Option<VarInt>,
+}
+
+impl Vcd {
+    pub fn var_type(&self) -> VarType {
+        self.var_type
+    }
+
+    pub fn direction(&self) -> VarDir {
+        self.direction
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Length in bits of the variable
+    pub fn length(&self) -> u64 {
+        self.length_of_variable.0
+    }
+
+    /// Handle of the variable this one is an alias of
+    pub fn alias(&self) -> Option<Handle> {
+        match self.alias_variable_id.0 {
+            0 => None,
+            id => Some(Handle(id as u32)),
+        }
+    }
+}
+
+impl ScopeBegin {
+    pub fn scope_type(&self) -> ScopeType {
+        self.scope_type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+}
+
+impl Attribute {
+    pub fn attr_type(&self) -> AttributeType {
+        self.attr_type
+    }
+
+    pub fn misc_type(&self) -> MiscType {
+        self.misc_type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value.0
+    }
+
+    /// Id of the source file of a [MiscType::SourceStem] or [MiscType::SourceIStem],
+    /// see [HierarchyContent::source_path]
+    pub fn path_id(&self) -> Option<u64> {
+        self.path_id.map(|id| id.0)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
@@ -71,41 +162,111 @@ pub enum HierarchyToken {
 pub enum HierarchyParseErrorKind {
     #[error("misc type was wrong on attribute. the value was {0}")]
     WrongMiscType(u8),
-    #[error("scope type was wrong on scope. the value was {0}")]
-    WrongScopeType(u8),
-    #[error("var type was wrong. the value was {0}")]
-    WrongVarType(u8),
-    #[error("var dir was wrong. the value was {0}")]
-    WrongVarDir(u8),
     #[error("did not start with scope")]
     DidNotStartWithScope,
     #[error("unreachable error")]
     Unreachable,
-    #[error("unknown attribute type {0}")]
-    WrongAttributeType(u8),
+}
+
+/// A variable in the hierarchy together with the handle of its value changes
+#[derive(Debug, Clone, Serialize)]
+pub struct Variable {
+    #[serde(flatten)]
+    vcd: Vcd,
+    handle: Handle,
+    attributes: Vec<Attribute>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enum_table: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supplemental_type: Option<SupplementalType>,
+}
+
+impl Variable {
+    pub fn vcd(&self) -> &Vcd {
+        &self.vcd
+    }
+
+    /// Handle of the value changes, shared with other variables when this is an alias
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Attributes that were active when the variable was declared, outermost first
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Id of the enum table that names the values of the variable,
+    /// see [HierarchyContent::enum_table]
+    pub fn enum_table_id(&self) -> Option<u64> {
+        self.enum_table
+    }
+
+    /// Type of the variable in the source language, given by GHDL and nvc
+    pub fn supplemental_type(&self) -> Option<&SupplementalType> {
+        self.supplemental_type.as_ref()
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Scope {
     scope_type: ScopeType,
-    name: String,
-    component: String,
+    name: Name,
+    component: Name,
     attributes: Vec<Attribute>,
-    signals: Vec<Vcd>,
+    signals: Vec<Variable>,
     scopes: Vec<Scope>,
 }
 
+impl Drop for Scope {
+    fn drop(&mut self) {
+        // take the child scopes apart one by one, dropping them recursively
+        // would overflow the stack for deep hierarchies
+        let mut scopes = std::mem::take(&mut self.scopes);
+        while let Some(mut scope) = scopes.pop() {
+            scopes.append(&mut scope.scopes);
+        }
+    }
+}
+
 impl Scope {
-    pub fn new(scope_type: ScopeType, name: String, component: String) -> Self {
+    pub fn new(scope_type: ScopeType, name: impl Into<Name>, component: impl Into<Name>) -> Self {
         Self {
             scope_type,
-            name,
-            component,
+            name: name.into(),
+            component: component.into(),
             attributes: vec![],
             signals: vec![],
             scopes: vec![],
         }
     }
+
+    pub fn scope_type(&self) -> ScopeType {
+        self.scope_type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    /// Attributes that were active when the scope was declared, outermost first
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Variables declared directly in this scope
+    pub fn variables(&self) -> &[Variable] {
+        &self.signals
+    }
+
+    /// Child scopes
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
 }
 
 type Span<'a> = (&'a [u8], &'a [u8]);
@@ -118,7 +279,57 @@ type Span<'a> = (&'a [u8], &'a [u8]);
 
 #[derive(Debug, Clone, Serialize)]
 pub struct HierarchyContent {
-    root_scope: Scope,
+    attributes: Vec<Attribute>,
+    variables: Vec<Variable>,
+    scopes: Vec<Scope>,
+    /// enum tables by their id
+    enum_tables: BTreeMap<u64, EnumTable>,
+    /// source file paths by their id
+    source_paths: BTreeMap<u64, String>,
+}
+
+impl HierarchyContent {
+    /// Attributes given outside of any scope
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Variables declared outside of any scope
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    /// Top level scopes
+    pub fn roots(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    /// Enum tables defined in the hierarchy by their id
+    pub fn enum_tables(&self) -> &BTreeMap<u64, EnumTable> {
+        &self.enum_tables
+    }
+
+    /// Path of the source file with `id`, see [Attribute::path_id]
+    pub fn source_path(&self, id: u64) -> Option<&str> {
+        self.source_paths.get(&id).map(String::as_str)
+    }
+
+    /// The enum table that names the values of `variable`
+    pub fn enum_table(&self, variable: &Variable) -> Option<&EnumTable> {
+        self.enum_tables.get(&variable.enum_table?)
+    }
+
+    /// A value of `variable` as text, using the name of the enum literal when there is one
+    /// and the form of the [SupplementalType] for VHDL booleans, characters and integers
+    pub fn display_value<'a>(&'a self, variable: &Variable, value: &'a [u8]) -> Cow<'a, str> {
+        if let Some(table) = self.enum_table(variable) {
+            return table.display(value);
+        }
+        variable
+            .supplemental_type()
+            .and_then(|supplemental| supplemental.data_type.display(value))
+            .map_or_else(|| String::from_utf8_lossy(value), Cow::Owned)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -128,51 +339,60 @@ impl HierarchyBlock {
         Self(block)
     }
 
+    pub fn get_block(&self) -> &Block {
+        &self.0
+    }
+
     pub fn get_content(&self) -> Result<HierarchyContent, HierarchyBlockConvertError> {
         let _span = debug_span!("get content").entered();
-        let tokens = self.get_tokens()?;
-
-        let scope = HierarchyContent::parse_structual_hierarchy(&tokens)
-            .finish()
-            .map(|(_, v)| v)
-            .unwrap();
+        let mut builder = stream::ContentBuilder::default();
+        self.stream(&mut builder)?;
+        Ok(builder.finish())
+    }
 
-        Ok(HierarchyContent { root_scope: scope })
+    /// Go through the hierarchy in one pass and call `handler` for every scope and variable
+    /// without building the tree of [HierarchyContent]
+    pub fn stream(
+        &self,
+        handler: &mut impl HierarchyHandler,
+    ) -> Result<(), HierarchyBlockConvertError> {
+        let _span = debug_span!("stream hierarchy").entered();
+        let uncompressed_data = self.extract_data()?;
+        let mut structure = stream::StructureBuilder::new(handler, &self.0);
+        for token in HierarchyTokens::new(&uncompressed_data) {
+            let (position, token) = token.map_err(|e| e.in_extracted(self.0.location()))?;
+            structure.push(position.position, token)?;
+        }
+        structure.finish()?;
+        Ok(())
     }
 
     pub fn get_tokens(
         &self,
     ) -> Result<Vec<(PosistionAndSize, HierarchyToken)>, HierarchyBlockConvertError> {
         let _scope = debug_span!("get tokens").entered();
+        let uncompressed_data = self.extract_data()?;
+        let tokens: Vec<_> = HierarchyTokens::new(&uncompressed_data)
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.in_extracted(self.0.location()))?;
+        for (position, token) in &tokens {
+            for anomaly in stream::token_anomalies(position.position, token) {
+                self.0.report(anomaly)?;
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn extract_data(&self) -> Result<Vec<u8>, HierarchyBlockConvertError> {
         if !matches!(
             self.0.block_type,
             BlockType::HierarchyGz | BlockType::HierarchyLz4 | BlockType::HierarchyLz4Duo
         ) {
             return Err(HierarchyBlockConvertError::NotHierarchyBlock);
         }
-
         let uncompressed_data = self.0.extract_data()?;
-
         debug!(uncompressed_data_len = uncompressed_data.len());
-
-        let tokens = HierarchyContent::parse_tokens(&uncompressed_data)
-            .finish()
-            .map(|(_, tokens)| {
-                tokens
-                    .into_iter()
-                    .map(|((start, end), token)| {
-                        (
-                            PosistionAndSize {
-                                position: uncompressed_data[..].offset(start),
-                                size: start.offset(end),
-                            },
-                            token,
-                        )
-                    })
-                    .collect::<Vec<(PosistionAndSize, HierarchyToken)>>()
-            })
-            .map_err(|e| PositionError::from_verbose_parse_error(e, &uncompressed_data))?;
-        Ok(tokens)
+        Ok(uncompressed_data)
     }
 }
 
@@ -184,6 +404,8 @@ pub enum HierarchyBlockConvertError {
     TokenParseError(#[from] PositionError<VerboseErrorKind>),
     #[error("error during uncompressing hierarchy data: {0}")]
     DataDecompressError(#[from] DecompressError),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
 }
 
 #[derive(Debug, Serialize)]
@@ -193,28 +415,53 @@ pub struct PosistionAndSize {
 }
 
 impl HierarchyContent {
-    fn parse_tokens(input: &[u8]) -> ParseResult<Vec<(Span, HierarchyToken)>> {
-        let (input, (token, _)) = many_till(
-            alt((
-                HierarchyContent::parse_attr_begin,
-                HierarchyContent::parse_attr_end,
-                HierarchyContent::parse_scope_begin,
-                HierarchyContent::parse_scope_end,
-                HierarchyContent::parse_vcd,
+    /// Parse the next token of the hierarchy, with the names taken from `interner`
+    fn parse_token<'a>(
+        input: &'a [u8],
+        interner: &mut Interner,
+    ) -> ParseResult<'a, (Span<'a>, HierarchyToken)> {
+        match input.first().copied() {
+            Some(b) if b == ScopeType::GenAttrBegin.to_u8() => alt((
+                |input| HierarchyContent::parse_attr_begin(input, interner),
+                HierarchyContent::parse_unknown,
+            ))(input),
+            Some(b) if b == ScopeType::GenAttrEnd.to_u8() => {
+                HierarchyContent::parse_attr_end(input)
+            }
+            Some(b) if b == ScopeType::VcdScope.to_u8() => alt((
+                |input| HierarchyContent::parse_scope_begin(input, interner),
+                HierarchyContent::parse_unknown,
+            ))(input),
+            Some(b) if b == ScopeType::VcdUnScope.to_u8() => {
+                HierarchyContent::parse_scope_end(input)
+            }
+            _ => alt((
+                |input| HierarchyContent::parse_vcd(input, interner),
                 HierarchyContent::parse_unknown,
-            )),
-            eof,
-        )(input)?;
-        Ok((input, token))
+            ))(input),
+        }
     }
 
-    fn parse_attr_begin(input: &[u8]) -> ParseResult<'_, (Span, HierarchyToken)> {
+    fn parse_attr_begin<'a>(
+        input: &'a [u8],
+        interner: &mut Interner,
+    ) -> ParseResult<'a, (Span<'a>, HierarchyToken)> {
         trace!("attr begin");
         let original_input = input;
-        let (input, _) = tag(&[ScopeType::GenAttrBegin as u8])(input)?;
+        let (input, _) = tag(&[ScopeType::GenAttrBegin.to_u8()])(input)?;
         let (input, attr_type) = AttributeType::parse(input)?;
         let (input, misc_type) = MiscType::parse(input)?;
-        let (input, name) = c_str_up_to_512(input)?;
+        let (input, (name, path_id)) = match (attr_type, misc_type) {
+            (AttributeType::Misc, MiscType::SourceStem | MiscType::SourceIStem) => {
+                let (input, path_id) = VarInt::parse(input)?;
+                let (input, _) = tag(&[0])(input)?;
+                (input, (interner.intern(""), Some(path_id)))
+            }
+            _ => {
+                let (input, name) = raw_c_str_up_to_512(input)?;
+                (input, (interner.intern_bytes(name), None))
+            }
+        };
         let (input, value) = VarInt::parse(input)?;
 
         Ok((
@@ -226,28 +473,34 @@ impl HierarchyContent {
                     misc_type,
                     name,
                     value,
+                    path_id,
                 }),
             ),
         ))
     }
 
-    fn parse_attr_end(input: &[u8]) -> ParseResult<(Span, HierarchyToken)> {
+    fn parse_attr_end(input: &[u8]) -> ParseResult<'_, (Span<'_>, HierarchyToken)> {
         trace!("attr end");
         let original_input = input;
-        let (input, _) = tag(&[ScopeType::GenAttrEnd as u8])(input)?;
+        let (input, _) = tag(&[ScopeType::GenAttrEnd.to_u8()])(input)?;
         Ok((
             input,
             ((original_input, input), HierarchyToken::AttributeEnd),
         ))
     }
 
-    fn parse_scope_begin(input: &[u8]) -> ParseResult<(Span, HierarchyToken)> {
+    fn parse_scope_begin<'a>(
+        input: &'a [u8],
+        interner: &mut Interner,
+    ) -> ParseResult<'a, (Span<'a>, HierarchyToken)> {
         trace!("scope begin");
         let original_input = input;
-        let (input, _) = tag(&[ScopeType::VcdScope as u8])(input)?;
+        let (input, _) = tag(&[ScopeType::VcdScope.to_u8()])(input)?;
         let (input, scope_type) = ScopeType::parse(input)?;
-        let (input, name) = c_str_up_to_512(input)?;
-        let (input, component) = c_str_up_to_512(input)?;
+        let (input, name) = raw_c_str_up_to_512(input)?;
+        let (input, component) = raw_c_str_up_to_512(input)?;
+        let name = interner.intern_bytes(name);
+        let component = interner.intern_bytes(component);
 
         Ok((
             input,
@@ -262,21 +515,25 @@ impl HierarchyContent {
         ))
     }
 
-    fn parse_scope_end(input: &[u8]) -> ParseResult<(Span, HierarchyToken)> {
+    fn parse_scope_end(input: &[u8]) -> ParseResult<'_, (Span<'_>, HierarchyToken)> {
         trace!("scope end");
         let original_input = input;
-        let (input, _) = tag(&[ScopeType::VcdUnScope as u8])(input)?;
+        let (input, _) = tag(&[ScopeType::VcdUnScope.to_u8()])(input)?;
         Ok((input, ((original_input, input), HierarchyToken::ScopeEnd)))
     }
 
-    fn parse_vcd(input: &[u8]) -> ParseResult<(Span, HierarchyToken)> {
+    fn parse_vcd<'a>(
+        input: &'a [u8],
+        interner: &mut Interner,
+    ) -> ParseResult<'a, (Span<'a>, HierarchyToken)> {
         trace!("vcd data");
         let original_input = input;
         let (input, var_type) = VarType::parse(input)?;
         let (input, direction) = VarDir::parse(input)?;
-        let (input, name) = c_str_up_to_512(input)?;
+        let (input, name) = raw_c_str_up_to_512(input)?;
         let (input, length_of_variable) = VarInt::parse(input)?;
         let (input, alias_variable_id) = VarInt::parse(input)?;
+        let name = interner.intern_bytes(name);
 
         Ok((
             input,
@@ -293,146 +550,19 @@ impl HierarchyContent {
         ))
     }
 
-    fn parse_unknown(input: &[u8]) -> ParseResult<(Span, HierarchyToken)> {
+    fn parse_unknown(input: &[u8]) -> ParseResult<'_, (Span<'_>, HierarchyToken)> {
         let original_input = input;
         let (input, b) = take(1u8)(input)?;
-        warn!("unknown byte while parsing hierarchy");
         Ok((
             input,
             ((original_input, input), HierarchyToken::Unknown(b[0])),
         ))
     }
-
-    fn parse_structual_hierarchy(
-        input: &[(PosistionAndSize, HierarchyToken)],
-    ) -> ParseResult<Scope, [(PosistionAndSize, HierarchyToken)]> {
-        let (input, t) = scope_begin(input)?;
-        let HierarchyToken::ScopeBegin(ScopeBegin { scope_type, name, component }) = t else {unreachable!()};
-        let mut scope = Scope::new(*scope_type, name.clone(), component.clone());
-
-        let mut input = input;
-        loop {
-            let (input_t, t) = opt(attr_begin)(input)?;
-            if let Some(HierarchyToken::Attribute(attribute)) = t {
-                input = input_t;
-                scope.attributes.push(attribute.clone());
-                continue;
-            }
-
-            let (input_t, t) = opt(attr_end)(input)?;
-            if let Some(HierarchyToken::AttributeEnd) = t {
-                input = input_t;
-                continue;
-            }
-
-            let (input_t, t) = opt(vcd)(input)?;
-            if let Some(HierarchyToken::Vcd(vcd)) = t {
-                input = input_t;
-                scope.signals.push(vcd.clone());
-                continue;
-            }
-
-            let (input_t, s) = opt(unknown)(input)?;
-            if let Some(HierarchyToken::Unknown(u)) = s {
-                input = input_t;
-                warn!("ignoring unknown token {}", u);
-                continue;
-            }
-
-            let (input_t, s) = opt(Self::parse_structual_hierarchy)(input)?;
-            if let Some(s) = s {
-                input = input_t;
-                scope.scopes.push(s);
-                continue;
-            }
-
-            let (input_t, s) = opt(scope_end)(input)?;
-            if let Some(HierarchyToken::ScopeEnd) = s {
-                input = input_t;
-                break;
-            }
-            return Err(nom::Err::Error(VerboseError::from_error_kind(
-                input,
-                ErrorKind::IsNot,
-            )));
-        }
-        Ok((input, scope))
-    }
-}
-
-fn attr_begin(
-    input: &[(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<&HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    token_condition(|t| matches!(t, HierarchyToken::Attribute(_)))(input)
-}
-
-fn attr_end(
-    input: &[(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<&HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    token(HierarchyToken::AttributeEnd)(input)
-}
-
-fn scope_begin(
-    input: &[(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<&HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    token_condition(|t| matches!(t, HierarchyToken::ScopeBegin(_)))(input)
-}
-
-fn vcd(
-    input: &[(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<&HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    token_condition(|t| matches!(t, HierarchyToken::Vcd(_)))(input)
-}
-
-fn unknown(
-    input: &[(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<&HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    token_condition(|t| matches!(t, HierarchyToken::Unknown(_)))(input)
-}
-
-fn scope_end(
-    input: &[(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<&HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    token(HierarchyToken::ScopeEnd)(input)
-}
-
-fn token<'a>(
-    token: HierarchyToken,
-) -> impl Fn(
-    &'a [(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<'a, &'a HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    move |i: &[(PosistionAndSize, HierarchyToken)]| match &i[0] {
-        t if t.1 == token => {
-            let (t, rest) = i.split_first().unwrap();
-            Ok((rest, &t.1))
-        }
-        _ => Err(nom::Err::Error(VerboseError::from_error_kind(
-            i,
-            ErrorKind::Eof,
-        ))),
-    }
-}
-
-fn token_condition<'a>(
-    condition: impl Fn(&HierarchyToken) -> bool,
-) -> impl Fn(
-    &'a [(PosistionAndSize, HierarchyToken)],
-) -> ParseResult<'a, &'a HierarchyToken, [(PosistionAndSize, HierarchyToken)]> {
-    move |i: &'a [(PosistionAndSize, HierarchyToken)]| match &i[0] {
-        t if condition(&t.1) => {
-            let (t, rest) = i.split_first().unwrap();
-            Ok((rest, &t.1))
-        }
-        _ => Err(nom::Err::Error(VerboseError::from_error_kind(
-            i,
-            ErrorKind::Eof,
-        ))),
-    }
 }
 
-fn c_str_up_to_512(input: &[u8]) -> ParseResult<'_, String> {
+fn raw_c_str_up_to_512(input: &[u8]) -> ParseResult<'_, &[u8]> {
     let (input, raw_str) = take_while_m_n(0, 511, |c| c != 0)(input)?;
     // for the last 0
     let (input, _) = take(1u8)(input)?;
-    Ok((input, String::from_utf8_lossy(raw_str).to_string()))
+    Ok((input, raw_str))
 }
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{index::normalize, Attribute, AttributeType, HierarchyContent, MiscType};
+
+/// Where a scope or variable comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u64,
+    /// whether this is where the scope was instantiated instead of declared
+    pub instantiation: bool,
+}
+
+/// Source locations of the scopes and variables of a [HierarchyContent] by their path.
+///
+/// They come from [MiscType::SourceStem] and [MiscType::SourceIStem] attributes,
+/// which refer to a file given by a [MiscType::PathName] attribute.
+/// Paths are looked up like in [super::index::PathIndex].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceLocations {
+    separator: char,
+    /// locations by the normalized path
+    locations: BTreeMap<String, Vec<SourceLocation>>,
+}
+
+impl SourceLocations {
+    pub fn new(content: &HierarchyContent) -> Self {
+        Self::with_separator(content, '.')
+    }
+
+    pub fn with_separator(content: &HierarchyContent, separator: char) -> Self {
+        let mut locations = BTreeMap::new();
+        for (path, node) in content.iter_with_separator(separator) {
+            let found = content.source_locations(node.attributes());
+            if !found.is_empty() {
+                locations.insert(normalize(&path), found);
+            }
+        }
+        Self {
+            separator,
+            locations,
+        }
+    }
+
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Locations of the scope or variable at `path`, the declaration before the instantiation
+    pub fn get(&self, path: &str) -> &[SourceLocation] {
+        self.locations
+            .get(&normalize(path))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Paths with their locations in the order of the paths
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[SourceLocation])> {
+        self.locations
+            .iter()
+            .map(|(path, locations)| (path.as_str(), locations.as_slice()))
+    }
+}
+
+impl HierarchyContent {
+    /// Source locations given by `attributes`, usually the ones of a scope or variable.
+    /// Stems that refer to an unknown file are left out.
+    pub fn source_locations(&self, attributes: &[Attribute]) -> Vec<SourceLocation> {
+        let mut locations: Vec<_> = attributes
+            .iter()
+            .filter(|attribute| attribute.attr_type() == AttributeType::Misc)
+            .filter_map(|attribute| {
+                let instantiation = match attribute.misc_type() {
+                    MiscType::SourceStem => false,
+                    MiscType::SourceIStem => true,
+                    _ => return None,
+                };
+                Some(SourceLocation {
+                    file: self.source_path(attribute.path_id()?)?.to_string(),
+                    line: attribute.value(),
+                    instantiation,
+                })
+            })
+            .collect();
+        locations.sort_by_key(|location| location.instantiation);
+        locations
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+
+use nom::{
+    error::{ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    Offset,
+};
+use tracing::trace;
+
+use crate::{
+    block_parsers::Block,
+    data_types::Handle,
+    error::PositionError,
+    options::{Anomaly, AnomalyKind},
+};
+
+use super::{
+    Attribute, AttributeType, EnumTable, HierarchyContent, HierarchyToken, Interner, MiscType,
+    PosistionAndSize, Scope, ScopeBegin, ScopeType, SupplementalType, VarDir, VarType, Variable,
+};
+
+/// The tokens of decompressed hierarchy data, parsed one at a time.
+///
+/// Stops after the first error.
+/// Equal names of scopes and variables share their memory.
+pub struct HierarchyTokens<'a> {
+    data: &'a [u8],
+    rest: &'a [u8],
+    interner: Interner,
+}
+
+impl<'a> HierarchyTokens<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_interner(data, Interner::default())
+    }
+
+    /// Tokens with names shared with those already in `interner`
+    pub fn with_interner(data: &'a [u8], interner: Interner) -> Self {
+        Self {
+            data,
+            rest: data,
+            interner,
+        }
+    }
+
+    /// The names of the tokens so far
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+}
+
+impl Iterator for HierarchyTokens<'_> {
+    type Item = Result<(PosistionAndSize, HierarchyToken), PositionError<VerboseErrorKind>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        match HierarchyContent::parse_token(self.rest, &mut self.interner) {
+            Ok((rest, ((start, end), token))) => {
+                self.rest = rest;
+                Some(Ok((
+                    PosistionAndSize {
+                        position: self.data.offset(start),
+                        size: start.offset(end),
+                    },
+                    token,
+                )))
+            }
+            Err(e) => {
+                let error = match e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => e,
+                    nom::Err::Incomplete(_) => {
+                        VerboseError::from_error_kind(self.rest, ErrorKind::Complete)
+                    }
+                };
+                self.rest = &[];
+                Some(Err(PositionError::from_verbose_parse_error(
+                    error, self.data,
+                )))
+            }
+        }
+    }
+}
+
+/// Receives the scopes and variables of a hierarchy in the order they are declared,
+/// see [super::HierarchyBlock::stream].
+///
+/// All methods do nothing by default.
+pub trait HierarchyHandler {
+    /// A scope starts, its contents follow until the matching [HierarchyHandler::scope_end].
+    /// The scope has its attributes but no children.
+    fn scope_begin(&mut self, _scope: Scope) {}
+
+    fn scope_end(&mut self) {}
+
+    fn variable(&mut self, _variable: Variable) {}
+
+    /// Any attribute token, `depth` is the number of open scopes
+    fn attribute(&mut self, _attribute: &Attribute, _depth: usize) {}
+
+    fn enum_table(&mut self, _id: u64, _table: EnumTable) {}
+
+    fn source_path(&mut self, _id: u64, _path: String) {}
+}
+
+/// Unknown tokens and the codes of a token that are kept as `Unknown(code)`
+pub(super) fn token_anomalies(position: usize, token: &HierarchyToken) -> Vec<AnomalyKind> {
+    let unknown = |what, code| AnomalyKind::UnknownCode {
+        what,
+        code,
+        offset: position,
+    };
+    let mut anomalies = Vec::new();
+    match token {
+        HierarchyToken::Vcd(vcd) => {
+            if let VarType::Unknown(code) = vcd.var_type() {
+                anomalies.push(unknown("variable type", code));
+            }
+            if let VarDir::Unknown(code) = vcd.direction() {
+                anomalies.push(unknown("variable direction", code));
+            }
+        }
+        HierarchyToken::ScopeBegin(scope) => {
+            if let ScopeType::Unknown(code) = scope.scope_type() {
+                anomalies.push(unknown("scope type", code));
+            }
+        }
+        HierarchyToken::Attribute(attribute) => {
+            if let AttributeType::Unknown(code) = attribute.attr_type() {
+                anomalies.push(unknown("attribute type", code));
+            }
+        }
+        HierarchyToken::Unknown(byte) => anomalies.push(AnomalyKind::UnknownToken {
+            byte: *byte,
+            offset: position,
+        }),
+        HierarchyToken::AttributeEnd | HierarchyToken::ScopeEnd => {}
+    }
+    anomalies
+}
+
+/// Turns the tokens into calls of a [HierarchyHandler], keeping only the open scopes and attributes
+pub(super) struct StructureBuilder<'h, H: HierarchyHandler> {
+    handler: &'h mut H,
+    /// the hierarchy block, anomalies are reported to it
+    block: &'h Block,
+    /// number of open scopes
+    depth: usize,
+    /// last handle given to a variable that is not an alias
+    next_handle: u32,
+    /// attributes opened by `GenAttrBegin` and not closed yet, outermost first
+    open_attributes: Vec<Attribute>,
+    /// misc attributes waiting for the next scope or variable
+    next_attributes: Vec<Attribute>,
+    /// enum table referenced for the next variable
+    next_enum_table: Option<u64>,
+    next_supplemental_type: Option<SupplementalType>,
+    /// whether the last token was a misc attribute, which fstapi sometimes closes right away
+    misc_attribute_open: bool,
+}
+
+impl<'h, H: HierarchyHandler> StructureBuilder<'h, H> {
+    pub(super) fn new(handler: &'h mut H, block: &'h Block) -> Self {
+        Self {
+            handler,
+            block,
+            depth: 0,
+            next_handle: 0,
+            open_attributes: Vec::new(),
+            next_attributes: Vec::new(),
+            next_enum_table: None,
+            next_supplemental_type: None,
+            misc_attribute_open: false,
+        }
+    }
+
+    /// Handle the token at `position` of the extracted data
+    pub(super) fn push(&mut self, position: usize, token: HierarchyToken) -> Result<(), Anomaly> {
+        for anomaly in token_anomalies(position, &token) {
+            self.block.report(anomaly)?;
+        }
+        match token {
+            HierarchyToken::Attribute(attribute) => {
+                self.handler.attribute(&attribute, self.depth);
+                self.begin_attribute(attribute)?;
+            }
+            HierarchyToken::AttributeEnd => self.end_attribute(),
+            HierarchyToken::ScopeBegin(ScopeBegin {
+                scope_type,
+                name,
+                component,
+            }) => {
+                let mut scope = Scope::new(scope_type, name, component);
+                scope.attributes = self.annotations();
+                // enum tables and types only apply to variables
+                self.next_enum_table = None;
+                self.next_supplemental_type = None;
+                self.depth += 1;
+                self.handler.scope_begin(scope);
+            }
+            HierarchyToken::ScopeEnd if self.depth == 0 => {
+                self.block
+                    .report(AnomalyKind::StrayScopeEnd { offset: position })?;
+            }
+            HierarchyToken::ScopeEnd => {
+                self.depth -= 1;
+                self.handler.scope_end();
+            }
+            HierarchyToken::Vcd(vcd) => {
+                let handle = vcd.alias().unwrap_or_else(|| {
+                    self.next_handle += 1;
+                    Handle(self.next_handle)
+                });
+                let variable = Variable {
+                    vcd,
+                    handle,
+                    attributes: self.annotations(),
+                    enum_table: self.next_enum_table.take(),
+                    supplemental_type: self.next_supplemental_type.take(),
+                };
+                self.handler.variable(variable);
+            }
+            // reported by token_anomalies
+            HierarchyToken::Unknown(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Close the scopes that are still open at the end of the hierarchy
+    pub(super) fn finish(self) -> Result<(), Anomaly> {
+        if self.depth > 0 {
+            self.block
+                .report(AnomalyKind::UnclosedScopes { count: self.depth })?;
+        }
+        for _ in 0..self.depth {
+            self.handler.scope_end();
+        }
+        Ok(())
+    }
+
+    /// Misc attributes (source locations, supplemental types, enum tables, ...) only annotate
+    /// the following scope or variable and are written without an end or closed right away.
+    /// All other attributes nest until their `GenAttrEnd`.
+    /// Enum tables are defined by a misc attribute with the table as its name and the id as its value,
+    /// a variable refers to one with a misc attribute without a name.
+    fn begin_attribute(&mut self, attribute: Attribute) -> Result<(), Anomaly> {
+        self.misc_attribute_open = attribute.attr_type == AttributeType::Misc;
+        match (attribute.attr_type, attribute.misc_type) {
+            (AttributeType::Misc, MiscType::EnumTable) if !attribute.name.is_empty() => {
+                match EnumTable::parse(&attribute.name) {
+                    Ok(table) => self.handler.enum_table(attribute.value.0, table),
+                    Err(e) => self.block.report(AnomalyKind::InvalidEnumTable {
+                        id: attribute.value.0,
+                        message: e.to_string(),
+                    })?,
+                }
+            }
+            (AttributeType::Misc, MiscType::EnumTable) => {
+                self.next_enum_table = Some(attribute.value.0);
+                self.next_attributes.push(attribute);
+            }
+            (AttributeType::Misc, MiscType::SupVar) => {
+                self.next_supplemental_type = SupplementalType::from_attribute(&attribute);
+                if self.next_supplemental_type.is_none() {
+                    self.block.report(AnomalyKind::UnknownSupplementalType {
+                        value: attribute.value.0,
+                    })?;
+                }
+                self.next_attributes.push(attribute);
+            }
+            (AttributeType::Misc, MiscType::PathName) => {
+                self.handler
+                    .source_path(attribute.value.0, attribute.name.to_string());
+            }
+            (AttributeType::Misc, _) => self.next_attributes.push(attribute),
+            _ => self.open_attributes.push(attribute),
+        }
+        Ok(())
+    }
+
+    fn end_attribute(&mut self) {
+        if std::mem::take(&mut self.misc_attribute_open) {
+            return;
+        }
+        if self.open_attributes.pop().is_none() {
+            trace!("ignoring attribute end without open attribute");
+        }
+    }
+
+    /// Attributes of the scope or variable that is declared now
+    fn annotations(&mut self) -> Vec<Attribute> {
+        self.misc_attribute_open = false;
+        let mut attributes = self.open_attributes.clone();
+        attributes.append(&mut self.next_attributes);
+        attributes
+    }
+}
+
+/// Builds the tree of [HierarchyContent], one [Scope] for every open scope
+pub(super) struct ContentBuilder {
+    /// the top level at the bottom, then the open scopes
+    scopes: Vec<Scope>,
+    enum_tables: BTreeMap<u64, EnumTable>,
+    source_paths: BTreeMap<u64, String>,
+}
+
+impl Default for ContentBuilder {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope::new(ScopeType::VcdModule, "", "")],
+            enum_tables: BTreeMap::new(),
+            source_paths: BTreeMap::new(),
+        }
+    }
+}
+
+impl ContentBuilder {
+    fn current(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("top level is never closed")
+    }
+
+    pub(super) fn finish(mut self) -> HierarchyContent {
+        let mut top_level = self.scopes.swap_remove(0);
+        HierarchyContent {
+            attributes: std::mem::take(&mut top_level.attributes),
+            variables: std::mem::take(&mut top_level.signals),
+            scopes: std::mem::take(&mut top_level.scopes),
+            enum_tables: self.enum_tables,
+            source_paths: self.source_paths,
+        }
+    }
+}
+
+impl HierarchyHandler for ContentBuilder {
+    fn scope_begin(&mut self, scope: Scope) {
+        self.scopes.push(scope);
+    }
+
+    fn scope_end(&mut self) {
+        if self.scopes.len() > 1 {
+            let scope = self.scopes.pop().expect("checked length");
+            self.current().scopes.push(scope);
+        }
+    }
+
+    fn variable(&mut self, variable: Variable) {
+        self.current().signals.push(variable);
+    }
+
+    fn attribute(&mut self, attribute: &Attribute, depth: usize) {
+        if depth == 0 {
+            self.current().attributes.push(attribute.clone());
+        }
+    }
+
+    fn enum_table(&mut self, id: u64, table: EnumTable) {
+        self.enum_tables.insert(id, table);
+    }
+
+    fn source_path(&mut self, id: u64, path: String) {
+        self.source_paths.insert(id, path);
+    }
+}
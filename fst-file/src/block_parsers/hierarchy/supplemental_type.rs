@@ -0,0 +1,134 @@
+use enum_primitive_derive::Primitive;
+use num_traits::FromPrimitive;
+use serde::Serialize;
+
+use super::{Attribute, AttributeType, MiscType, Name};
+
+/// Kind of a VHDL object, see [SupplementalType]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive, Serialize)]
+#[repr(u8)]
+pub enum SupplementalVarType {
+    None = 0,
+    VhdlSignal = 1,
+    VhdlVariable = 2,
+    VhdlConstant = 3,
+    VhdlFile = 4,
+    VhdlMemory = 5,
+}
+
+/// Predefined VHDL type of a variable, see [SupplementalType]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Primitive, Serialize)]
+#[repr(u8)]
+pub enum SupplementalDataType {
+    None = 0,
+    VhdlBoolean = 1,
+    VhdlBit = 2,
+    VhdlBitVector = 3,
+    VhdlStdULogic = 4,
+    VhdlStdULogicVector = 5,
+    VhdlStdLogic = 6,
+    VhdlStdLogicVector = 7,
+    VhdlUnsigned = 8,
+    VhdlSigned = 9,
+    VhdlInteger = 10,
+    VhdlReal = 11,
+    VhdlNatural = 12,
+    VhdlPositive = 13,
+    VhdlTime = 14,
+    VhdlCharacter = 15,
+    VhdlString = 16,
+}
+
+/// Number of bits of the data type in the value of a [MiscType::SupVar] attribute
+const DATA_TYPE_BITS: u32 = 10;
+
+/// The type of a variable in the source language, written by GHDL and nvc as a
+/// [MiscType::SupVar] attribute in front of the variable.
+///
+/// The attribute name holds the name of the type, like `std_logic_vector` or the name of a record,
+/// and the value holds the var type above the lowest 10 bits with the data type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SupplementalType {
+    pub type_name: Name,
+    pub var_type: SupplementalVarType,
+    pub data_type: SupplementalDataType,
+}
+
+impl SupplementalType {
+    /// Decode a [MiscType::SupVar] attribute, [None] for other attributes and unknown type codes
+    pub fn from_attribute(attribute: &Attribute) -> Option<Self> {
+        if attribute.attr_type() != AttributeType::Misc || attribute.misc_type() != MiscType::SupVar
+        {
+            return None;
+        }
+        let value = attribute.value();
+        Some(Self {
+            type_name: attribute.name.clone(),
+            var_type: SupplementalVarType::from_u64(value >> DATA_TYPE_BITS)?,
+            data_type: SupplementalDataType::from_u64(value & ((1 << DATA_TYPE_BITS) - 1))?,
+        })
+    }
+
+    /// The value of the attribute that stores this type
+    pub fn attribute_value(&self) -> u64 {
+        ((self.var_type as u64) << DATA_TYPE_BITS) | self.data_type as u64
+    }
+}
+
+impl SupplementalDataType {
+    /// A value in the way VHDL writes it, [None] when the type has no special form
+    /// or the value has bits that are not `0` or `1`
+    pub fn display(&self, value: &[u8]) -> Option<String> {
+        match self {
+            SupplementalDataType::VhdlBoolean => match value {
+                b"0" => Some("false".to_string()),
+                b"1" => Some("true".to_string()),
+                _ => None,
+            },
+            SupplementalDataType::VhdlCharacter => {
+                let c = char::from(u8::try_from(unsigned(value)?).ok()?);
+                Some(format!("{c:?}"))
+            }
+            SupplementalDataType::VhdlInteger => {
+                let bits = u32::try_from(value.len())
+                    .ok()
+                    .filter(|bits| (1..=64).contains(bits))?;
+                let shift = 64 - bits;
+                Some(((unsigned(value)? << shift) as i64 >> shift).to_string())
+            }
+            SupplementalDataType::VhdlNatural | SupplementalDataType::VhdlPositive => {
+                Some(unsigned(value)?.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A bit string of up to 64 bits as number
+fn unsigned(value: &[u8]) -> Option<u64> {
+    if value.is_empty() || value.len() > 64 {
+        return None;
+    }
+    value.iter().try_fold(0, |number, bit| match bit {
+        b'0' => Some(number << 1),
+        b'1' => Some(number << 1 | 1),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_values() {
+        use SupplementalDataType::*;
+        assert_eq!(VhdlBoolean.display(b"1").as_deref(), Some("true"));
+        assert!(VhdlBoolean.display(b"x").is_none());
+        assert_eq!(VhdlCharacter.display(b"01000001").as_deref(), Some("'A'"));
+        assert_eq!(VhdlInteger.display(b"11111110").as_deref(), Some("-2"));
+        assert_eq!(VhdlInteger.display(b"0111").as_deref(), Some("7"));
+        assert_eq!(VhdlNatural.display(b"1010").as_deref(), Some("10"));
+        assert!(VhdlStdLogicVector.display(b"1010").is_none());
+    }
+}
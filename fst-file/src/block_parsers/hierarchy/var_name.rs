@@ -0,0 +1,210 @@
+use serde::Serialize;
+
+use super::Vcd;
+
+/// Bits of a vector from the most to the least significant one, like `[7:0]` or `[0:7]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BitRange {
+    pub msb: i64,
+    pub lsb: i64,
+}
+
+impl BitRange {
+    pub fn new(msb: i64, lsb: i64) -> Self {
+        Self { msb, lsb }
+    }
+
+    /// The usual range of a vector with `length` bits, `[length-1:0]`.
+    /// Lengths that do not fit are clamped to `[i64::MAX:0]`.
+    pub fn from_length(length: u64) -> Self {
+        Self::new(length.saturating_sub(1).min(i64::MAX as u64) as i64, 0)
+    }
+
+    /// Number of bits, `u64::MAX` for the one range that has more
+    pub fn width(&self) -> u64 {
+        self.msb.abs_diff(self.lsb).saturating_add(1)
+    }
+
+    /// Number of the bit at `position` of a value, where position 0 is the first character
+    pub fn bit(&self, position: u64) -> Option<i64> {
+        if position >= self.width() {
+            return None;
+        }
+        let (msb, position) = (i128::from(self.msb), i128::from(position));
+        let bit = if self.msb >= self.lsb {
+            msb - position
+        } else {
+            msb + position
+        };
+        i64::try_from(bit).ok()
+    }
+
+    /// Position of the bit number `bit` in a value, the inverse of [BitRange::bit]
+    pub fn position(&self, bit: i64) -> Option<u64> {
+        let lowest = self.msb.min(self.lsb);
+        let highest = self.msb.max(self.lsb);
+        (lowest..=highest)
+            .contains(&bit)
+            .then(|| self.msb.abs_diff(bit))
+    }
+
+    /// Bit numbers in the order of the characters of a value
+    pub fn bits(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..self.width()).filter_map(|position| self.bit(position))
+    }
+}
+
+/// The parts of a variable name like `mem[3][15:0]`.
+///
+/// Escaped identifiers start with `\` and end with a space, everything in between is the name,
+/// so `\a[1].b [3:0]` is the vector `a[1].b` with the range `[3:0]`.
+/// Names that do not follow this form are kept whole as the base name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VarName<'a> {
+    /// The name without the escape, indices and range
+    pub base: &'a str,
+    pub escaped: bool,
+    /// Indices of unpacked array dimensions from the outermost one, like `3` in `mem[3][15:0]`
+    pub indices: Vec<i64>,
+    /// The bit range given at the end of the name
+    pub range: Option<BitRange>,
+}
+
+impl<'a> VarName<'a> {
+    pub fn parse(name: &'a str) -> Self {
+        let whole = Self {
+            base: name,
+            escaped: false,
+            indices: Vec::new(),
+            range: None,
+        };
+        let (base, escaped, rest) = match name.strip_prefix('\\') {
+            Some(escaped) => match escaped.split_once(char::is_whitespace) {
+                Some((base, rest)) => (base, true, rest),
+                None => (escaped, true, ""),
+            },
+            None => match name.find(['[', ' ']) {
+                Some(start) => (&name[..start], false, &name[start..]),
+                None => (name, false, ""),
+            },
+        };
+        let Some((indices, range)) = parse_selects(rest.trim()) else {
+            return whole;
+        };
+        if base.is_empty() {
+            return whole;
+        }
+        Self {
+            base,
+            escaped,
+            indices,
+            range,
+        }
+    }
+
+    /// The bit range of a variable with `length` bits, `[length-1:0]` when the name gives none
+    pub fn bit_range(&self, length: u64) -> BitRange {
+        self.range.unwrap_or_else(|| BitRange::from_length(length))
+    }
+}
+
+/// Parse `[3][15:0]` into the indices and the range at the end
+fn parse_selects(mut text: &str) -> Option<(Vec<i64>, Option<BitRange>)> {
+    let mut indices = Vec::new();
+    let mut range = None;
+    while !text.is_empty() {
+        if range.is_some() {
+            return None;
+        }
+        let (select, rest) = text.strip_prefix('[')?.split_once(']')?;
+        match select.split_once(':') {
+            Some((msb, lsb)) => {
+                range = Some(BitRange::new(
+                    msb.trim().parse().ok()?,
+                    lsb.trim().parse().ok()?,
+                ))
+            }
+            None => indices.push(select.trim().parse().ok()?),
+        }
+        text = rest.trim_start();
+    }
+    Some((indices, range))
+}
+
+impl Vcd {
+    /// The name split into base name, array indices and bit range
+    pub fn parsed_name(&self) -> VarName<'_> {
+        VarName::parse(self.name())
+    }
+
+    /// Bit numbers of the variable, from the name or `[length-1:0]`
+    pub fn bit_range(&self) -> BitRange {
+        self.parsed_name().bit_range(self.length())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vectors_and_arrays() {
+        let name = VarName::parse("data[7:0]");
+        assert_eq!(name.base, "data");
+        assert!(name.indices.is_empty());
+        assert_eq!(name.range, Some(BitRange::new(7, 0)));
+
+        let name = VarName::parse("mem[3][15:0]");
+        assert_eq!(name.base, "mem");
+        assert_eq!(name.indices, [3]);
+        assert_eq!(name.range, Some(BitRange::new(15, 0)));
+
+        let name = VarName::parse("pc [31:0]");
+        assert_eq!(name.base, "pc");
+        assert_eq!(name.range.unwrap().width(), 32);
+
+        let name = VarName::parse("grid[1][-2]");
+        assert_eq!(name.indices, [1, -2]);
+        assert_eq!(name.range, None);
+        assert_eq!(name.bit_range(4), BitRange::new(3, 0));
+
+        // not a select, kept whole
+        assert_eq!(VarName::parse("a[7:0][1]").base, "a[7:0][1]");
+        assert_eq!(VarName::parse("a[i]").base, "a[i]");
+        assert_eq!(VarName::parse("[3]").base, "[3]");
+    }
+
+    #[test]
+    fn escaped_identifiers() {
+        let name = VarName::parse("\\esc.name ");
+        assert_eq!(name.base, "esc.name");
+        assert!(name.escaped);
+        assert_eq!(name.range, None);
+
+        let name = VarName::parse("\\a[1].b [3:0]");
+        assert_eq!(name.base, "a[1].b");
+        assert_eq!(name.range, Some(BitRange::new(3, 0)));
+        assert!(name.indices.is_empty());
+    }
+
+    #[test]
+    fn bit_numbering() {
+        let range = BitRange::new(3, 0);
+        assert_eq!(range.bits().collect::<Vec<_>>(), [3, 2, 1, 0]);
+        let range = BitRange::new(0, 3);
+        assert_eq!(range.bits().collect::<Vec<_>>(), [0, 1, 2, 3]);
+        let range = BitRange::new(8, 5);
+        assert_eq!(range.width(), 4);
+        assert_eq!(range.bit(1), Some(7));
+        assert_eq!(range.bit(4), None);
+        assert_eq!(range.position(7), Some(1));
+        assert_eq!(range.position(4), None);
+        assert_eq!(BitRange::new(0, 3).position(3), Some(3));
+
+        assert_eq!(BitRange::from_length(0), BitRange::new(0, 0));
+        assert_eq!(BitRange::from_length(u64::MAX), BitRange::new(i64::MAX, 0));
+        let range = BitRange::new(i64::MAX, i64::MIN);
+        assert_eq!(range.width(), u64::MAX);
+        assert_eq!(range.bit(u64::MAX - 1), Some(i64::MIN + 1));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{ptr, slice};
+
+use super::{Attribute, HierarchyContent, Scope, Variable};
+
+/// A scope or a variable in the hierarchy
+#[derive(Debug, Clone, Copy)]
+pub enum HierarchyNode<'a> {
+    Scope(&'a Scope),
+    Variable(&'a Variable),
+}
+
+impl<'a> HierarchyNode<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            HierarchyNode::Scope(scope) => scope.name(),
+            HierarchyNode::Variable(variable) => variable.vcd().name(),
+        }
+    }
+
+    /// Attributes that were active when the node was declared
+    pub fn attributes(&self) -> &'a [Attribute] {
+        match self {
+            HierarchyNode::Scope(scope) => scope.attributes(),
+            HierarchyNode::Variable(variable) => variable.attributes(),
+        }
+    }
+}
+
+/// Callbacks for [HierarchyContent::visit]
+pub trait HierarchyVisitor<'a> {
+    /// Called before the variables and child scopes of `scope`
+    fn enter_scope(&mut self, _scope: &'a Scope) {}
+
+    /// Called after the variables and child scopes of `scope`
+    fn leave_scope(&mut self, _scope: &'a Scope) {}
+
+    /// Called for every variable of the scope that was entered last,
+    /// or before any scope for variables outside of all scopes
+    fn variable(&mut self, _variable: &'a Variable) {}
+}
+
+/// Depth first iterator over the hierarchy, see [HierarchyContent::iter]
+pub struct HierarchyIter<'a> {
+    separator: char,
+    /// open scopes and the position in them, their names make up the path
+    stack: Vec<(&'a Scope, Position)>,
+    variables: slice::Iter<'a, Variable>,
+    roots: slice::Iter<'a, Scope>,
+}
+
+#[derive(Clone, Copy)]
+enum Position {
+    Variable(usize),
+    Scope(usize),
+}
+
+impl HierarchyIter<'_> {
+    /// Path of `name` inside of the open scopes
+    fn path(&self, name: &str) -> String {
+        let mut path = String::new();
+        for (scope, _) in &self.stack {
+            path.push_str(scope.name());
+            path.push(self.separator);
+        }
+        path.push_str(name);
+        path
+    }
+}
+
+impl<'a> Iterator for HierarchyIter<'a> {
+    type Item = (String, HierarchyNode<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(variable) = self.variables.next() {
+            let path = variable.vcd().name().to_string();
+            return Some((path, HierarchyNode::Variable(variable)));
+        }
+        loop {
+            let Some((scope, position)) = self.stack.last_mut() else {
+                let root = self.roots.next()?;
+                self.stack.push((root, Position::Variable(0)));
+                return Some((root.name().to_string(), HierarchyNode::Scope(root)));
+            };
+            let scope = *scope;
+            match *position {
+                Position::Variable(i) => match scope.variables().get(i) {
+                    Some(variable) => {
+                        *position = Position::Variable(i + 1);
+                        let path = self.path(variable.vcd().name());
+                        return Some((path, HierarchyNode::Variable(variable)));
+                    }
+                    None => *position = Position::Scope(0),
+                },
+                Position::Scope(i) => match scope.scopes().get(i) {
+                    Some(child) => {
+                        *position = Position::Scope(i + 1);
+                        let path = self.path(child.name());
+                        self.stack.push((child, Position::Variable(0)));
+                        return Some((path, HierarchyNode::Scope(child)));
+                    }
+                    None => {
+                        self.stack.pop();
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl HierarchyContent {
+    /// Iterate over all scopes and variables depth first together with their path.
+    ///
+    /// Variables outside of any scope come first, then the top level scopes in their order.
+    /// A scope comes before its variables and its variables before its child scopes.
+    /// The path is made of the scope names and the variable name joined with `.`.
+    pub fn iter(&self) -> HierarchyIter<'_> {
+        self.iter_with_separator('.')
+    }
+
+    /// Like [HierarchyContent::iter] with another separator in the path
+    pub fn iter_with_separator(&self, separator: char) -> HierarchyIter<'_> {
+        HierarchyIter {
+            separator,
+            stack: Vec::new(),
+            variables: self.variables().iter(),
+            roots: self.roots().iter(),
+        }
+    }
+
+    /// Walk the hierarchy depth first and call `visitor` for every scope and variable
+    pub fn visit<'a>(&'a self, visitor: &mut impl HierarchyVisitor<'a>) {
+        fn enter<'a>(
+            scope: &'a Scope,
+            visitor: &mut impl HierarchyVisitor<'a>,
+        ) -> (&'a Scope, slice::Iter<'a, Scope>) {
+            visitor.enter_scope(scope);
+            for variable in scope.variables() {
+                visitor.variable(variable);
+            }
+            (scope, scope.scopes().iter())
+        }
+        for variable in self.variables() {
+            visitor.variable(variable);
+        }
+        // entered scopes with the child scopes that are not visited yet,
+        // a stack instead of recursion because hierarchies can be very deep
+        let mut stack = Vec::new();
+        for root in self.roots() {
+            stack.push(enter(root, visitor));
+            while let Some((scope, children)) = stack.last_mut() {
+                match children.next() {
+                    Some(child) => stack.push(enter(child, visitor)),
+                    None => {
+                        visitor.leave_scope(scope);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// The scopes from the top level scope down to the one that declares `variable`.
+    /// Empty when `variable` is outside of all scopes or not part of this hierarchy.
+    ///
+    /// The tree has no links to parents, so every call searches the hierarchy and takes time
+    /// in the number of scopes and variables. Use [HierarchyContent::visit] or
+    /// [HierarchyContent::iter] to get the scopes of many variables.
+    pub fn ancestors(&self, variable: &Variable) -> Vec<&Scope> {
+        self.ancestors_of(HierarchyNode::Variable(variable))
+    }
+
+    /// The scope that declares `variable`, searched like in [HierarchyContent::ancestors]
+    pub fn parent(&self, variable: &Variable) -> Option<&Scope> {
+        self.ancestors(variable).pop()
+    }
+
+    /// The scope that contains `scope`, [None] for top level scopes.
+    /// Searched like in [HierarchyContent::ancestors].
+    pub fn parent_scope(&self, scope: &Scope) -> Option<&Scope> {
+        let mut ancestors = self.ancestors_of(HierarchyNode::Scope(scope));
+        ancestors.pop();
+        ancestors.pop()
+    }
+
+    /// Scopes from the top level down to the one that holds `node`, including `node` for scopes
+    fn ancestors_of(&self, node: HierarchyNode) -> Vec<&Scope> {
+        let holds = |scope: &Scope| match node {
+            HierarchyNode::Scope(target) => ptr::eq(scope, target),
+            HierarchyNode::Variable(target) => scope
+                .variables()
+                .iter()
+                .any(|variable| ptr::eq(variable, target)),
+        };
+        // the scopes on the way down with their child scopes that are not searched yet
+        let mut path: Vec<(&Scope, slice::Iter<'_, Scope>)> = Vec::new();
+        let mut roots = self.roots().iter();
+        loop {
+            let next = match path.last_mut() {
+                Some((_, children)) => children.next(),
+                None => match roots.next() {
+                    Some(root) => Some(root),
+                    None => return Vec::new(),
+                },
+            };
+            match next {
+                Some(scope) => {
+                    path.push((scope, scope.scopes().iter()));
+                    if holds(scope) {
+                        return path.into_iter().map(|(scope, _)| scope).collect();
+                    }
+                }
+                None => {
+                    path.pop();
+                }
+            }
+        }
+    }
+}
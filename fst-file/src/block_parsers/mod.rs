@@ -1,4 +1,4 @@
-use std::{fmt, io::Read};
+use std::fmt;
 
 use nom::{
     combinator::map_res,
@@ -7,10 +7,15 @@ use nom::{
     number::complete::be_u64,
     IResult,
 };
+use serde::Serialize;
 use thiserror::Error;
-use tracing::warn;
 
-use crate::{data_types::BlockType, FstParsable};
+use crate::{
+    as_usize,
+    data_types::{BlockLocation, BlockType, VarInt},
+    options::{Allocation, Anomaly, AnomalyKind, LimitError, ParseOptions},
+    FstParsable,
+};
 
 /// Blackout Block
 pub mod blackout;
@@ -28,6 +33,9 @@ pub mod value_change_data;
 pub struct Block {
     pub block_type: BlockType,
     data: Vec<u8>,
+    /// index and offset in the file
+    position: (usize, usize),
+    options: ParseOptions,
 }
 
 #[derive(Debug, Error)]
@@ -38,78 +46,197 @@ pub enum DecompressError {
     Lz4(#[from] lz4_flex::block::DecompressError),
     #[error("Io error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("could not read the length of the data compressed once")]
+    IntermediateLength,
+    #[error("FastLZ decompress error")]
+    FastLz,
+    #[error("block is too short to hold the uncompressed length")]
+    MissingLength,
+    #[error("uncompressed size {0} can not be the size of the compressed data")]
+    SizeTooLarge(u64),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitError),
 }
 
-impl Block {
-    fn extract_data_gz(&self) -> Result<Vec<u8>, DecompressError> {
-        let uncompressed_size =
-            usize::try_from(u64::from_be_bytes(self.data[..8].try_into().unwrap())).unwrap();
-        let mut decompressor = flate2::read::GzDecoder::new(&self.data[8..]);
-        let mut data = Vec::new();
-        decompressor.read_to_end(&mut data)?;
-        if data.len() != uncompressed_size {
-            warn!(
-                data_len = data.len(),
-                uncompressed_size = uncompressed_size,
-                "extracted data size did not match specified.",
-            );
+/// Decompress a LZ4 block, refusing sizes that the compressed data can not reach
+/// so a corrupted size does not allocate huge buffers
+pub(crate) fn decompress_lz4(
+    compressed: &[u8],
+    uncompressed_size: usize,
+) -> Result<Vec<u8>, DecompressError> {
+    if uncompressed_size > compressed.len().saturating_mul(LZ4_MAX_RATIO) {
+        return Err(DecompressError::SizeTooLarge(uncompressed_size as u64));
+    }
+    Ok(lz4_flex::block::decompress(compressed, uncompressed_size)?)
+}
+
+/// Uncompressed data that is not as long as the size stored in front of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeMismatch {
+    /// which data of the block
+    pub what: &'static str,
+    pub declared: usize,
+    pub actual: usize,
+}
+
+impl SizeMismatch {
+    pub(crate) fn compare(
+        what: &'static str,
+        declared: usize,
+        actual: usize,
+        mismatches: &mut Vec<SizeMismatch>,
+    ) {
+        if declared != actual {
+            mismatches.push(SizeMismatch {
+                what,
+                declared,
+                actual,
+            });
         }
+    }
+}
+
+impl fmt::Display for SizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is {} bytes but {} bytes were declared",
+            self.what, self.actual, self.declared
+        )
+    }
+}
+
+/// Largest ratio between the uncompressed and compressed size of a LZ4 block
+const LZ4_MAX_RATIO: usize = 255;
+
+impl Block {
+    /// The uncompressed size in front of the compressed data and the data after it
+    fn split_uncompressed_size(&self) -> Result<(usize, &[u8]), DecompressError> {
+        let (size, data) = self
+            .data
+            .split_first_chunk::<8>()
+            .ok_or(DecompressError::MissingLength)?;
+        let size = u64::from_be_bytes(*size);
+        let size = usize::try_from(size).map_err(|_| DecompressError::SizeTooLarge(size))?;
+        Ok((size, data))
+    }
+
+    fn extract_data_gz(
+        &self,
+        allocation: &mut Allocation,
+        mismatches: &mut Vec<SizeMismatch>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        let (uncompressed_size, compressed) = self.split_uncompressed_size()?;
+        let decompressor = flate2::read::GzDecoder::new(compressed);
+        let data = allocation.read_to_end("block data", decompressor)?;
+        SizeMismatch::compare("block data", uncompressed_size, data.len(), mismatches);
         Ok(data)
     }
 
-    fn extract_data_lz4(&self) -> Result<Vec<u8>, DecompressError> {
-        let uncompressed_size =
-            usize::try_from(u64::from_be_bytes(self.data[..8].try_into().unwrap())).unwrap();
-        let data = lz4_flex::block::decompress(&self.data[8..], uncompressed_size)?;
-        if data.len() != uncompressed_size {
-            warn!(
-                data_len = data.len(),
-                uncompressed_size = uncompressed_size,
-                "extracted data size did not match specified.",
-            );
-        }
+    fn extract_data_lz4(
+        &self,
+        allocation: &mut Allocation,
+        mismatches: &mut Vec<SizeMismatch>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        let (uncompressed_size, compressed) = self.split_uncompressed_size()?;
+        allocation.decompressed("block data", uncompressed_size)?;
+        let data = decompress_lz4(compressed, uncompressed_size)?;
+        SizeMismatch::compare("block data", uncompressed_size, data.len(), mismatches);
         Ok(data)
     }
 
-    fn extract_data_lz4_twice(&self) -> Result<Vec<u8>, DecompressError> {
-        let uncompressed_size =
-            usize::try_from(u64::from_be_bytes(self.data[..8].try_into().unwrap())).unwrap();
-        let uncompressed_once_size =
-            usize::try_from(u64::from_be_bytes(self.data[8..16].try_into().unwrap())).unwrap();
-        let data = lz4_flex::block::decompress(&self.data[8..], uncompressed_once_size)?;
-        if data.len() != uncompressed_once_size {
-            warn!(
-                data_len = data.len(),
-                uncompressed_size = uncompressed_once_size,
-                "first extracted data size did not match specified.",
-            );
-        }
-        let data2 = lz4_flex::block::decompress(&data, uncompressed_size)?;
-        if data2.len() != uncompressed_size {
-            warn!(
-                data_len = data2.len(),
-                uncompressed_size = uncompressed_size,
-                "second extracted data size did not match specified.",
-            );
-        }
+    fn extract_data_lz4_twice(
+        &self,
+        allocation: &mut Allocation,
+        mismatches: &mut Vec<SizeMismatch>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        let (uncompressed_size, compressed) = self.split_uncompressed_size()?;
+        let (data, uncompressed_once_size) =
+            as_usize(VarInt::parse)(compressed).map_err(|_| DecompressError::IntermediateLength)?;
+        allocation.decompressed("block data after the first pass", uncompressed_once_size)?;
+        allocation.decompressed("block data", uncompressed_size)?;
+        let data = decompress_lz4(data, uncompressed_once_size)?;
+        SizeMismatch::compare(
+            "block data after the first pass",
+            uncompressed_once_size,
+            data.len(),
+            mismatches,
+        );
+        let data2 = decompress_lz4(&data, uncompressed_size)?;
+        SizeMismatch::compare("block data", uncompressed_size, data2.len(), mismatches);
         Ok(data2)
     }
 
     /// Extracts data from block.
     /// If the block content is compressed, it will be uncompressed in this function.
+    /// Sizes of uncompressed data that do not match the declared sizes are reported
+    /// as anomalies, see [ParseOptions].
     pub fn extract_data(&self) -> Result<Vec<u8>, DecompressError> {
-        Ok(match self.block_type {
-            BlockType::Header => self.data.to_vec(),
-            BlockType::ValueChangeData => self.data.to_vec(),
-            BlockType::Blackout => self.data.to_vec(),
-            BlockType::Geometry => self.data.to_vec(),
-            BlockType::HierarchyGz => self.extract_data_gz()?,
-            BlockType::ValueChangeDataAlias => self.data.to_vec(),
-            BlockType::HierarchyLz4 => self.extract_data_lz4()?,
-            BlockType::HierarchyLz4Duo => self.extract_data_lz4_twice()?,
-            BlockType::ValueChangeDataAlias2 => self.data.to_vec(),
-            BlockType::GZippedWrapper => self.extract_data_gz()?,
-            BlockType::Skip => self.data.to_vec(),
+        let (data, mismatches) = self.extract_data_with_sizes()?;
+        for mismatch in mismatches {
+            self.report(AnomalyKind::SizeMismatch(mismatch))?;
+        }
+        Ok(data)
+    }
+
+    /// Like [Block::extract_data], but the sizes of uncompressed data that do not match
+    /// the declared sizes are returned instead of reported
+    pub fn extract_data_with_sizes(&self) -> Result<(Vec<u8>, Vec<SizeMismatch>), DecompressError> {
+        let mut mismatches = Vec::new();
+        let mut allocation = self.allocation();
+        let data = match self.block_type {
+            BlockType::HierarchyGz | BlockType::GZippedWrapper => {
+                self.extract_data_gz(&mut allocation, &mut mismatches)?
+            }
+            BlockType::HierarchyLz4 => self.extract_data_lz4(&mut allocation, &mut mismatches)?,
+            BlockType::HierarchyLz4Duo => {
+                self.extract_data_lz4_twice(&mut allocation, &mut mismatches)?
+            }
+            BlockType::Header
+            | BlockType::ValueChangeData
+            | BlockType::Blackout
+            | BlockType::Geometry
+            | BlockType::ValueChangeDataAlias
+            | BlockType::ValueChangeDataAlias2
+            | BlockType::Skip => self.data.to_vec(),
+        };
+        Ok((data, mismatches))
+    }
+
+    /// Where the block is in the file it was parsed from
+    pub fn location(&self) -> BlockLocation {
+        BlockLocation {
+            index: self.position.0,
+            offset: self.position.1,
+            block_type: Some(self.block_type),
+        }
+    }
+
+    pub(crate) fn set_position(&mut self, index: usize, offset: usize) {
+        self.position = (index, offset);
+    }
+
+    /// The options the block is parsed with
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    pub(crate) fn set_options(&mut self, options: ParseOptions) {
+        self.options = options;
+    }
+
+    /// A new allocation for reading the block, bounded by the limits of its options
+    pub(crate) fn allocation(&self) -> Allocation {
+        Allocation::new(self.options.limits)
+    }
+
+    /// Fail with `kind` in strict mode, add it to the diagnostics otherwise
+    pub(crate) fn report(&self, kind: AnomalyKind) -> Result<(), Anomaly> {
+        self.options.report(Anomaly {
+            block: self.location(),
+            kind,
         })
     }
 
@@ -125,20 +252,25 @@ impl Block {
             map_res(be_u64, |v| {
                 v.checked_sub(8)
                     .map(|v| v as usize)
-                    .ok_or_else(|| (input, ErrorKind::Verify))
+                    .ok_or((input, ErrorKind::Verify))
             }),
         )(input)
     }
 
     pub(crate) fn parse_block_with_position(
         input: &[u8],
-    ) -> IResult<&[u8], (Span, Self), VerboseError<&[u8]>> {
+    ) -> IResult<&[u8], (Span<'_>, Self), VerboseError<&[u8]>> {
         let original_input = input;
         let (input, block_type) = context("block type", BlockType::parse)(input)?;
         let (input, data) =
             context("block data length", length_data(Block::parse_block_length))(input)?;
         let data = data.to_vec();
-        let block = Block { block_type, data };
+        let block = Block {
+            block_type,
+            data,
+            position: (0, 0),
+            options: ParseOptions::default(),
+        };
         Ok((input, ((original_input, input), block)))
     }
 }
@@ -1,7 +1,11 @@
-use std::{borrow::Cow, io::Read};
+use std::borrow::Cow;
 
 use nom::{
-    bytes::complete::take, error::VerboseErrorKind, multi::many_m_n, number::complete::be_u64,
+    bytes::complete::take,
+    error::{ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    multi::many_m_n,
+    number::complete::be_u64,
+    sequence::tuple,
     Finish,
 };
 use serde::Serialize;
@@ -9,13 +13,20 @@ use thiserror::Error;
 use tracing::debug_span;
 
 use crate::{
-    as_usize,
-    data_types::{BlockType, SVarInt, VarInt, WriterPackType},
+    as_usize, convert_type,
+    data_types::{BlockType, Handle, SVarInt, VarInt, WriterPackType},
     error::{ParseResult, PositionError},
-    FstParsable, convert_type,
+    fastlz,
+    options::{decompress_failed, within_limits, Allocation, Anomaly, AnomalyKind, LimitError},
+    FstParsable,
 };
 
-use super::{header::HeaderBlockContent, Block};
+use super::{
+    decompress_lz4,
+    geometry::{Geometry, SignalGeometry},
+    header::HeaderBlockContent,
+    Block, DecompressError, SizeMismatch,
+};
 
 #[derive(Debug)]
 pub struct ValueChangeDataBlock(Block);
@@ -31,6 +42,19 @@ pub struct ValueChangeDataIntermediate {
     wave_data_raw: Vec<u8>,
     waves_packtype: WriterPackType,
     waves_count: usize,
+    /// uncompressed frame and time table sizes that do not match the declared sizes
+    size_mismatches: Vec<SizeMismatch>,
+}
+
+impl ValueChangeDataIntermediate {
+    pub fn size_mismatches(&self) -> &[SizeMismatch] {
+        &self.size_mismatches
+    }
+
+    /// Compression of the value changes of the signals
+    pub fn waves_pack_type(&self) -> WriterPackType {
+        self.waves_packtype
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -40,12 +64,54 @@ pub struct ValueChangeData {
     chain_table_lengths: Vec<u32>,
 }
 
+/// Value changes of one signal inside a value change data block
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SignalValueChanges {
+    /// Value at the start of the block. Empty for variable length signals.
+    pub initial: Vec<u8>,
+    /// Time and value of every change in the block
+    pub changes: Vec<(u64, Vec<u8>)>,
+}
+
+/// Decoded value changes of a value change data block.
+///
+/// Values are stored the same way as [crate::writer::FstWriter::emit_value_change] takes them:
+/// one character per bit for bit vectors, 8 bytes for reals and the raw bytes for variable length signals.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValueChanges {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub time_table: Vec<u64>,
+    /// Indexed by [Handle::index]. [None] for signals that were not selected.
+    pub signals: Vec<Option<SignalValueChanges>>,
+}
+
+impl ValueChanges {
+    pub fn get(&self, handle: Handle) -> Option<&SignalValueChanges> {
+        self.signals.get(handle.index()?)?.as_ref()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ValueChangeDataError {
     #[error("parse error {0}")]
     ParseError(#[from] PositionError<VerboseErrorKind>),
     #[error("interger convert to other type error {0}")]
     TryFromIntError(#[from] std::num::TryFromIntError),
+    #[error("error during uncompressing value changes: {0}")]
+    Decompress(#[from] DecompressError),
+    #[error("block has {block} signals but the geometry has {geometry}")]
+    SignalCountMismatch { block: usize, geometry: usize },
+    #[error("malformed value changes of {handle:?}")]
+    MalformedWave { handle: Handle },
+    #[error("malformed position table")]
+    MalformedPositions,
+    #[error("value changes of {0} blocks can not be decoded into a chain table")]
+    UnsupportedBlockType(BlockType),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitError),
 }
 
 impl ValueChangeDataBlock {
@@ -53,17 +119,54 @@ impl ValueChangeDataBlock {
         Self(block)
     }
 
+    pub fn get_block(&self) -> &Block {
+        &self.0
+    }
+
+    /// Start and end time of the block without decoding the rest of it
+    pub fn get_time_range(&self) -> Result<(u64, u64), ValueChangeDataError> {
+        let data = self.0.get_data_raw();
+        let (_, (start_time, end_time)) = tuple((be_u64, be_u64))(data).finish().map_err(|e| {
+            PositionError::from_verbose_parse_error(e, data).in_block(self.0.location())
+        })?;
+        Ok((start_time, end_time))
+    }
+
+    /// The sections of the block, sizes that do not match the declared sizes are
+    /// returned in [ValueChangeDataIntermediate::size_mismatches] instead of reported
     pub fn get_intermediate_content(
         &self,
         _header_content: &HeaderBlockContent,
     ) -> Result<ValueChangeDataIntermediate, ValueChangeDataError> {
         let _span = debug_span!("get_intermediate_content").entered();
+        self.intermediate(&mut self.0.allocation())
+    }
+
+    fn intermediate(
+        &self,
+        allocation: &mut Allocation,
+    ) -> Result<ValueChangeDataIntermediate, ValueChangeDataError> {
         let data = self.0.get_data_raw();
-        Ok(self
-            .parse_value_change_data(data)
+        self.parse_value_change_data(data, allocation)
             .finish()
             .map(|(_, v)| v)
-            .map_err(|e| PositionError::from_verbose_parse_error(e, data))?)
+            .map_err(|e| match allocation.exceeded() {
+                Some(limit) => limit.into(),
+                None => PositionError::from_verbose_parse_error(e, data)
+                    .in_block(self.0.location())
+                    .into(),
+            })
+    }
+
+    /// Report the size mismatches of the sections as anomalies
+    fn report_sizes(
+        &self,
+        intermediate: &ValueChangeDataIntermediate,
+    ) -> Result<(), ValueChangeDataError> {
+        for mismatch in &intermediate.size_mismatches {
+            self.0.report(AnomalyKind::SizeMismatch(mismatch.clone()))?;
+        }
+        Ok(())
     }
 
     pub fn get_content(
@@ -72,81 +175,111 @@ impl ValueChangeDataBlock {
     ) -> Result<ValueChangeData, ValueChangeDataError> {
         let _span = debug_span!("get_content").entered();
         let intermediate = self.get_intermediate_content(header_content)?;
+        self.report_sizes(&intermediate)?;
 
         let mut time_data = vec![0; intermediate.time_data.len()];
         let mut previous_time_value = 0;
         for (i, t) in intermediate.time_data.iter().enumerate() {
-            previous_time_value += t.0;
+            previous_time_value = u64::wrapping_add(previous_time_value, t.0);
             time_data[i] = previous_time_value;
         }
 
         // get chain_table and chain_table_length
-        assert_eq!(self.0.block_type, BlockType::ValueChangeDataAlias2);
+        if self.0.block_type != BlockType::ValueChangeDataAlias2 {
+            return Err(ValueChangeDataError::UnsupportedBlockType(
+                self.0.block_type,
+            ));
+        }
         let vc_max_handle = intermediate.waves_count;
-        let mut chain_table = vec![0; vc_max_handle + 1];
-        let mut chain_table_lengths = vec![0u32; vc_max_handle + 1];
-        let mut pval = 0;
+        // grown while reading, so a wrong signal count does not allocate huge tables
+        let mut chain_table: Vec<i64> = vec![0];
+        let mut chain_table_lengths = vec![0u32];
+        let mut pval = 0i64;
         let mut pidx = 0;
         let mut prev_alias = 0;
         let mut position_data_ptr = &intermediate.position_data_raw[..];
         let mut idx = 0;
-        loop {
-            if position_data_ptr[0] & 1 != 0 {
-                let (t, val) = SVarInt::parse(position_data_ptr).finish().map_err(|e|PositionError::from_verbose_parse_error(e, &intermediate.position_data_raw[..]))?;
+        let malformed = || ValueChangeDataError::MalformedPositions;
+        // move on to the next entry of the tables
+        let next =
+            |idx: &mut usize, chain_table: &mut Vec<i64>, chain_table_lengths: &mut Vec<u32>| {
+                *idx += 1;
+                if *idx > vc_max_handle {
+                    return Err(malformed());
+                }
+                chain_table.push(0);
+                chain_table_lengths.push(0);
+                Ok(())
+            };
+        while let Some(first) = position_data_ptr.first() {
+            if first & 1 != 0 {
+                let (t, val) = SVarInt::parse(position_data_ptr).finish().map_err(|e| {
+                    PositionError::from_verbose_parse_error(e, &intermediate.position_data_raw[..])
+                        .in_extracted(self.0.location())
+                })?;
                 position_data_ptr = t;
                 let shval = val.0 >> 1;
                 match shval {
                     shval if shval > 0 => {
-                        pval += shval;
+                        pval = pval.checked_add(shval).ok_or_else(malformed)?;
                         chain_table[idx] = pval;
 
                         if idx != 0 {
                             chain_table_lengths[pidx] = u32::try_from(pval - chain_table[pidx])?;
                         }
                         pidx = idx;
-                        idx += 1;
                     }
                     shval if shval < 0 => {
-                        chain_table[idx] = 0;
-                        prev_alias = u32::try_from(shval)?;
-                        chain_table_lengths[idx] = u32::try_from(shval)?;
-                        idx += 1;
+                        // dynamic alias to the signal at index -shval - 1
+                        prev_alias = u32::try_from(-shval - 1)?;
+                        chain_table_lengths[idx] = prev_alias;
                     }
                     _ => {
-                        chain_table[idx] = 0;
                         chain_table_lengths[idx] = prev_alias;
-                        idx += 1;
                     }
                 }
+                next(&mut idx, &mut chain_table, &mut chain_table_lengths)?;
             } else {
-                let (t, val):(_,u32) = convert_type(VarInt::parse)(position_data_ptr).finish().map_err(|e|PositionError::from_verbose_parse_error(e, &intermediate.position_data_raw[..]))?;
+                let (t, val): (_, u32) = convert_type(VarInt::parse)(position_data_ptr)
+                    .finish()
+                    .map_err(|e| {
+                        PositionError::from_verbose_parse_error(
+                            e,
+                            &intermediate.position_data_raw[..],
+                        )
+                        .in_extracted(self.0.location())
+                    })?;
                 position_data_ptr = t;
                 let loopcnt = val >> 1;
                 for _i in 0..loopcnt {
-                    chain_table[idx] = 0;
-                    idx += 1;
+                    next(&mut idx, &mut chain_table, &mut chain_table_lengths)?;
                 }
             }
-            if position_data_ptr.is_empty() {
-                break;
-            }
         }
         chain_table[idx] = intermediate.wave_data_raw.len() as i64 + 1;
         chain_table_lengths[pidx] = u32::try_from(chain_table[idx] - chain_table[pidx])?;
+        // signals after the last entry have no changes
+        let missing = vc_max_handle + 1 - chain_table.len();
+        chain_table
+            .try_reserve_exact(missing)
+            .and_then(|_| chain_table_lengths.try_reserve_exact(missing))
+            .map_err(|_| malformed())?;
+        chain_table.resize(vc_max_handle + 1, 0);
+        chain_table_lengths.resize(vc_max_handle + 1, 0);
 
         // This check was implemented in gtk wave as a sanity check.
         // since this implementation cannot have negative values as length
         // for i in 0..idx {
         //     let mut v32 = chain_table_lengths[i];
-            // if (v32 < 0) && (chain_table[i] != 0) {
-            //     v32 = -v32;
-            //     v32 -= 1;
-            //     let v32: usize = v32.try_into().unwrap();
-            //     if v32 < i {
-            //         chain_table[i] = chain_table[v32];
-            //         chain_table_lengths[i] = chain_table_lengths[v32];
-            //     }
-            // }
+        // if (v32 < 0) && (chain_table[i] != 0) {
+        //     v32 = -v32;
+        //     v32 -= 1;
+        //     let v32: usize = v32.try_into().unwrap();
+        //     if v32 < i {
+        //         chain_table[i] = chain_table[v32];
+        //         chain_table_lengths[i] = chain_table_lengths[v32];
+        //     }
+        // }
         // }
 
         // for i in 0..idx {
@@ -157,8 +290,6 @@ impl ValueChangeDataBlock {
         //     }
         // }
 
-        
-
         Ok(ValueChangeData {
             time_data,
             chain_table,
@@ -166,6 +297,191 @@ impl ValueChangeDataBlock {
         })
     }
 
+    /// Decode the value changes of the signals for which `filter` returns true.
+    pub fn get_value_changes(
+        &self,
+        geometry: &Geometry,
+        filter: impl Fn(Handle) -> bool,
+    ) -> Result<ValueChanges, ValueChangeDataError> {
+        let _span = debug_span!("get_value_changes").entered();
+        let mut allocation = self.0.allocation();
+        let intermediate = self.intermediate(&mut allocation)?;
+        self.report_sizes(&intermediate)?;
+        if intermediate.waves_count != geometry.len() {
+            return Err(ValueChangeDataError::SignalCountMismatch {
+                block: intermediate.waves_count,
+                geometry: geometry.len(),
+            });
+        }
+
+        let time_table: Vec<u64> = intermediate
+            .time_data
+            .iter()
+            .scan(0u64, |time, delta| {
+                *time = time.wrapping_add(delta.0);
+                Some(*time)
+            })
+            .collect();
+        let locations = self.wave_locations(&intermediate)?;
+
+        let mut frame_offset = 0;
+        let mut signals = Vec::with_capacity(geometry.len());
+        for (index, signal_geometry) in geometry.iter().enumerate() {
+            let handle = Handle::from_index(index);
+            let frame_size = signal_geometry.frame_size();
+            let frame_range = frame_offset..frame_offset + frame_size;
+            frame_offset += frame_size;
+            if !filter(handle) {
+                signals.push(None);
+                continue;
+            }
+            let initial = intermediate
+                .bits_data
+                .get(frame_range)
+                .ok_or(ValueChangeDataError::MalformedWave { handle })?
+                .to_vec();
+            let changes = match locations[index] {
+                WaveLocation::Empty => Vec::new(),
+                // the wave data is shared, but it has to be decoded with the geometry of this signal
+                WaveLocation::Alias(source) => match signals.get(source) {
+                    Some(Some(SignalValueChanges { changes, .. }))
+                        if geometry.get(Handle::from_index(source)) == Some(signal_geometry) =>
+                    {
+                        claim_copy(changes, &mut allocation)?;
+                        changes.clone()
+                    }
+                    _ => {
+                        let (position, length) = resolve_alias(&locations, source)
+                            .ok_or(ValueChangeDataError::MalformedPositions)?;
+                        decode_wave(
+                            &intermediate,
+                            &time_table,
+                            signal_geometry,
+                            handle,
+                            position,
+                            length,
+                            &mut allocation,
+                        )?
+                    }
+                },
+                WaveLocation::Data { position, length } => decode_wave(
+                    &intermediate,
+                    &time_table,
+                    signal_geometry,
+                    handle,
+                    position,
+                    length,
+                    &mut allocation,
+                )?,
+            };
+            signals.push(Some(SignalValueChanges { initial, changes }));
+        }
+
+        Ok(ValueChanges {
+            start_time: intermediate.start_time,
+            end_time: intermediate.end_time,
+            time_table,
+            signals,
+        })
+    }
+
+    /// Check that the position table only points into the wave data of the block
+    /// and that aliases refer to signals of the block
+    pub fn check_positions(&self) -> Result<(), ValueChangeDataError> {
+        let intermediate = self.intermediate(&mut self.0.allocation())?;
+        let locations = self.wave_locations(&intermediate)?;
+        let in_bounds = |location: &WaveLocation| match *location {
+            WaveLocation::Empty => true,
+            WaveLocation::Alias(source) => source < locations.len(),
+            WaveLocation::Data { position, length } => position
+                .checked_sub(1)
+                .and_then(|start| start.checked_add(length))
+                .is_some_and(|end| end <= intermediate.wave_data_raw.len()),
+        };
+        if locations.iter().all(in_bounds) {
+            Ok(())
+        } else {
+            Err(ValueChangeDataError::MalformedPositions)
+        }
+    }
+
+    /// Read the position table into the location of the wave data of each signal
+    fn wave_locations(
+        &self,
+        intermediate: &ValueChangeDataIntermediate,
+    ) -> Result<Vec<WaveLocation>, ValueChangeDataError> {
+        let count = intermediate.waves_count;
+        let end = intermediate.wave_data_raw.len() + 1;
+        let mut locations = Vec::with_capacity(count);
+        let mut previous_position = 0usize;
+        let mut previous_data_index = None;
+        let mut previous_alias = None;
+        let mut input = &intermediate.position_data_raw[..];
+
+        let malformed = |_| ValueChangeDataError::MalformedPositions;
+        while !input.is_empty() && locations.len() < count {
+            let alias2 = self.0.block_type == BlockType::ValueChangeDataAlias2;
+            if alias2 && input[0] & 1 == 1 {
+                let (rest, value) = SVarInt::parse(input).finish().map_err(malformed)?;
+                input = rest;
+                let shval = value.0 >> 1;
+                match shval {
+                    shval if shval > 0 => {
+                        let position = usize::try_from(shval)
+                            .ok()
+                            .and_then(|s| previous_position.checked_add(s))
+                            .ok_or(ValueChangeDataError::MalformedPositions)?;
+                        close_location(&mut locations, previous_data_index, position)?;
+                        previous_position = position;
+                        previous_data_index = Some(locations.len());
+                        locations.push(WaveLocation::Data {
+                            position,
+                            length: 0,
+                        });
+                    }
+                    shval if shval < 0 => {
+                        let source = usize::try_from(-shval - 1)
+                            .map_err(|_| ValueChangeDataError::MalformedPositions)?;
+                        previous_alias = Some(source);
+                        locations.push(WaveLocation::Alias(source));
+                    }
+                    _ => locations.push(WaveLocation::Alias(
+                        previous_alias.ok_or(ValueChangeDataError::MalformedPositions)?,
+                    )),
+                }
+            } else {
+                let (rest, value) = VarInt::parse(input).finish().map_err(malformed)?;
+                input = rest;
+                if alias2 || value.0 & 1 == 0 && value.0 != 0 {
+                    let zeros = usize::try_from(value.0 >> 1)?;
+                    let zeros = zeros.min(count - locations.len());
+                    locations.extend(std::iter::repeat_n(WaveLocation::Empty, zeros));
+                } else if value.0 == 0 {
+                    let (rest, alias) = VarInt::parse(input).finish().map_err(malformed)?;
+                    input = rest;
+                    let source = usize::try_from(alias.0)?
+                        .checked_sub(1)
+                        .ok_or(ValueChangeDataError::MalformedPositions)?;
+                    locations.push(WaveLocation::Alias(source));
+                } else {
+                    let position = usize::try_from(value.0 >> 1)?
+                        .checked_add(previous_position)
+                        .ok_or(ValueChangeDataError::MalformedPositions)?;
+                    close_location(&mut locations, previous_data_index, position)?;
+                    previous_position = position;
+                    previous_data_index = Some(locations.len());
+                    locations.push(WaveLocation::Data {
+                        position,
+                        length: 0,
+                    });
+                }
+            }
+        }
+        close_location(&mut locations, previous_data_index, end)?;
+        locations.resize(count, WaveLocation::Empty);
+        Ok(locations)
+    }
+
     // pub fn get_parsed_data(&self) ->  {
     //     self.value_change_data.get_or_init(move || {
     //         self.parse_value_change_data(self.block.data)
@@ -178,7 +494,8 @@ impl ValueChangeDataBlock {
     fn parse_value_change_data<'a>(
         &'a self,
         input: &'a [u8],
-    ) -> ParseResult<ValueChangeDataIntermediate> {
+        allocation: &mut Allocation,
+    ) -> ParseResult<'a, ValueChangeDataIntermediate> {
         let (input, start_time) = be_u64(input)?;
         let (input, end_time) = be_u64(input)?;
         let (input, memory_required) = be_u64(input)?;
@@ -194,20 +511,30 @@ impl ValueChangeDataBlock {
         //     &bits_compressed_length,
         //     &bits_count
         // );
+        let mut size_mismatches = Vec::new();
         let bits_data = if bits_compressed_length == bits_uncompressed_len {
+            within_limits(bits_data_raw, allocation.claim(bits_data_raw.len()))?;
             bits_data_raw.to_vec()
         } else {
-            let mut decoder = flate2::read::ZlibDecoder::new(bits_data_raw);
-            let mut buf = Vec::new();
-            decoder.read_to_end(&mut buf).unwrap();
-            buf
+            let bits_data = inflate(bits_data_raw, "frame", allocation)?;
+            SizeMismatch::compare(
+                "frame",
+                bits_uncompressed_len,
+                bits_data.len(),
+                &mut size_mismatches,
+            );
+            bits_data
         };
         // dbg!(&bits_data);
         let (input, waves_count) = as_usize(VarInt::parse)(input)?;
+        within_limits(
+            input,
+            allocation.elements::<WaveLocation>("signals", waves_count),
+        )?;
         let (input, waves_packtype) = WriterPackType::parse(input)?;
         // dbg!(&waves_count, &waves_packtype);
 
-        let (input, input_end) = input.split_at(input.len() - 24);
+        let (input, input_end) = split_end(input, 24)?;
         let (input_end, time_uncompressed_length) = as_usize(be_u64)(input_end)?;
         let (input_end, time_compressed_length) = as_usize(be_u64)(input_end)?;
         let (_, time_count) = as_usize(be_u64)(input_end)?;
@@ -217,23 +544,44 @@ impl ValueChangeDataBlock {
         //     &time_count
         // );
 
-        let (input, time_data_raw) = input.split_at(input.len() - time_compressed_length);
+        let (input, time_data_raw) = split_end(input, time_compressed_length)?;
         let time_data_buf = if time_compressed_length == time_uncompressed_length {
             Cow::Borrowed(time_data_raw)
         } else {
-            let mut decoder = flate2::read::ZlibDecoder::new(time_data_raw);
-            let mut buf = Vec::new();
-            decoder.read_to_end(&mut buf).unwrap();
-            Cow::Owned(buf)
+            let time_data = inflate(time_data_raw, "time table", allocation)?;
+            SizeMismatch::compare(
+                "time table",
+                time_uncompressed_length,
+                time_data.len(),
+                &mut size_mismatches,
+            );
+            Cow::Owned(time_data)
         };
-        let (_, time_data) =
-            many_m_n(time_count, time_count, VarInt::parse)(&time_data_buf).unwrap();
+        // every time takes at least one byte
+        if time_count > time_data_buf.len() {
+            return Err(nom::Err::Error(VerboseError::from_error_kind(
+                time_data_raw,
+                ErrorKind::Count,
+            )));
+        }
+        within_limits(
+            time_data_raw,
+            allocation.elements::<VarInt>("times", time_count),
+        )?;
+        let (_, time_data) = many_m_n(time_count, time_count, VarInt::parse)(&time_data_buf)
+            .map_err(|_| {
+                nom::Err::Error(VerboseError::from_error_kind(
+                    time_data_raw,
+                    ErrorKind::Count,
+                ))
+            })?;
         // dbg!(&time_data);
 
-        let (input, input_end) = input.split_at(input.len() - 8);
+        let (input, input_end) = split_end(input, 8)?;
         let (_, position_length) = as_usize(be_u64)(input_end)?;
         // dbg!(&position_length);
-        let (waves_data_raw, position_data_raw) = input.split_at(input.len() - position_length);
+        let (waves_data_raw, position_data_raw) = split_end(input, position_length)?;
+        within_limits(input, allocation.claim(input.len()))?;
 
         let vcd = ValueChangeDataIntermediate {
             start_time,
@@ -245,11 +593,170 @@ impl ValueChangeDataBlock {
             wave_data_raw: waves_data_raw.to_vec(),
             waves_count,
             waves_packtype,
+            size_mismatches,
         };
         Ok((input, vcd))
     }
 }
 
+/// Split the last `length` bytes off the end of `input`
+fn split_end(input: &[u8], length: usize) -> ParseResult<'_, &[u8]> {
+    match input.len().checked_sub(length) {
+        Some(at) => Ok(input.split_at(at)),
+        None => Err(nom::Err::Error(VerboseError::from_error_kind(
+            input,
+            ErrorKind::Eof,
+        ))),
+    }
+}
+
+/// Uncompress zlib data of a value change block
+fn inflate<'a>(
+    input: &'a [u8],
+    what: &'static str,
+    allocation: &mut Allocation,
+) -> Result<Vec<u8>, nom::Err<VerboseError<&'a [u8]>>> {
+    let decoder = flate2::read::ZlibDecoder::new(input);
+    allocation
+        .read_to_end(what, decoder)
+        .map_err(|e| decompress_failed(input, e))
+}
+
+/// Where the wave data of a signal is, relative to the pack type byte
+#[derive(Debug, Clone, Copy)]
+enum WaveLocation {
+    Empty,
+    /// Same value changes as the signal with the index
+    Alias(usize),
+    Data {
+        position: usize,
+        length: usize,
+    },
+}
+
+/// Set the length of the previous data location now that the next position is known
+fn close_location(
+    locations: &mut [WaveLocation],
+    previous: Option<usize>,
+    next_position: usize,
+) -> Result<(), ValueChangeDataError> {
+    if let Some(WaveLocation::Data { position, length }) =
+        previous.and_then(|i| locations.get_mut(i))
+    {
+        *length = next_position
+            .checked_sub(*position)
+            .ok_or(ValueChangeDataError::MalformedPositions)?;
+    }
+    Ok(())
+}
+
+fn resolve_alias(locations: &[WaveLocation], source: usize) -> Option<(usize, usize)> {
+    match locations.get(source)? {
+        WaveLocation::Data { position, length } => Some((*position, *length)),
+        _ => None,
+    }
+}
+
+/// Uncompress and decode the wave data of one signal
+fn decode_wave(
+    intermediate: &ValueChangeDataIntermediate,
+    time_table: &[u64],
+    geometry: SignalGeometry,
+    handle: Handle,
+    position: usize,
+    length: usize,
+    allocation: &mut Allocation,
+) -> Result<Vec<(u64, Vec<u8>)>, ValueChangeDataError> {
+    let malformed = || ValueChangeDataError::MalformedWave { handle };
+    let packed = position
+        .checked_sub(1)
+        .and_then(|start| intermediate.wave_data_raw.get(start..start + length))
+        .ok_or_else(malformed)?;
+    let (packed, uncompressed_length) = as_usize(VarInt::parse)(packed)
+        .finish()
+        .map_err(|_| malformed())?;
+    let wave = if uncompressed_length == 0 {
+        Cow::Borrowed(packed)
+    } else {
+        Cow::Owned(match intermediate.waves_packtype {
+            WriterPackType::Zlib => {
+                let decoder = flate2::read::ZlibDecoder::new(packed);
+                allocation.read_to_end("wave data", decoder)?
+            }
+            WriterPackType::FaslLz => {
+                allocation.decompressed("wave data", uncompressed_length)?;
+                fastlz::decompress(packed, uncompressed_length).ok_or(DecompressError::FastLz)?
+            }
+            WriterPackType::Lz4 => {
+                allocation.decompressed("wave data", uncompressed_length)?;
+                decompress_lz4(packed, uncompressed_length)?
+            }
+        })
+    };
+
+    let mut input = &wave[..];
+    let mut time_index = 0usize;
+    let mut changes = Vec::new();
+    while !input.is_empty() {
+        let (rest, vli) = VarInt::parse(input).finish().map_err(|_| malformed())?;
+        input = rest;
+        let vli = vli.0;
+        let (delta, value) = match geometry {
+            SignalGeometry::Bits(1) => {
+                if vli & 1 == 0 {
+                    (vli >> 2, vec![b'0' + ((vli >> 1) & 1) as u8])
+                } else {
+                    (vli >> 4, vec![b"xzhuwl-?"[((vli >> 1) & 7) as usize]])
+                }
+            }
+            SignalGeometry::Bits(bits) => {
+                let bits = bits as usize;
+                let value = if vli & 1 == 0 {
+                    let bytes = input.get(..bits.div_ceil(8)).ok_or_else(malformed)?;
+                    input = &input[bytes.len()..];
+                    (0..bits)
+                        .map(|i| b'0' + ((bytes[i / 8] >> (7 - i % 8)) & 1))
+                        .collect()
+                } else {
+                    let value = input.get(..bits).ok_or_else(malformed)?.to_vec();
+                    input = &input[bits..];
+                    value
+                };
+                (vli >> 1, value)
+            }
+            SignalGeometry::Real => {
+                let value = input.get(..8).ok_or_else(malformed)?.to_vec();
+                input = &input[8..];
+                (vli >> 1, value)
+            }
+            SignalGeometry::VariableLength => {
+                let (rest, len) = as_usize(VarInt::parse)(input)
+                    .finish()
+                    .map_err(|_| malformed())?;
+                let value = rest.get(..len).ok_or_else(malformed)?.to_vec();
+                input = &rest[len..];
+                (vli >> 1, value)
+            }
+        };
+        time_index = usize::try_from(delta)
+            .ok()
+            .and_then(|d| time_index.checked_add(d))
+            .ok_or_else(malformed)?;
+        let time = *time_table.get(time_index).ok_or_else(malformed)?;
+        // the wave data can be much smaller than the changes decoded from it
+        allocation.element::<(u64, Vec<u8>)>("value changes", changes.len())?;
+        allocation.claim(value.len())?;
+        changes.push((time, value));
+    }
+    Ok(changes)
+}
+
+/// Take the memory of a copy of the decoded `changes` of another signal
+fn claim_copy(changes: &[(u64, Vec<u8>)], allocation: &mut Allocation) -> Result<(), LimitError> {
+    allocation.elements::<(u64, Vec<u8>)>("value changes", changes.len())?;
+    allocation.claim(changes.iter().map(|(_, value)| value.len()).sum())
+}
+
 // fn a() {
 //     let b = ValueChangeDataBlock::from_block(
 //         &Block {
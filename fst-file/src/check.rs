@@ -0,0 +1,341 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    block_parsers::{
+        geometry::Geometry,
+        header::HeaderBlockContent,
+        hierarchy::{HierarchyContent, HierarchyVisitor, Scope, Variable},
+    },
+    options::ParseOptions,
+    FstFileContent,
+};
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// The check needs a block that is missing or could not be read
+    Skip,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => f.pad("PASS"),
+            CheckStatus::Fail => f.pad("FAIL"),
+            CheckStatus::Skip => f.pad("SKIP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+/// The checks of a file, see [check_file]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckReport {
+    pub checks: Vec<Check>,
+    /// whether the blocks of the file could be read, there are no other checks otherwise
+    readable: bool,
+}
+
+impl CheckReport {
+    /// The blocks of the file could be read, so the other checks were run
+    pub fn readable(&self) -> bool {
+        self.readable
+    }
+
+    /// No check failed
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status != CheckStatus::Fail)
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus, message: impl Into<String>) {
+        self.checks.push(Check {
+            name,
+            status,
+            message: message.into(),
+        });
+    }
+
+    /// Pass when `problems` is empty, fail with all of them otherwise
+    fn push_problems(&mut self, name: &'static str, passed: &str, problems: Vec<String>) {
+        if problems.is_empty() {
+            self.push(name, CheckStatus::Pass, passed);
+        } else {
+            self.push(name, CheckStatus::Fail, problems.join("; "));
+        }
+    }
+
+    fn push_count(&mut self, name: &'static str, header: u64, actual: (&str, u64)) {
+        let status = if header == actual.1 {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        };
+        self.push(
+            name,
+            status,
+            format!("header {header}, {} {}", actual.0, actual.1),
+        );
+    }
+}
+
+/// Counts the scopes and variables of a hierarchy
+#[derive(Default)]
+struct Counter {
+    scopes: u64,
+    variables: u64,
+}
+
+impl<'a> HierarchyVisitor<'a> for Counter {
+    fn enter_scope(&mut self, _scope: &'a Scope) {
+        self.scopes += 1;
+    }
+
+    fn variable(&mut self, _variable: &'a Variable) {
+        self.variables += 1;
+    }
+}
+
+/// Check that the blocks of a file agree with each other.
+///
+/// The counts in the header are compared with the geometry, hierarchy and value change blocks,
+/// the time ranges of the value change blocks have to be in order and inside of the header range,
+/// uncompressed data has to have the declared sizes and the position tables of the value change
+/// blocks have to point into the block.
+pub fn check_file(input: &[u8]) -> CheckReport {
+    check_file_with_options(input, &ParseOptions::default())
+}
+
+/// Check the blocks of a file like [check_file], the blocks are read with `options`
+pub fn check_file_with_options(input: &[u8], options: &ParseOptions) -> CheckReport {
+    let mut report = CheckReport::default();
+    let content = match crate::parse_with_options(input, options) {
+        Ok(content) => {
+            report.push("blocks", CheckStatus::Pass, "all blocks were read");
+            report.readable = true;
+            content
+        }
+        Err(e) => {
+            report.push("blocks", CheckStatus::Fail, e.to_string());
+            return report;
+        }
+    };
+    check_content(&content, &mut report);
+    report
+}
+
+fn check_content(content: &FstFileContent, report: &mut CheckReport) {
+    let header = match content.header.as_ref().map(|header| header.get_content()) {
+        Some(Ok(header)) => {
+            report.push("header", CheckStatus::Pass, "header was read");
+            Some(header)
+        }
+        Some(Err(e)) => {
+            report.push("header", CheckStatus::Fail, e.to_string());
+            None
+        }
+        None => {
+            report.push("header", CheckStatus::Fail, "the file has no header block");
+            None
+        }
+    };
+
+    let mut size_problems = Vec::new();
+    let geometry = match content
+        .geometry
+        .as_ref()
+        .map(|geometry| geometry.get_content_with_sizes())
+    {
+        Some(Ok((geometry, mismatches))) => {
+            report.push("geometry", CheckStatus::Pass, "geometry was read");
+            size_problems.extend(mismatches.iter().map(|m| format!("geometry: {m}")));
+            Some(geometry)
+        }
+        Some(Err(e)) => {
+            report.push("geometry", CheckStatus::Fail, e.to_string());
+            None
+        }
+        None => {
+            report.push(
+                "geometry",
+                CheckStatus::Fail,
+                "the file has no geometry block",
+            );
+            None
+        }
+    };
+
+    let hierarchy = match &content.hierarchy {
+        Some(hierarchy) => {
+            match hierarchy.get_block().extract_data_with_sizes() {
+                Ok((_, mismatches)) => {
+                    size_problems.extend(mismatches.iter().map(|m| format!("hierarchy: {m}")))
+                }
+                Err(e) => size_problems.push(format!("hierarchy: {e}")),
+            }
+            match hierarchy.get_content() {
+                Ok(hierarchy) => {
+                    report.push("hierarchy", CheckStatus::Pass, "hierarchy was read");
+                    Some(hierarchy)
+                }
+                Err(e) => {
+                    report.push("hierarchy", CheckStatus::Fail, e.to_string());
+                    None
+                }
+            }
+        }
+        None => {
+            report.push(
+                "hierarchy",
+                CheckStatus::Fail,
+                "the file has no hierarchy block",
+            );
+            None
+        }
+    };
+
+    check_counts(
+        content,
+        header.as_ref(),
+        geometry.as_ref(),
+        hierarchy.as_ref(),
+        report,
+    );
+    check_time_ranges(content, header.as_ref(), report);
+
+    let mut position_problems = Vec::new();
+    for (index, block) in content.value_change_data.iter().enumerate() {
+        // a block that can not be read is reported by the position check
+        if let Some(Ok(intermediate)) = header
+            .as_ref()
+            .map(|header| block.get_intermediate_content(header))
+        {
+            size_problems.extend(
+                intermediate
+                    .size_mismatches()
+                    .iter()
+                    .map(|m| format!("value change block {index}: {m}")),
+            );
+        }
+        if let Err(e) = block.check_positions() {
+            position_problems.push(format!("value change block {index}: {e}"));
+        }
+    }
+    report.push_problems(
+        "sizes",
+        "uncompressed data has the declared sizes",
+        size_problems,
+    );
+    report.push_problems(
+        "positions",
+        "position tables point into their blocks",
+        position_problems,
+    );
+}
+
+fn check_counts(
+    content: &FstFileContent,
+    header: Option<&HeaderBlockContent>,
+    geometry: Option<&Geometry>,
+    hierarchy: Option<&HierarchyContent>,
+    report: &mut CheckReport,
+) {
+    let Some(header) = header else {
+        for name in [
+            "num_vars",
+            "num_hierarchy_vars",
+            "num_scopes",
+            "num_vc_blocks",
+        ] {
+            report.push(name, CheckStatus::Skip, "the header could not be read");
+        }
+        return;
+    };
+    match geometry {
+        Some(geometry) => report.push_count(
+            "num_vars",
+            header.num_vars as u64,
+            ("geometry", geometry.len() as u64),
+        ),
+        None => report.push("num_vars", CheckStatus::Skip, "no geometry"),
+    }
+    match hierarchy {
+        Some(hierarchy) => {
+            let mut counter = Counter::default();
+            hierarchy.visit(&mut counter);
+            report.push_count(
+                "num_hierarchy_vars",
+                header.num_hierarchy_vars,
+                ("hierarchy", counter.variables),
+            );
+            report.push_count(
+                "num_scopes",
+                header.num_scopes,
+                ("hierarchy", counter.scopes),
+            );
+        }
+        None => {
+            report.push("num_hierarchy_vars", CheckStatus::Skip, "no hierarchy");
+            report.push("num_scopes", CheckStatus::Skip, "no hierarchy");
+        }
+    }
+    report.push_count(
+        "num_vc_blocks",
+        header.num_vc_blocks,
+        ("blocks", content.value_change_data.len() as u64),
+    );
+}
+
+fn check_time_ranges(
+    content: &FstFileContent,
+    header: Option<&HeaderBlockContent>,
+    report: &mut CheckReport,
+) {
+    let mut problems = Vec::new();
+    let mut previous_end = None;
+    for (index, block) in content.value_change_data.iter().enumerate() {
+        let (start, end) = match block.get_time_range() {
+            Ok(range) => range,
+            Err(e) => {
+                problems.push(format!("value change block {index}: {e}"));
+                continue;
+            }
+        };
+        if start > end {
+            problems.push(format!(
+                "value change block {index} starts at {start} after its end {end}"
+            ));
+        }
+        if let Some(previous_end) = previous_end.filter(|previous_end| start < *previous_end) {
+            problems.push(format!(
+                "value change block {index} starts at {start} before the previous block ends at {previous_end}"
+            ));
+        }
+        if let Some(header) = header {
+            if start < header.start_time || end > header.end_time {
+                problems.push(format!(
+                    "value change block {index} covers {start}..{end} outside of the header range {}..{}",
+                    header.start_time, header.end_time
+                ));
+            }
+        }
+        previous_end = Some(end);
+    }
+    report.push_problems(
+        "time_ranges",
+        "value change blocks are in order and inside of the header range",
+        problems,
+    );
+}
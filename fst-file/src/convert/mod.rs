@@ -0,0 +1,3 @@
+mod vcd;
+
+pub use vcd::*;
@@ -0,0 +1,454 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Seek, Write},
+};
+
+use thiserror::Error;
+use tracing::{debug, debug_span, warn};
+
+use crate::{
+    block_parsers::hierarchy::{ScopeType, VarDir, VarType},
+    data_types::{Handle, TimeScale},
+    writer::{FstWriter, WriterError, WriterOptions},
+};
+
+#[derive(Debug, Error)]
+pub enum VcdConvertError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("error while writing fst: {0}")]
+    Writer(#[from] WriterError),
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+}
+
+/// Splits the input into whitespace separated tokens without reading everything into memory
+struct Tokenizer<R> {
+    input: R,
+    line: usize,
+    token: Vec<u8>,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+    fn new(input: R) -> Self {
+        Self {
+            input,
+            line: 1,
+            token: Vec::new(),
+        }
+    }
+
+    fn next_token(&mut self) -> io::Result<Option<&[u8]>> {
+        self.token.clear();
+        loop {
+            let buf = self.input.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let mut used = 0;
+            let mut done = false;
+            for &b in buf {
+                used += 1;
+                if b.is_ascii_whitespace() {
+                    if b == b'\n' {
+                        self.line += 1;
+                    }
+                    if !self.token.is_empty() {
+                        done = true;
+                        break;
+                    }
+                } else {
+                    self.token.push(b);
+                }
+            }
+            self.input.consume(used);
+            if done {
+                break;
+            }
+        }
+        Ok((!self.token.is_empty()).then_some(&self.token[..]))
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, VcdConvertError> {
+        Err(VcdConvertError::Syntax {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    /// Next token that has to exist
+    fn expect_token(&mut self, what: &str) -> Result<Vec<u8>, VcdConvertError> {
+        match self.next_token()? {
+            Some(t) => Ok(t.to_vec()),
+            None => self.error(format!("unexpected end of file while reading {what}")),
+        }
+    }
+
+    /// Collect all tokens until `$end`
+    fn tokens_until_end(&mut self) -> Result<Vec<String>, VcdConvertError> {
+        let mut tokens = Vec::new();
+        loop {
+            match self.next_token()? {
+                Some(b"$end") => return Ok(tokens),
+                Some(t) => tokens.push(String::from_utf8_lossy(t).to_string()),
+                None => return self.error("unexpected end of file while looking for $end"),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SignalKind {
+    Bits(usize),
+    Real,
+    String,
+}
+
+enum Declaration {
+    Scope(ScopeType, String),
+    Upscope,
+    Var {
+        var_type: VarType,
+        size: u32,
+        id: Vec<u8>,
+        name: String,
+    },
+}
+
+fn parse_timescale(text: &str) -> Option<TimeScale> {
+    let unit_start = text.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = text.split_at(unit_start);
+    let magnitude = match number {
+        "" | "1" => 0,
+        "10" => 1,
+        "100" => 2,
+        _ => return None,
+    };
+    let exponent = match unit.trim() {
+        "s" => 0,
+        "ms" => -3,
+        "us" => -6,
+        "ns" => -9,
+        "ps" => -12,
+        "fs" => -15,
+        "as" => -18,
+        "zs" => -21,
+        _ => return None,
+    };
+    Some(TimeScale(exponent + magnitude))
+}
+
+fn scope_type_from_vcd(name: &[u8]) -> Option<ScopeType> {
+    Some(match name {
+        b"module" => ScopeType::VcdModule,
+        b"task" => ScopeType::VcdTask,
+        b"function" => ScopeType::VcdFunction,
+        b"begin" => ScopeType::VcdBegin,
+        b"fork" => ScopeType::VcdFork,
+        b"generate" => ScopeType::VcdGenerate,
+        b"struct" => ScopeType::VcdStruct,
+        b"union" => ScopeType::VcdUnion,
+        b"class" => ScopeType::VcdClass,
+        b"interface" => ScopeType::VcdInterface,
+        b"package" => ScopeType::VcdPackage,
+        b"program" => ScopeType::VcdProgram,
+        b"vhdl_architecture" => ScopeType::VhdlArchitecture,
+        b"vhdl_procedure" => ScopeType::VhdlProcedure,
+        b"vhdl_function" => ScopeType::VhdlFunction,
+        b"vhdl_record" => ScopeType::VhdlRecord,
+        b"vhdl_process" => ScopeType::VhdlProcess,
+        b"vhdl_block" => ScopeType::VhdlBlock,
+        b"vhdl_for_generate" => ScopeType::VhdlGorGenerate,
+        b"vhdl_if_generate" => ScopeType::VhdlIfGenerate,
+        b"vhdl_generate" => ScopeType::VhdlGenerate,
+        b"vhdl_package" => ScopeType::VhdlPackage,
+        _ => return None,
+    })
+}
+
+fn var_type_from_vcd(name: &[u8]) -> Option<VarType> {
+    Some(match name {
+        b"event" => VarType::VcdEvent,
+        b"integer" => VarType::VcdInteger,
+        b"parameter" => VarType::VcdParameter,
+        b"real" => VarType::VcdReal,
+        b"real_parameter" => VarType::VcdRealParameter,
+        b"reg" => VarType::VcdReg,
+        b"supply0" => VarType::VcdSupply0,
+        b"supply1" => VarType::VcdSupply1,
+        b"time" => VarType::VcdTime,
+        b"tri" => VarType::VcdTri,
+        b"triand" => VarType::VcdTriAnd,
+        b"trior" => VarType::VcdTriOr,
+        b"trireg" => VarType::VcdTriReg,
+        b"tri0" => VarType::VcdTri0,
+        b"tri1" => VarType::VcdTri1,
+        b"wand" => VarType::VcdWand,
+        b"wire" => VarType::VcdWire,
+        b"wor" => VarType::VcdWor,
+        b"port" => VarType::VcdPort,
+        b"sparray" => VarType::VcdSparray,
+        b"realtime" => VarType::VcdRealtime,
+        b"string" => VarType::GenString,
+        b"bit" => VarType::SvBit,
+        b"logic" => VarType::SvLogic,
+        b"int" => VarType::SvInt,
+        b"shortint" => VarType::SvShortInt,
+        b"longint" => VarType::SvLongInt,
+        b"byte" => VarType::SvByte,
+        b"enum" => VarType::SvEnum,
+        b"shortreal" => VarType::SvShortReal,
+        _ => return None,
+    })
+}
+
+/// Extend or cut a vector value to `width` characters following the rules of VCD
+fn resize_vector(value: &[u8], width: usize, out: &mut Vec<u8>) {
+    out.clear();
+    if value.len() >= width {
+        out.extend(
+            value[value.len() - width..]
+                .iter()
+                .map(u8::to_ascii_lowercase),
+        );
+    } else {
+        let fill = match value.first().map(u8::to_ascii_lowercase) {
+            Some(b'x') => b'x',
+            Some(b'z') => b'z',
+            _ => b'0',
+        };
+        out.resize(width - value.len(), fill);
+        out.extend(value.iter().map(u8::to_ascii_lowercase));
+    }
+}
+
+struct Header {
+    declarations: Vec<Declaration>,
+    timescale: Option<TimeScale>,
+    timezero: Option<i64>,
+    date: Option<String>,
+}
+
+fn parse_header<R: BufRead>(tokens: &mut Tokenizer<R>) -> Result<Header, VcdConvertError> {
+    let _span = debug_span!("parse vcd header").entered();
+    let mut header = Header {
+        declarations: Vec::new(),
+        timescale: None,
+        timezero: None,
+        date: None,
+    };
+    loop {
+        let Some(token) = tokens.next_token()? else {
+            return tokens.error("unexpected end of file before $enddefinitions");
+        };
+        match token {
+            b"$date" => header.date = Some(tokens.tokens_until_end()?.join(" ")),
+            b"$timescale" => {
+                let text = tokens.tokens_until_end()?.concat();
+                match parse_timescale(&text) {
+                    Some(timescale) => header.timescale = Some(timescale),
+                    None => return tokens.error(format!("unknown timescale {text}")),
+                }
+            }
+            b"$timezero" => {
+                let text = tokens.tokens_until_end()?.concat();
+                match text.parse() {
+                    Ok(timezero) => header.timezero = Some(timezero),
+                    Err(_) => return tokens.error(format!("invalid timezero {text}")),
+                }
+            }
+            b"$scope" => {
+                let scope_type = tokens.expect_token("scope type")?;
+                let Some(scope_type) = scope_type_from_vcd(&scope_type) else {
+                    return tokens.error(format!(
+                        "unknown scope type {}",
+                        String::from_utf8_lossy(&scope_type)
+                    ));
+                };
+                let name = tokens.tokens_until_end()?.join(" ");
+                header
+                    .declarations
+                    .push(Declaration::Scope(scope_type, name));
+            }
+            b"$upscope" => {
+                tokens.tokens_until_end()?;
+                header.declarations.push(Declaration::Upscope);
+            }
+            b"$var" => {
+                let var_type = tokens.expect_token("var type")?;
+                let Some(var_type) = var_type_from_vcd(&var_type) else {
+                    return tokens.error(format!(
+                        "unknown var type {}",
+                        String::from_utf8_lossy(&var_type)
+                    ));
+                };
+                let size = tokens.expect_token("var size")?;
+                let Some(size) = std::str::from_utf8(&size).ok().and_then(|s| s.parse().ok())
+                else {
+                    return tokens.error("var size is not a number");
+                };
+                let id = tokens.expect_token("var id")?;
+                let name = tokens.tokens_until_end()?.join(" ");
+                header.declarations.push(Declaration::Var {
+                    var_type,
+                    size,
+                    id,
+                    name,
+                });
+            }
+            b"$enddefinitions" => {
+                tokens.tokens_until_end()?;
+                return Ok(header);
+            }
+            t if t.starts_with(b"$") => {
+                debug!("skipping {}", String::from_utf8_lossy(t));
+                tokens.tokens_until_end()?;
+            }
+            t => {
+                let message = format!("unexpected {} in header", String::from_utf8_lossy(t));
+                return tokens.error(message);
+            }
+        }
+    }
+}
+
+/// Convert a VCD file into a FST file.
+///
+/// The VCD is read token by token so the input does not need to fit in memory.
+/// The timescale, timezero and date of the VCD override the ones in `options`.
+pub fn vcd_to_fst<R: BufRead, W: Write + Seek>(
+    input: R,
+    output: W,
+    mut options: WriterOptions,
+) -> Result<W, VcdConvertError> {
+    let mut tokens = Tokenizer::new(input);
+    let header = parse_header(&mut tokens)?;
+    if let Some(timescale) = header.timescale {
+        options.timescale = timescale;
+    }
+    if let Some(timezero) = header.timezero {
+        options.timezero = timezero;
+    }
+    if header.date.is_some() {
+        options.date = header.date;
+    }
+
+    let mut writer = FstWriter::new(output, options)?;
+    let mut signals: HashMap<Vec<u8>, (Handle, SignalKind)> = HashMap::new();
+    for declaration in header.declarations {
+        match declaration {
+            Declaration::Scope(scope_type, name) => writer.set_scope(scope_type, &name, "")?,
+            Declaration::Upscope => writer.set_upscope()?,
+            Declaration::Var {
+                var_type,
+                size,
+                id,
+                name,
+            } => {
+                let alias = signals.get(&id).map(|(handle, _)| *handle);
+                let kind = match var_type {
+                    VarType::VcdReal
+                    | VarType::VcdRealParameter
+                    | VarType::VcdRealtime
+                    | VarType::SvShortReal => SignalKind::Real,
+                    VarType::GenString => SignalKind::String,
+                    _ => SignalKind::Bits(size.max(1) as usize),
+                };
+                let handle =
+                    writer.create_var(var_type, VarDir::Implicit, size.max(1), &name, alias)?;
+                signals.entry(id).or_insert((handle, kind));
+            }
+        }
+    }
+
+    let _span = debug_span!("convert vcd values").entered();
+    let mut value = Vec::new();
+    let mut resized = Vec::new();
+    while let Some(token) = tokens.next_token()? {
+        let (kind_char, rest) = (token[0], &token[1..]);
+        value.clear();
+        let id = match kind_char {
+            b'#' => {
+                let Some(time) = std::str::from_utf8(rest).ok().and_then(|s| s.parse().ok()) else {
+                    return tokens.error("time is not a number");
+                };
+                writer.emit_time_change(time)?;
+                continue;
+            }
+            b'$' => {
+                match rest {
+                    b"dumpoff" => writer.emit_dump_active(false)?,
+                    b"dumpon" => writer.emit_dump_active(true)?,
+                    b"comment" => {
+                        tokens.tokens_until_end()?;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            b'b' | b'B' | b'r' | b'R' | b's' | b'S' => {
+                value.extend_from_slice(rest);
+                tokens.expect_token("signal id")?
+            }
+            _ => {
+                value.push(kind_char);
+                rest.to_vec()
+            }
+        };
+        let Some(&(handle, kind)) = signals.get(&id) else {
+            warn!(
+                line = tokens.line,
+                "value change for unknown signal {}",
+                String::from_utf8_lossy(&id)
+            );
+            continue;
+        };
+        match kind {
+            SignalKind::Bits(width) => {
+                resize_vector(&value, width, &mut resized);
+                writer.emit_value_change(handle, &resized)?;
+            }
+            SignalKind::Real => {
+                let Some(real) = std::str::from_utf8(&value)
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok())
+                else {
+                    return tokens.error("real value is not a number");
+                };
+                writer.emit_value_change(handle, &real.to_le_bytes())?;
+            }
+            SignalKind::String => writer.emit_value_change(handle, &value)?,
+        }
+    }
+    Ok(writer.finish()?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data_types::TimeScale;
+
+    use super::{parse_timescale, resize_vector};
+
+    #[test]
+    fn timescale() {
+        assert_eq!(parse_timescale("1ns").map(|t| t.0), Some(-9));
+        assert_eq!(parse_timescale("10ps").map(|t| t.0), Some(-11));
+        assert_eq!(parse_timescale("100us").map(|t| t.0), Some(-4));
+        assert_eq!(parse_timescale("1s").map(|t| t.0), Some(0));
+        assert!(parse_timescale("3ns").is_none());
+        assert!(matches!(parse_timescale("1fs"), Some(TimeScale(-15))));
+    }
+
+    #[test]
+    fn vector_resize() {
+        let mut out = Vec::new();
+        resize_vector(b"101", 6, &mut out);
+        assert_eq!(out, b"000101");
+        resize_vector(b"X1", 4, &mut out);
+        assert_eq!(out, b"xxx1");
+        resize_vector(b"z", 3, &mut out);
+        assert_eq!(out, b"zzz");
+        resize_vector(b"110011", 4, &mut out);
+        assert_eq!(out, b"0011");
+    }
+}
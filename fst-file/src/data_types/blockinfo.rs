@@ -1,7 +1,38 @@
+use std::fmt;
+
 use serde::{ser::SerializeStruct, Serialize};
 
 use crate::block_parsers::Block;
 
+use super::BlockType;
+
+/// Where a block is in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BlockLocation {
+    /// position of the block among all blocks of the file
+    pub index: usize,
+    /// offset of the block type byte in the file
+    pub offset: usize,
+    /// [None] when the type byte is unknown
+    pub block_type: Option<BlockType>,
+}
+
+impl BlockLocation {
+    /// Offset of the block data in the file, after the type and the length
+    pub fn data_offset(&self) -> usize {
+        self.offset + 9
+    }
+}
+
+impl fmt::Display for BlockLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.block_type {
+            Some(block_type) => write!(f, "block #{} ({block_type})", self.index),
+            None => write!(f, "block #{} (unknown type)", self.index),
+        }
+    }
+}
+
 pub struct BlockInfo {
     block: Block,
     start_position: usize,
@@ -32,6 +63,11 @@ impl BlockInfo {
         self.block
     }
 
+    /// Let the block know its index and offset for error messages
+    pub(crate) fn set_index(&mut self, index: usize) {
+        self.block.set_position(index, self.start_position);
+    }
+
     pub fn from_offset_and_block(start_position: usize, size: usize, block: Block) -> Self {
         Self {
             start_position,
@@ -0,0 +1,30 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Handle of a signal in the value change data
+///
+/// Handles start from 1 in FST files.
+/// Variables in the hierarchy that are not an alias get the next free handle.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[repr(transparent)]
+pub struct Handle(pub u32);
+
+impl Handle {
+    /// Handle of the signal at `index` in the geometry
+    pub fn from_index(index: usize) -> Self {
+        Handle(index as u32 + 1)
+    }
+
+    /// Index of the signal in the geometry and value change data,
+    /// [None] for the invalid handle 0
+    pub fn index(self) -> Option<usize> {
+        (self.0 as usize).checked_sub(1)
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
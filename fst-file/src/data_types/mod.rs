@@ -1,15 +1,15 @@
 mod blockinfo;
-mod blocks;
 mod blocktype;
 mod filetype;
+mod handle;
 mod timescale;
 mod varint;
 mod writer_pack_type;
 
 pub use blockinfo::*;
-pub use blocks::*;
 pub use blocktype::*;
 pub use filetype::*;
+pub use handle::*;
 pub use timescale::*;
 pub use varint::*;
 pub use writer_pack_type::*;
@@ -8,7 +8,6 @@ use nom::{
     combinator::{map, verify},
     error::{context, make_error, ErrorKind},
 };
-use num_traits::WrappingSub;
 use serde::Serialize;
 
 use crate::{error::ParseResult, FstParsable};
@@ -38,7 +37,7 @@ impl Serialize for VarInt {
 
 impl FstParsable for VarInt {
     /// Parse a [VarInt] from &[[u8]]
-    fn parse<'a>(input: &'a [u8]) -> ParseResult<'_, VarInt> {
+    fn parse<'a>(input: &'a [u8]) -> ParseResult<'a, VarInt> {
         context("varint", |input: &'a [u8]| {
             let input_original = input;
             let (input, data) = take_while_m_n(0, 20, |b| b & 0b1000_0000 != 0)(input)?;
@@ -83,6 +82,34 @@ impl Serialize for SVarInt {
     }
 }
 
+impl VarInt {
+    /// Append the encoded form of this [VarInt] to `buf`
+    pub fn write_to(self, buf: &mut Vec<u8>) {
+        let mut v = self.0;
+        while v >= 0b1000_0000 {
+            buf.push((v as u8) | 0b1000_0000);
+            v >>= 7;
+        }
+        buf.push(v as u8);
+    }
+}
+
+impl SVarInt {
+    /// Append the encoded form of this [SVarInt] to `buf`
+    pub fn write_to(self, buf: &mut Vec<u8>) {
+        let mut v = self.0;
+        loop {
+            let b = (v as u8) & 0b0111_1111;
+            v >>= 7;
+            if (v == 0 && b & 0b0100_0000 == 0) || (v == -1 && b & 0b0100_0000 != 0) {
+                buf.push(b);
+                break;
+            }
+            buf.push(b | 0b1000_0000);
+        }
+    }
+}
+
 impl TryFrom<VarInt> for usize {
     type Error = <usize as TryFrom<u64>>::Error;
 
@@ -101,16 +128,13 @@ impl TryFrom<VarInt> for u32 {
 
 impl FstParsable for SVarInt {
     /// Parse a [SVarInt] from &[[u8]]
-    fn parse<'a>(input: &'a [u8]) -> ParseResult<'_, SVarInt> {
+    fn parse<'a>(input: &'a [u8]) -> ParseResult<'a, SVarInt> {
         context("svarint", |input: &'a [u8]| {
             let input_original = input;
             let (input, data) = take_while_m_n(0, 20, |b| b & 0b1000_0000 != 0)(input)?;
             let (input, last) = map(take(1u8), |v: &[u8]| v[0])(input)?;
-            let mut val = 0;
-            if last & 0b0100_0000 != 0 {
-                val = val.wrapping_sub(&1);
-            }
-            val |= last as i64;
+            // sign extend from the 7th bit of the last byte
+            let mut val = ((last << 1) as i8 >> 1) as i64;
             for s in data.iter().rev() {
                 let v: i64 = val.shl(7);
                 if val != v.shr(7) {
@@ -180,13 +204,69 @@ mod test {
 
         let input = [0xC5, 0x58];
         let (_i, a) = SVarInt::parse(&input).unwrap();
-        assert_eq!(a, SVarInt(-59));
+        assert_eq!(a, SVarInt(-5051));
+
+        let input = [0x40];
+        let (_i, a) = SVarInt::parse(&input).unwrap();
+        assert_eq!(a, SVarInt(-64));
 
         let input = [0xBB, 0x87, 0x7F];
         let (_i, a) = SVarInt::parse(&input).unwrap();
         assert_eq!(a, SVarInt(-15429));
     }
 
+    #[test]
+    fn svarint_sign_extension() {
+        // signed LEB128 as written by fstWriterSVarint, the sign is bit 6 of the last byte
+        for (input, expected) in [
+            (&[0x7F][..], -1),
+            (&[0x3F], 63),
+            (&[0xC0, 0x00], 64),
+            (&[0xBF, 0x7F], -65),
+            (&[0x80, 0x7F], -128),
+            (&[0xFF, 0x00], 127),
+        ] {
+            let (rest, a) = SVarInt::parse(input).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(a, SVarInt(expected), "{input:02x?}");
+        }
+    }
+
+    #[test]
+    fn varint_write() {
+        for v in [0, 1, 0x58, 127, 128, 3141, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            VarInt(v).write_to(&mut buf);
+            let (rest, a) = VarInt::parse(&buf).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(a, VarInt(v));
+        }
+    }
+
+    #[test]
+    fn svarint_write() {
+        for v in [
+            0,
+            1,
+            -1,
+            63,
+            64,
+            -64,
+            -65,
+            3141,
+            -59,
+            -15429,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            let mut buf = Vec::new();
+            SVarInt(v).write_to(&mut buf);
+            let (rest, a) = SVarInt::parse(&buf).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(a, SVarInt(v));
+        }
+    }
+
     #[test]
     fn svarint_toolarge() {
         let input = [
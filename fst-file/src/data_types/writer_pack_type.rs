@@ -3,22 +3,33 @@ use serde::Serialize;
 
 use crate::{error::ParseResult, FstParsable};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum WriterPackType {
     Zlib,
     FaslLz,
     Lz4,
 }
 
+impl WriterPackType {
+    /// Byte used to mark this pack type in value change data blocks
+    pub fn as_byte(self) -> u8 {
+        match self {
+            WriterPackType::Zlib => b'Z',
+            WriterPackType::FaslLz => b'F',
+            WriterPackType::Lz4 => b'4',
+        }
+    }
+}
+
 impl FstParsable for WriterPackType {
     fn parse(input: &[u8]) -> ParseResult<'_, Self> {
         context(
             "writer pack",
             alt((
-                map(tag(&[b'!']), |_| Self::Zlib),
-                map(tag(&[b'Z']), |_| Self::Zlib),
-                map(tag(&[b'F']), |_| Self::FaslLz),
-                map(tag(&[b'4']), |_| Self::Lz4),
+                map(tag(b"!"), |_| Self::Zlib),
+                map(tag(b"Z"), |_| Self::Zlib),
+                map(tag(b"F"), |_| Self::FaslLz),
+                map(tag(b"4"), |_| Self::Lz4),
             )),
         )(input)
     }
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use num_traits::FromPrimitive;
+
 use nom::{
     error::{
         ContextError, ErrorKind, FromExternalError, ParseError, VerboseError, VerboseErrorKind,
@@ -8,6 +10,19 @@ use nom::{
 };
 use thiserror::Error;
 
+use crate::{
+    block_parsers::{
+        blackout::BlackoutParseError, geometry::GeometryParseError, header::HeaderParseError,
+        hierarchy::HierarchyBlockConvertError, value_change_data::ValueChangeDataError,
+        DecompressError,
+    },
+    convert::VcdConvertError,
+    data_types::{BlockLocation, BlockType},
+    options::{Anomaly, LimitError},
+    transform::TransformError,
+    writer::WriterError,
+};
+
 // use crate::data_types::VarIntParseErrorKind;
 
 #[derive(Debug, Clone, PartialEq, Error)]
@@ -97,35 +112,277 @@ impl<I> ContextError<I> for FstFileParseError<I> {
 
 pub type FstFileResult<'a, T, I = &'a [u8]> = IResult<I, T, FstFileParseError<I>>;
 
-#[derive(Debug, Clone, Error)]
-#[error("error while parsing with position: {errors:?}")]
-
+/// A parse error with the offsets of the failing inputs.
+///
+/// The offsets are relative to the parsed data, which is the file, the data of a block
+/// or data extracted from a block.
+/// The error remembers the block and bytes around the failure for diagnostics.
+#[derive(Debug, Clone)]
 pub struct PositionError<E: fmt::Debug> {
     errors: Vec<(usize, E)>,
+    excerpt: Excerpt,
+    block: Option<BlockLocation>,
+    /// file offset of the parsed data, [None] when it is not in the file as is
+    data_offset: Option<usize>,
 }
 
-impl PositionError<VerboseErrorKind> {
-    pub fn from_verbose_parse_error<I: Offset>(error: VerboseError<I>, original_input: I) -> Self {
+/// Bytes of the parsed data around an error
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Excerpt {
+    /// offset of the first byte in the parsed data
+    pub start: usize,
+    pub bytes: Vec<u8>,
+}
+
+/// Rows of 16 bytes kept before and after the row of an error
+const EXCERPT_CONTEXT_ROWS: usize = 2;
+
+impl Excerpt {
+    fn around(data: &[u8], offset: usize) -> Self {
+        let row = offset / 16;
+        let start = (row.saturating_sub(EXCERPT_CONTEXT_ROWS) * 16).min(data.len());
+        let end = ((row + EXCERPT_CONTEXT_ROWS + 1) * 16).min(data.len());
+        Excerpt {
+            start,
+            bytes: data[start..end].to_vec(),
+        }
+    }
+}
+
+/// Text of the error kinds that [PositionError] collects
+pub trait ErrorKindText: fmt::Debug {
+    /// The name given with [nom::error::context]
+    fn context(&self) -> Option<&'static str>;
+
+    fn description(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl ErrorKindText for VerboseErrorKind {
+    fn context(&self) -> Option<&'static str> {
+        match self {
+            VerboseErrorKind::Context(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            VerboseErrorKind::Context(context) => context.to_string(),
+            VerboseErrorKind::Char(c) => format!("expected {c:?}"),
+            VerboseErrorKind::Nom(kind) => kind.description().to_lowercase(),
+        }
+    }
+}
+
+impl ErrorKindText for FstFileParseErrorKind {
+    fn context(&self) -> Option<&'static str> {
+        match self {
+            FstFileParseErrorKind::Context(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    fn description(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<E: fmt::Debug> PositionError<E> {
+    fn new(errors: Vec<(usize, E)>, data: &[u8]) -> Self {
+        let offset = errors.first().map_or(0, |(offset, _)| *offset);
         PositionError {
-            errors: error
-                .errors
-                .into_iter()
-                .map(|(i, e)| (original_input.offset(&i), e))
-                .collect(),
+            errors,
+            excerpt: Excerpt::around(data, offset),
+            block: None,
+            data_offset: None,
         }
     }
+
+    /// The offsets and kinds from the innermost to the outermost parser
+    pub fn errors(&self) -> &[(usize, E)] {
+        &self.errors
+    }
+
+    /// Offset of the innermost error in the parsed data
+    pub fn offset(&self) -> Option<usize> {
+        self.errors.first().map(|(offset, _)| *offset)
+    }
+
+    /// Offset of the innermost error in the file, [None] when the data was decompressed
+    pub fn file_offset(&self) -> Option<usize> {
+        Some(self.data_offset? + self.offset()?)
+    }
+
+    /// File offset of the parsed data, [None] when the data was decompressed
+    pub fn data_offset(&self) -> Option<usize> {
+        self.data_offset
+    }
+
+    /// The block with the failing bytes
+    pub fn block(&self) -> Option<&BlockLocation> {
+        self.block.as_ref()
+    }
+
+    pub fn excerpt(&self) -> &Excerpt {
+        &self.excerpt
+    }
+
+    /// The parsed data was the whole file
+    pub fn in_file(mut self, file: &[u8]) -> Self {
+        self.block = self.offset().and_then(|offset| block_at(file, offset));
+        self.data_offset = Some(0);
+        self
+    }
+
+    /// The parsed data was the data of the block as it is in the file
+    pub fn in_block(mut self, block: BlockLocation) -> Self {
+        self.data_offset = Some(block.data_offset());
+        self.block = Some(block);
+        self
+    }
+
+    /// The parsed data was decompressed or taken from the data of the block
+    pub fn in_extracted(mut self, block: BlockLocation) -> Self {
+        self.data_offset = None;
+        self.block = Some(block);
+        self
+    }
+}
+
+impl<E: ErrorKindText> PositionError<E> {
+    /// The contexts from the innermost to the outermost parser
+    pub fn contexts(&self) -> Vec<&'static str> {
+        let mut contexts: Vec<_> = self
+            .errors
+            .iter()
+            .filter_map(|(_, e)| e.context())
+            .collect();
+        // a parser with a context is often wrapped in the same context again
+        contexts.dedup();
+        contexts
+    }
+
+    /// The first error that is not a context
+    pub fn kind(&self) -> Option<&E> {
+        self.errors
+            .iter()
+            .map(|(_, e)| e)
+            .find(|e| e.context().is_none())
+    }
+}
+
+impl PositionError<VerboseErrorKind> {
+    pub fn from_verbose_parse_error(error: VerboseError<&[u8]>, original_input: &[u8]) -> Self {
+        let errors = error
+            .errors
+            .into_iter()
+            .map(|(i, e)| (original_input.offset(i), e))
+            .collect();
+        Self::new(errors, original_input)
+    }
 }
 
 impl PositionError<FstFileParseErrorKind> {
-    pub fn from_fst_parse_error<I: Offset>(error: FstFileParseError<I>, original_input: I) -> Self {
-        PositionError {
-            errors: error
-                .errors
-                .into_iter()
-                .map(|(i, e)| (original_input.offset(&i), e))
-                .collect(),
+    pub fn from_fst_parse_error(error: FstFileParseError<&[u8]>, original_input: &[u8]) -> Self {
+        let errors = error
+            .errors
+            .into_iter()
+            .map(|(i, e)| (original_input.offset(i), e))
+            .collect();
+        Self::new(errors, original_input)
+    }
+}
+
+impl<E: ErrorKindText> fmt::Display for PositionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind() {
+            Some(kind) => write!(f, "{}", kind.description())?,
+            None => write!(f, "parse error")?,
+        }
+        match (self.file_offset(), self.offset()) {
+            (Some(file_offset), _) => write!(f, " at file offset {file_offset}")?,
+            (None, Some(offset)) if self.block.is_some() => {
+                write!(f, " at offset {offset} of the extracted data")?
+            }
+            (None, Some(offset)) => write!(f, " at offset {offset}")?,
+            (None, None) => {}
+        }
+        if let Some(block) = &self.block {
+            write!(f, " in {block}")?;
         }
+        let contexts = self.contexts();
+        if !contexts.is_empty() {
+            write!(f, " while parsing {}", contexts.join(" → "))?;
+        }
+        Ok(())
     }
 }
 
+impl<E: ErrorKindText> std::error::Error for PositionError<E> {}
+
+/// Find the block that holds `offset` by following the block lengths
+fn block_at(file: &[u8], offset: usize) -> Option<BlockLocation> {
+    let mut start = 0;
+    let mut index = 0;
+    while let Some((&type_byte, rest)) = file.get(start..)?.split_first() {
+        let end = rest
+            .split_first_chunk::<8>()
+            .map(|(length, _)| u64::from_be_bytes(*length))
+            .filter(|length| *length >= 8)
+            .and_then(|length| usize::try_from(length).ok())
+            .and_then(|length| length.checked_add(1)?.checked_add(start));
+        match end {
+            Some(end) if offset >= end => {
+                start = end;
+                index += 1;
+            }
+            _ => {
+                return Some(BlockLocation {
+                    index,
+                    offset: start,
+                    block_type: BlockType::from_u8(type_byte),
+                })
+            }
+        }
+    }
+    None
+}
+
 pub type ParseResult<'a, T, I = [u8]> = IResult<&'a I, T, VerboseError<&'a I>>;
+
+/// Any error of this crate.
+///
+/// The per block errors convert into it, so code that reads a whole file can use `?` everywhere.
+#[derive(Debug, Error)]
+pub enum FstError {
+    #[error("parse error: {0}")]
+    Parse(#[from] PositionError<VerboseErrorKind>),
+    #[error("the file has no {0} block")]
+    MissingBlock(&'static str),
+    #[error("header error: {0}")]
+    Header(#[from] HeaderParseError),
+    #[error("hierarchy error: {0}")]
+    Hierarchy(#[from] HierarchyBlockConvertError),
+    #[error("geometry error: {0}")]
+    Geometry(#[from] GeometryParseError),
+    #[error("blackout error: {0}")]
+    Blackout(#[from] BlackoutParseError),
+    #[error("value change data error: {0}")]
+    ValueChangeData(#[from] ValueChangeDataError),
+    #[error("decompress error: {0}")]
+    Decompress(#[from] DecompressError),
+    #[error("writer error: {0}")]
+    Writer(#[from] WriterError),
+    #[error("transform error: {0}")]
+    Transform(#[from] TransformError),
+    #[error("vcd conversion error: {0}")]
+    VcdConvert(#[from] VcdConvertError),
+    #[error("{0}")]
+    Anomaly(#[from] Anomaly),
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitError),
+}
+
+pub type FstResult<T> = Result<T, FstError>;
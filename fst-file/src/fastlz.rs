@@ -0,0 +1,144 @@
+//! Minimal FastLZ implementation used by the `F` ([crate::data_types::WriterPackType::FaslLz]) wave pack type.
+//!
+//! Only level 1 streams are produced, which every FastLZ decoder accepts.
+//! See <https://github.com/ariya/FastLZ> for the format.
+
+const MAX_COPY: usize = 32;
+const MAX_LEN: usize = 264;
+const MAX_L1_DISTANCE: usize = 8192;
+const HASH_LOG: u32 = 13;
+
+fn hash(data: &[u8]) -> usize {
+    let v = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+    (v.wrapping_mul(2_654_435_769) >> (32 - HASH_LOG)) as usize
+}
+
+fn push_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(MAX_COPY) {
+        out.push((chunk.len() - 1) as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn push_match(out: &mut Vec<u8>, len: usize, distance: usize) {
+    let m = len - 2;
+    let d = distance - 1;
+    if m < 7 {
+        out.push(((m << 5) | (d >> 8)) as u8);
+    } else {
+        out.push(((7 << 5) | (d >> 8)) as u8);
+        out.push((m - 7) as u8);
+    }
+    out.push(d as u8);
+}
+
+/// Compress `input` into a level 1 FastLZ stream
+pub(crate) fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() + input.len() / 16 + 2);
+    // positions are stored off by one so that 0 means empty
+    let mut table = vec![0usize; 1 << HASH_LOG];
+    let mut literal_start = 0;
+    let mut ip = 0;
+    while ip + 3 <= input.len() {
+        let h = hash(&input[ip..]);
+        let candidate = table[h];
+        table[h] = ip + 1;
+        if candidate != 0 {
+            let r = candidate - 1;
+            let distance = ip - r;
+            if distance <= MAX_L1_DISTANCE && input[r..r + 3] == input[ip..ip + 3] {
+                let mut len = 3;
+                while ip + len < input.len() && len < MAX_LEN && input[r + len] == input[ip + len] {
+                    len += 1;
+                }
+                push_literals(&mut out, &input[literal_start..ip]);
+                push_match(&mut out, len, distance);
+                ip += len;
+                literal_start = ip;
+                continue;
+            }
+        }
+        ip += 1;
+    }
+    push_literals(&mut out, &input[literal_start..]);
+    out
+}
+
+/// Decompress a level 1 or level 2 FastLZ stream.
+/// Returns [None] when the stream is malformed or longer than `output_size`.
+pub(crate) fn decompress(input: &[u8], output_size: usize) -> Option<Vec<u8>> {
+    // a match op code of 3 bytes expands to at most 264 bytes
+    let mut out = Vec::with_capacity(output_size.min(input.len().saturating_mul(MAX_LEN)));
+    let level = (input.first()? >> 5) + 1;
+    if level > 2 {
+        return None;
+    }
+    let mut ip = 0;
+    // the level is stored in the first op code which is always a literal run
+    let mut op = input[0] & 0x1f;
+    ip += 1;
+    loop {
+        if op < 32 {
+            let run = op as usize + 1;
+            out.extend_from_slice(input.get(ip..ip + run)?);
+            ip += run;
+        } else {
+            let mut len = (op >> 5) as usize + 2;
+            let mut distance = ((op & 0x1f) as usize) << 8;
+            if len == 7 + 2 {
+                loop {
+                    let code = *input.get(ip)?;
+                    ip += 1;
+                    len += code as usize;
+                    if level == 1 || code != 255 {
+                        break;
+                    }
+                }
+            }
+            let code = *input.get(ip)?;
+            ip += 1;
+            distance += code as usize;
+            if level == 2 && code == 255 && op & 0x1f == 31 {
+                let far = u16::from_be_bytes([*input.get(ip)?, *input.get(ip + 1)?]) as usize;
+                ip += 2;
+                distance = far + MAX_L1_DISTANCE - 1;
+            }
+            let start = out.len().checked_sub(distance + 1)?;
+            for i in start..start + len {
+                out.push(out[i]);
+            }
+        }
+        if out.len() > output_size {
+            return None;
+        }
+        if ip >= input.len() {
+            break;
+        }
+        op = input[ip];
+        ip += 1;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compress, decompress};
+
+    #[test]
+    fn roundtrip() {
+        let mut data = Vec::new();
+        for i in 0..5000u32 {
+            data.extend_from_slice(format!("{} ", i % 97).as_bytes());
+        }
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed, data.len()), Some(data));
+    }
+
+    #[test]
+    fn level2_header() {
+        // literal run "abc", match of length 3 at distance 1 with the level 2 header set
+        let stream = [0b0010_0010, b'a', b'b', b'c', 0b0010_0000, 2];
+        assert_eq!(decompress(&stream, 6).unwrap(), b"abcabc");
+    }
+}
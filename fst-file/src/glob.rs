@@ -0,0 +1,90 @@
+/// Match a hierarchy path against a glob pattern.
+///
+/// `?` matches one character and `*` any number of characters inside a scope name,
+/// `**` matches across scope boundaries (`separator`).
+pub(crate) fn glob_match(pattern: &str, path: &str, separator: char) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    // pattern index after the last `*` and `**` and the path index they match up to,
+    // a mismatch retries from there with one more character taken by the wildcard
+    let mut star: Option<(usize, usize)> = None;
+    let mut globstar: Option<(usize, usize)> = None;
+    let (mut p, mut i) = (0, 0);
+    loop {
+        match pattern.get(p) {
+            Some('*') if pattern.get(p + 1) == Some(&'*') => {
+                p += 2;
+                globstar = Some((p, i));
+                star = None;
+                continue;
+            }
+            Some('*') => {
+                p += 1;
+                star = Some((p, i));
+                continue;
+            }
+            Some('?') if i < path.len() && path[i] != separator => {
+                p += 1;
+                i += 1;
+                continue;
+            }
+            Some(&c) if c != '?' && path.get(i) == Some(&c) => {
+                p += 1;
+                i += 1;
+                continue;
+            }
+            None if i == path.len() => return true,
+            _ => {}
+        }
+        // `*` stops at a separator, then only an earlier `**` can take more
+        if let Some((star_p, star_i)) = star {
+            if star_i < path.len() && path[star_i] != separator {
+                star = Some((star_p, star_i + 1));
+                (p, i) = (star_p, star_i + 1);
+                continue;
+            }
+        }
+        match globstar {
+            Some((globstar_p, globstar_i)) if globstar_i < path.len() => {
+                globstar = Some((globstar_p, globstar_i + 1));
+                star = None;
+                (p, i) = (globstar_p, globstar_i + 1);
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Whether the pattern uses any glob syntax
+pub(crate) fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+#[cfg(test)]
+mod test {
+    use super::glob_match;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("top.*", "top.clk", '.'));
+        assert!(!glob_match("top.*", "top.sub.clk", '.'));
+        assert!(glob_match("top.**", "top.sub.clk", '.'));
+        assert!(glob_match("**.clk", "top.sub.clk", '.'));
+        assert!(glob_match("top.*.c?k", "top.sub.clk", '.'));
+        assert!(!glob_match("top.?", "top.ab", '.'));
+        assert!(glob_match("top.data*", "top.data [7:0]", '.'));
+        assert!(glob_match("**", "", '.'));
+        assert!(glob_match("top.**.clk", "top.a.b.clk", '.'));
+        assert!(glob_match("**.sub.*", "top.sub.x.sub.clk", '.'));
+        assert!(!glob_match("**.sub.*", "top.sub.x.clk", '.'));
+        assert!(!glob_match("*.c*k", "top.sub.clk", '.'));
+    }
+
+    #[test]
+    fn glob_backtracking_is_not_exponential() {
+        let path = "a".repeat(200);
+        assert!(!glob_match(&format!("{}b", "*a".repeat(30)), &path, '.'));
+        assert!(!glob_match(&format!("{}b", "**a".repeat(30)), &path, '.'));
+        assert!(glob_match(&"*a".repeat(30), &path, '.'));
+    }
+}
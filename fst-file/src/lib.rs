@@ -12,20 +12,33 @@ use nom::{
     multi::many_till,
     Finish, IResult, Offset,
 };
+use options::ParseOptions;
 use tracing::{debug, debug_span};
 
 use crate::block_parsers::{geometry::GeometryBlock, header::HeaderBlock};
 
 /// Block data and their parsers
 pub mod block_parsers;
+/// Consistency checks of whole files
+pub mod check;
+/// Conversion from other waveform formats
+pub mod convert;
 pub mod data_types;
 pub mod error;
+mod fastlz;
+mod glob;
+/// Strict and lenient parsing
+pub mod options;
+/// Writing modified copies of FST files
+pub mod transform;
+/// Writing FST files
+pub mod writer;
 
 /// Parses blocks
-fn parse_blocks<'a>(input: &'a [u8]) -> IResult<&[u8], Vec<BlockInfo>, VerboseError<&[u8]>> {
+fn parse_blocks<'a>(input: &'a [u8]) -> IResult<&'a [u8], Vec<BlockInfo>, VerboseError<&'a [u8]>> {
     complete(|input: &'a [u8]| {
         let input_original = input;
-        let (input, (blocks, _)) = many_till(
+        let (input, (mut blocks, _)) = many_till(
             context(
                 "parse block",
                 map(Block::parse_block_with_position, |((s, e), b)| {
@@ -34,6 +47,9 @@ fn parse_blocks<'a>(input: &'a [u8]) -> IResult<&[u8], Vec<BlockInfo>, VerboseEr
             ),
             eof,
         )(input)?;
+        for (index, block) in blocks.iter_mut().enumerate() {
+            block.set_index(index);
+        }
         Ok((input, blocks))
     })(input)
 }
@@ -54,15 +70,23 @@ pub fn parse_raw_block_information(
     parse_blocks(input)
         .finish()
         .map(|(_, blocks)| blocks)
-        .map_err(|e| PositionError::from_verbose_parse_error(e, input))
+        .map_err(|e| PositionError::from_verbose_parse_error(e, input).in_file(input))
 }
 
-/// Parse the whole content of the fst file
+/// Parse the whole content of the fst file, the blocks are read leniently
 pub fn parse(input: &[u8]) -> Result<FstFileContent, PositionError<VerboseErrorKind>> {
+    parse_with_options(input, &ParseOptions::default())
+}
+
+/// Parse the whole content of the fst file, the blocks are read with `options`
+pub fn parse_with_options(
+    input: &[u8],
+    options: &ParseOptions,
+) -> Result<FstFileContent, PositionError<VerboseErrorKind>> {
     let _span = debug_span!("parse content");
     parse_blocks(input)
         .finish()
-        .map_err(|e| PositionError::from_verbose_parse_error(e, input))
+        .map_err(|e| PositionError::from_verbose_parse_error(e, input).in_file(input))
         .map(|(_, blocks)| {
             // let mut header_block = None;
             let mut hierarchy = None;
@@ -72,7 +96,8 @@ pub fn parse(input: &[u8]) -> Result<FstFileContent, PositionError<VerboseErrorK
             let mut value_change_data = Vec::new();
 
             for (i, block) in blocks.into_iter().enumerate() {
-                let block = block.take_block();
+                let mut block = block.take_block();
+                block.set_options(options.clone());
                 match block.block_type {
                     BlockType::HierarchyGz
                     | BlockType::HierarchyLz4
@@ -0,0 +1,303 @@
+use std::{
+    io::Read,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use nom::error::{ErrorKind, ParseError, VerboseError};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{
+    block_parsers::{DecompressError, SizeMismatch},
+    data_types::BlockLocation,
+};
+
+/// How the block parsers handle data that does not follow the format but can still be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseMode {
+    /// Log every anomaly, add it to the diagnostics and keep reading
+    #[default]
+    Lenient,
+    /// Fail on the first anomaly
+    Strict,
+}
+
+/// Options of the block parsers, given to every block by [crate::parse_with_options].
+///
+/// Clones share their diagnostics, so the anomalies of all blocks of a file end up in one list.
+/// Sizes and counts over the [Limits] fail in both modes.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    pub limits: Limits,
+    diagnostics: Arc<Mutex<Vec<Anomaly>>>,
+}
+
+impl ParseOptions {
+    /// Fail on any anomaly, for checking that a writer follows the format
+    pub fn strict() -> Self {
+        Self {
+            mode: ParseMode::Strict,
+            ..Self::default()
+        }
+    }
+
+    /// Read imperfect files and collect their anomalies
+    pub fn lenient() -> Self {
+        Self::default()
+    }
+
+    /// The anomalies found so far in lenient mode
+    pub fn diagnostics(&self) -> Vec<Anomaly> {
+        self.lock().clone()
+    }
+
+    /// The anomalies found so far in lenient mode, the list is empty afterwards
+    pub fn take_diagnostics(&self) -> Vec<Anomaly> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Fail with `anomaly` in strict mode, log it and add it to the diagnostics otherwise
+    pub(crate) fn report(&self, anomaly: Anomaly) -> Result<(), Anomaly> {
+        match self.mode {
+            ParseMode::Strict => Err(anomaly),
+            ParseMode::Lenient => {
+                warn!("{anomaly}");
+                self.lock().push(anomaly);
+                Ok(())
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Anomaly>> {
+        self.diagnostics
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Bounds on the memory the block parsers take for the sizes and counts a file declares.
+///
+/// The defaults allow large files, but refuse sizes that a 32 bit viewer could not allocate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Limits {
+    /// Largest uncompressed size of one compressed section, like the hierarchy or a time table
+    pub max_decompressed_size: usize,
+    /// Largest number of entries of one table, like the signals or the times of a block
+    pub max_elements: usize,
+    /// Largest sum of the decompressed data and tables while one block is read
+    pub max_total_allocation: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_decompressed_size: 1 << 30,
+            max_elements: 1 << 27,
+            max_total_allocation: 1 << 31,
+        }
+    }
+}
+
+impl Limits {
+    /// Trust every size and count of the file
+    pub fn unlimited() -> Self {
+        Self {
+            max_decompressed_size: usize::MAX,
+            max_elements: usize::MAX,
+            max_total_allocation: usize::MAX,
+        }
+    }
+}
+
+/// A size or count of the file that is over the [Limits]
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LimitError {
+    #[error("{what} is larger than the limit of {limit} bytes")]
+    DecompressedSize { what: &'static str, limit: usize },
+    #[error("{count} {what} are more than the limit of {limit}")]
+    Elements {
+        what: &'static str,
+        count: usize,
+        limit: usize,
+    },
+    #[error("reading the block needs more than the limit of {limit} bytes")]
+    TotalAllocation { limit: usize },
+}
+
+/// Memory taken while one block is read, checked against the [Limits]
+pub(crate) struct Allocation {
+    limits: Limits,
+    used: usize,
+    /// the first refused allocation, for parsers that can only fail with a nom error
+    exceeded: Option<LimitError>,
+}
+
+impl Allocation {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            used: 0,
+            exceeded: None,
+        }
+    }
+
+    /// Take `bytes` for a table or a copy of block data
+    pub(crate) fn claim(&mut self, bytes: usize) -> Result<(), LimitError> {
+        self.used = self.used.saturating_add(bytes);
+        if self.used > self.limits.max_total_allocation {
+            return self.refuse(LimitError::TotalAllocation {
+                limit: self.limits.max_total_allocation,
+            });
+        }
+        Ok(())
+    }
+
+    /// Take a table of `count` entries of `T`
+    pub(crate) fn elements<T>(
+        &mut self,
+        what: &'static str,
+        count: usize,
+    ) -> Result<(), LimitError> {
+        if count > self.limits.max_elements {
+            return self.refuse(LimitError::Elements {
+                what,
+                count,
+                limit: self.limits.max_elements,
+            });
+        }
+        self.claim(count.saturating_mul(std::mem::size_of::<T>()))
+    }
+
+    /// Take one more entry of `T` for a table that already has `count` entries,
+    /// for tables that grow while they are decoded
+    pub(crate) fn element<T>(
+        &mut self,
+        what: &'static str,
+        count: usize,
+    ) -> Result<(), LimitError> {
+        if count >= self.limits.max_elements {
+            return self.refuse(LimitError::Elements {
+                what,
+                count: count.saturating_add(1),
+                limit: self.limits.max_elements,
+            });
+        }
+        self.claim(std::mem::size_of::<T>())
+    }
+
+    /// Take `size` bytes of decompressed data
+    pub(crate) fn decompressed(
+        &mut self,
+        what: &'static str,
+        size: usize,
+    ) -> Result<(), LimitError> {
+        if size > self.limits.max_decompressed_size {
+            return self.refuse(LimitError::DecompressedSize {
+                what,
+                limit: self.limits.max_decompressed_size,
+            });
+        }
+        self.claim(size)
+    }
+
+    /// Read a decompressor to the end, but not further than the limits allow
+    pub(crate) fn read_to_end(
+        &mut self,
+        what: &'static str,
+        reader: impl Read,
+    ) -> Result<Vec<u8>, DecompressError> {
+        let room = self
+            .limits
+            .max_decompressed_size
+            .min(self.limits.max_total_allocation.saturating_sub(self.used));
+        let mut data = Vec::new();
+        // one byte more than allowed shows that the data is too large
+        reader
+            .take((room as u64).saturating_add(1))
+            .read_to_end(&mut data)?;
+        self.decompressed(what, data.len())?;
+        Ok(data)
+    }
+
+    /// The first limit that was exceeded
+    pub(crate) fn exceeded(&self) -> Option<LimitError> {
+        self.exceeded.clone()
+    }
+
+    fn refuse(&mut self, error: LimitError) -> Result<(), LimitError> {
+        self.exceeded.get_or_insert_with(|| error.clone());
+        Err(error)
+    }
+}
+
+/// Fail a parser when `result` is a refused allocation, the caller gets the [LimitError]
+/// from [Allocation::exceeded]
+pub(crate) fn within_limits<T>(
+    input: &[u8],
+    result: Result<T, LimitError>,
+) -> Result<T, nom::Err<VerboseError<&[u8]>>> {
+    result.map_err(|_| nom::Err::Failure(VerboseError::from_error_kind(input, ErrorKind::TooLarge)))
+}
+
+/// Fail a parser with a decompression error, refused allocations like [within_limits]
+pub(crate) fn decompress_failed(
+    input: &[u8],
+    error: DecompressError,
+) -> nom::Err<VerboseError<&[u8]>> {
+    match error {
+        DecompressError::LimitExceeded(_) => {
+            nom::Err::Failure(VerboseError::from_error_kind(input, ErrorKind::TooLarge))
+        }
+        _ => nom::Err::Error(VerboseError::from_error_kind(input, ErrorKind::Fail)),
+    }
+}
+
+/// Data of a block that does not follow the format, but that the parser can read anyway
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[error("{kind} in {block}")]
+pub struct Anomaly {
+    pub block: BlockLocation,
+    pub kind: AnomalyKind,
+}
+
+/// What is wrong.
+///
+/// Offsets are in the data of the block after [crate::block_parsers::Block::extract_data],
+/// at the start of the token or record that holds the anomaly.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Uncompressed data is not as long as declared
+    #[error("{0}")]
+    SizeMismatch(SizeMismatch),
+    /// A string of the header that is not UTF-8, it is read with replacement characters.
+    /// The offset is that of the first invalid byte.
+    #[error("{what} at offset {offset} is not valid UTF-8")]
+    InvalidText { what: &'static str, offset: usize },
+    /// A byte that does not start any hierarchy token, it is skipped
+    #[error("unknown hierarchy token {byte} at offset {offset}")]
+    UnknownToken { byte: u8, offset: usize },
+    /// A code that is kept as `Unknown(code)`, or a blackout activity that is neither 0 nor 1
+    #[error("unknown {what} {code} at offset {offset}")]
+    UnknownCode {
+        what: &'static str,
+        code: u8,
+        offset: usize,
+    },
+    /// A scope end without an open scope, it is skipped
+    #[error("scope end at offset {offset} outside of any scope")]
+    StrayScopeEnd { offset: usize },
+    /// Scopes that are still open at the end of the hierarchy, they are closed
+    #[error("{count} scopes are not closed")]
+    UnclosedScopes { count: usize },
+    /// An enum table that can not be read, it is skipped
+    #[error("enum table {id} can not be read: {message}")]
+    InvalidEnumTable { id: u64, message: String },
+    /// A supplemental type attribute with values this crate does not know
+    #[error("unknown supplemental type {value}")]
+    UnknownSupplementalType { value: u64 },
+}
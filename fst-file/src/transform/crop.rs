@@ -0,0 +1,142 @@
+use std::io::{Seek, Write};
+
+use tracing::{debug, debug_span};
+
+use crate::{
+    block_parsers::value_change_data::ValueChanges,
+    data_types::Handle,
+    writer::{FstWriter, WriterError, WriterOptions},
+    FstFileContent,
+};
+
+use super::{changes_by_time, copy_hierarchy, Source, TransformError};
+
+/// Emit the value of every signal at `time` including the changes at `time`,
+/// or the value at the start of the block when `time` is [None]
+fn emit_values_at<W: Write + Seek>(
+    writer: &mut FstWriter<W>,
+    value_changes: &ValueChanges,
+    new_handles: &[Option<Handle>],
+    time: Option<u64>,
+) -> Result<(), WriterError> {
+    for (i, signal) in value_changes.signals.iter().enumerate() {
+        let (Some(signal), Some(Some(handle))) = (signal, new_handles.get(i)) else {
+            continue;
+        };
+        let value = signal
+            .changes
+            .iter()
+            .take_while(|(t, _)| time.is_some_and(|time| *t <= time))
+            .last()
+            .map_or(&signal.initial, |(_, value)| value);
+        if !value.is_empty() {
+            writer.emit_value_change(*handle, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a copy of `content` to `output` that only covers the time from `from` to `to`.
+///
+/// The value of every signal at `from` becomes the initial value.
+/// Value change data blocks outside of the window are dropped,
+/// blocks crossing the start or end of the window are encoded again
+/// and blocks inside of it are copied as they are.
+/// The window is limited to the start and end time of the input.
+pub fn crop<W: Write + Seek>(
+    content: &FstFileContent,
+    from: u64,
+    to: u64,
+    output: W,
+    options: WriterOptions,
+) -> Result<W, TransformError> {
+    let _span = debug_span!("crop", from, to).entered();
+    let source = Source::new(content)?;
+    let from = from.max(source.header.start_time);
+    let to = to.min(source.header.end_time);
+    if from > to {
+        return Err(TransformError::EmptyTimeRange { from, to });
+    }
+
+    let mut writer = FstWriter::new(output, source.writer_options(options))?;
+    let new_handles = copy_hierarchy(&source, &mut writer)?;
+    // blocks can only be copied when every signal keeps its handle
+    let same_handles = new_handles
+        .iter()
+        .enumerate()
+        .all(|(i, handle)| *handle == Some(Handle::from_index(i)));
+
+    let all = |_| true;
+    let blocks = &source.content.value_change_data;
+    let mut started = false;
+    let mut last_copied = None;
+    let mut last_before = None;
+    for (index, block) in blocks.iter().enumerate() {
+        let (start_time, end_time) = block.get_time_range()?;
+        if end_time < from {
+            last_before = Some(block);
+            continue;
+        }
+        if start_time > to {
+            break;
+        }
+        if started && same_handles && start_time > from && end_time <= to {
+            debug!(index, "copy block");
+            let raw = block.get_block();
+            writer.write_encoded_block(raw.block_type, raw.get_data_raw(), start_time, end_time)?;
+            last_copied = Some(block);
+            continue;
+        }
+
+        let _span = debug_span!("encode block", index).entered();
+        let value_changes = block.get_value_changes(&source.geometry, all)?;
+        if !started {
+            // the values at `from` only go into the initial frame, not also as changes
+            emit_values_at(&mut writer, &value_changes, &new_handles, Some(from))?;
+            writer.emit_time_change(from)?;
+            started = true;
+        } else if last_copied.take().is_some() {
+            emit_values_at(&mut writer, &value_changes, &new_handles, None)?;
+        }
+        for (time, handle, value) in changes_by_time(&value_changes, &new_handles) {
+            if time > from && time <= to {
+                writer.emit_time_change(time)?;
+                writer.emit_value_change(handle, value)?;
+            }
+        }
+    }
+
+    if !started {
+        // the window is after the last value change
+        if let Some(block) = last_before {
+            let value_changes = block.get_value_changes(&source.geometry, all)?;
+            emit_values_at(&mut writer, &value_changes, &new_handles, Some(u64::MAX))?;
+            writer.emit_time_change(from)?;
+        } else {
+            writer.emit_time_change(from)?;
+        }
+    }
+    if writer.current_time().is_some_and(|time| time < to) {
+        if let Some(block) = last_copied {
+            let value_changes = block.get_value_changes(&source.geometry, all)?;
+            emit_values_at(&mut writer, &value_changes, &new_handles, Some(u64::MAX))?;
+        }
+        writer.emit_time_change(to)?;
+    }
+
+    let active_at_start = source
+        .dump_changes
+        .iter()
+        .take_while(|(_, time)| *time <= from)
+        .last()
+        .is_none_or(|(active, _)| *active);
+    if !active_at_start {
+        writer.push_dump_change(false, from);
+    }
+    for (active, time) in &source.dump_changes {
+        if *time > from && *time <= to {
+            writer.push_dump_change(*active, *time);
+        }
+    }
+    Ok(writer.finish()?)
+}
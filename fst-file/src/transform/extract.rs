@@ -0,0 +1,86 @@
+use std::io::{Seek, Write};
+
+use tracing::{debug, debug_span};
+
+use crate::{
+    glob::{glob_match, is_glob},
+    writer::{FstWriter, WriterOptions},
+    FstFileContent,
+};
+
+use super::{copy_selected_hierarchy, copy_value_changes, Source, TransformError};
+
+/// Selects signals by their hierarchy path.
+///
+/// Each pattern is either a path like `top.cpu.pc` or a glob like `top.*.pc` or `top.**.valid`.
+/// A signal is selected when its path or the path of one of its scopes matches.
+/// The bit range of a variable name (`data [7:0]`) can be left out.
+#[derive(Debug, Clone, Default)]
+pub struct SignalSelector {
+    patterns: Vec<String>,
+}
+
+impl SignalSelector {
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            patterns: patterns.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    fn matches_pattern(pattern: &str, path: &str) -> bool {
+        if is_glob(pattern) {
+            glob_match(pattern, path, '.')
+        } else {
+            pattern == path
+        }
+    }
+
+    /// Whether the variable at `path` is selected
+    pub fn matches(&self, path: &str) -> bool {
+        let without_range = path
+            .rsplit_once(" [")
+            .map_or(path, |(name, _)| name.trim_end());
+        let scopes = path
+            .match_indices('.')
+            .map(|(i, _)| &path[..i])
+            .filter(|scope| !scope.contains(" ["));
+        [path, without_range].into_iter().chain(scopes).any(|p| {
+            self.patterns
+                .iter()
+                .any(|pattern| Self::matches_pattern(pattern, p))
+        })
+    }
+}
+
+/// Write a copy of `content` to `output` that only holds the signals selected by `selector`.
+///
+/// The hierarchy is pruned to the scopes holding selected signals
+/// and the signals get new handles in the order they appear in the hierarchy.
+/// Timescale, timezero, file type and date are taken from the input.
+pub fn extract_signals<W: Write + Seek>(
+    content: &FstFileContent,
+    selector: &SignalSelector,
+    output: W,
+    options: WriterOptions,
+) -> Result<W, TransformError> {
+    let _span = debug_span!("extract signals").entered();
+    let source = Source::new(content)?;
+    let mut writer = FstWriter::new(output, source.writer_options(options))?;
+    let new_handles =
+        copy_selected_hierarchy(&source, &mut writer, |path, _| selector.matches(path))?;
+    let selected = new_handles.iter().flatten().count();
+    debug!(selected, "selected signals");
+    if selected == 0 {
+        return Err(TransformError::NoSignalSelected);
+    }
+    copy_value_changes(&source, &mut writer, &new_handles)?;
+    Ok(writer.finish()?)
+}
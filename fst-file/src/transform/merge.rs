@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    io::{Seek, Write},
+    slice,
+};
+
+use tracing::{debug, debug_span};
+
+use crate::{
+    block_parsers::{hierarchy::ScopeType, value_change_data::ValueChangeDataBlock},
+    data_types::{Handle, TimeScale},
+    writer::{FstWriter, WriterOptions},
+    FstFileContent,
+};
+
+use super::{changes_by_time, copy_hierarchy, Source, TransformError};
+
+/// Converts the times of one input to the timescale and timezero of the output
+#[derive(Debug, Clone, Copy)]
+struct TimeMapping {
+    factor: u64,
+    shift: u64,
+}
+
+impl TimeMapping {
+    fn map(&self, time: u64) -> Result<u64, TransformError> {
+        time.checked_mul(self.factor)
+            .and_then(|time| time.checked_add(self.shift))
+            .ok_or(TransformError::TimeOverflow(time))
+    }
+}
+
+/// Pick the finest timescale and the earliest timezero of all inputs
+/// and find how the times of every input map onto them.
+fn reconcile_time(
+    sources: &[Source],
+) -> Result<(TimeScale, i64, Vec<TimeMapping>), TransformError> {
+    let exponent = sources
+        .iter()
+        .map(|source| source.header.timescale.0)
+        .min()
+        .unwrap_or_default();
+    let mut factors = Vec::with_capacity(sources.len());
+    let mut timezeros = Vec::with_capacity(sources.len());
+    for source in sources {
+        let difference = (source.header.timescale.0 - exponent) as u32;
+        let factor = 10u64
+            .checked_pow(difference)
+            .ok_or(TransformError::TimeOverflow(0))?;
+        factors.push(factor);
+        timezeros.push(source.header.timezero as i128 * factor as i128);
+    }
+    let timezero = timezeros.iter().copied().min().unwrap_or_default();
+    let mappings = factors
+        .into_iter()
+        .zip(timezeros)
+        .map(|(factor, source_timezero)| {
+            let shift = u64::try_from(source_timezero - timezero)
+                .map_err(|_| TransformError::TimeOverflow(0))?;
+            Ok(TimeMapping { factor, shift })
+        })
+        .collect::<Result<_, TransformError>>()?;
+    let timezero = i64::try_from(timezero).map_err(|_| TransformError::TimeOverflow(0))?;
+    Ok((TimeScale(exponent), timezero, mappings))
+}
+
+/// Decodes the value changes of one input block by block
+struct ChangeStream<'a> {
+    source: &'a Source<'a>,
+    new_handles: Vec<Option<Handle>>,
+    mapping: TimeMapping,
+    blocks: slice::Iter<'a, ValueChangeDataBlock>,
+    /// values at the start of the first block
+    initial: Vec<(Handle, Vec<u8>)>,
+    pending: VecDeque<(u64, Handle, Vec<u8>)>,
+}
+
+impl<'a> ChangeStream<'a> {
+    fn new(
+        source: &'a Source<'a>,
+        new_handles: Vec<Option<Handle>>,
+        mapping: TimeMapping,
+    ) -> Result<Self, TransformError> {
+        let mut stream = Self {
+            source,
+            new_handles,
+            mapping,
+            blocks: source.content.value_change_data.iter(),
+            initial: Vec::new(),
+            pending: VecDeque::new(),
+        };
+        if let Some(block) = stream.blocks.next() {
+            let value_changes = block.get_value_changes(&source.geometry, |_| true)?;
+            for (i, signal) in value_changes.signals.iter().enumerate() {
+                if let (Some(signal), Some(Some(handle))) = (signal, stream.new_handles.get(i)) {
+                    if !signal.initial.is_empty() {
+                        stream.initial.push((*handle, signal.initial.clone()));
+                    }
+                }
+            }
+            for (time, handle, value) in changes_by_time(&value_changes, &stream.new_handles) {
+                let time = stream.mapping.map(time)?;
+                stream.pending.push_back((time, handle, value.to_vec()));
+            }
+        }
+        Ok(stream)
+    }
+
+    fn emit_initial<W: Write + Seek>(
+        &self,
+        writer: &mut FstWriter<W>,
+    ) -> Result<(), TransformError> {
+        for (handle, value) in &self.initial {
+            writer.emit_value_change(*handle, value)?;
+        }
+        Ok(())
+    }
+
+    /// Values at `time`: the values at the start of the first block updated by the changes
+    /// up to `time`, which are taken from the stream
+    fn take_values_at(&mut self, time: u64) -> Result<BTreeMap<Handle, Vec<u8>>, TransformError> {
+        let mut values: BTreeMap<_, _> = std::mem::take(&mut self.initial).into_iter().collect();
+        while self.next_time()?.is_some_and(|next| next <= time) {
+            if let Some((_, handle, value)) = self.pop() {
+                values.insert(handle, value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Time of the next value change, decoding the next blocks when needed
+    fn next_time(&mut self) -> Result<Option<u64>, TransformError> {
+        while self.pending.is_empty() {
+            let Some(block) = self.blocks.next() else {
+                return Ok(None);
+            };
+            let value_changes = block.get_value_changes(&self.source.geometry, |_| true)?;
+            for (time, handle, value) in changes_by_time(&value_changes, &self.new_handles) {
+                let time = self.mapping.map(time)?;
+                self.pending.push_back((time, handle, value.to_vec()));
+            }
+        }
+        Ok(self.pending.front().map(|(time, _, _)| *time))
+    }
+
+    fn pop(&mut self) -> Option<(u64, Handle, Vec<u8>)> {
+        self.pending.pop_front()
+    }
+}
+
+/// Write the inputs side by side into `output`.
+///
+/// Every input is put under a new top scope with the given name, so the inputs
+/// can come from different simulations of the same time span.
+/// The output uses the finest timescale and the earliest timezero of the inputs
+/// and covers the time from the earliest start to the latest end.
+/// The dump is only inactive while all inputs are inactive.
+pub fn merge_side_by_side<W: Write + Seek>(
+    inputs: &[(&str, &FstFileContent)],
+    output: W,
+    options: WriterOptions,
+) -> Result<W, TransformError> {
+    let _span = debug_span!("merge side by side", inputs = inputs.len()).entered();
+    let sources = inputs
+        .iter()
+        .map(|(_, content)| Source::new(content))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(first) = sources.first() else {
+        return Err(TransformError::NoInput);
+    };
+    let (timescale, timezero, mappings) = reconcile_time(&sources)?;
+    debug!(?timescale, timezero, "reconciled time");
+    let options = WriterOptions {
+        timescale,
+        timezero,
+        ..first.writer_options(options)
+    };
+    let mut writer = FstWriter::new(output, options)?;
+
+    let mut streams = Vec::with_capacity(sources.len());
+    for ((source, (name, _)), mapping) in sources.iter().zip(inputs).zip(&mappings) {
+        writer.set_scope(ScopeType::VcdModule, name, "")?;
+        let new_handles = copy_hierarchy(source, &mut writer)?;
+        writer.set_upscope()?;
+        streams.push(ChangeStream::new(source, new_handles, *mapping)?);
+    }
+
+    let mut start_time = u64::MAX;
+    let mut end_time = 0;
+    for (source, mapping) in sources.iter().zip(&mappings) {
+        start_time = start_time.min(mapping.map(source.header.start_time)?);
+        end_time = end_time.max(mapping.map(source.header.end_time)?);
+    }
+    for stream in &streams {
+        stream.emit_initial(&mut writer)?;
+    }
+    writer.emit_time_change(start_time)?;
+    loop {
+        let mut next: Option<(usize, u64)> = None;
+        for (i, stream) in streams.iter_mut().enumerate() {
+            if let Some(time) = stream.next_time()? {
+                if next.is_none_or(|(_, next_time)| time < next_time) {
+                    next = Some((i, time));
+                }
+            }
+        }
+        let Some((time, handle, value)) = next.and_then(|(i, _)| streams[i].pop()) else {
+            break;
+        };
+        writer.emit_time_change(time)?;
+        writer.emit_value_change(handle, &value)?;
+    }
+    if writer.current_time().is_some_and(|time| time < end_time) {
+        writer.emit_time_change(end_time)?;
+    }
+
+    // inputs without blackout records are always active
+    let mut dump_changes = Vec::new();
+    for (i, (source, mapping)) in sources.iter().zip(&mappings).enumerate() {
+        for (active, time) in &source.dump_changes {
+            dump_changes.push((mapping.map(*time)?, i, *active));
+        }
+    }
+    dump_changes.sort_by_key(|(time, _, _)| *time);
+    let mut active_inputs = vec![true; sources.len()];
+    let mut active = true;
+    for (time, i, input_active) in dump_changes {
+        active_inputs[i] = input_active;
+        if active_inputs.contains(&true) != active {
+            active = !active;
+            writer.push_dump_change(active, time);
+        }
+    }
+    Ok(writer.finish()?)
+}
+
+/// Write the inputs back to back into `output`.
+///
+/// The inputs are consecutive time segments of the same design, like checkpointed simulation runs,
+/// and have to be given in the order of their start time.
+/// The hierarchy of the first input is used and the variables of the other inputs are matched by their path.
+/// When segments overlap, the later segment takes over from its start time.
+/// The output uses the finest timescale and the earliest timezero of the inputs.
+pub fn merge_back_to_back<W: Write + Seek>(
+    inputs: &[&FstFileContent],
+    output: W,
+    options: WriterOptions,
+) -> Result<W, TransformError> {
+    let _span = debug_span!("merge back to back", inputs = inputs.len()).entered();
+    let sources = inputs
+        .iter()
+        .map(|content| Source::new(content))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(first) = sources.first() else {
+        return Err(TransformError::NoInput);
+    };
+    let (timescale, timezero, mappings) = reconcile_time(&sources)?;
+    debug!(?timescale, timezero, "reconciled time");
+    let options = WriterOptions {
+        timescale,
+        timezero,
+        ..first.writer_options(options)
+    };
+    let mut writer = FstWriter::new(output, options)?;
+    let first_handles = copy_hierarchy(first, &mut writer)?;
+    let first_paths = first.var_paths();
+    let paths: HashMap<_, _> = first_paths
+        .iter()
+        .rev()
+        .map(|(path, handle)| (path.as_str(), *handle))
+        .collect();
+
+    let mut segments = Vec::with_capacity(sources.len());
+    for (index, (source, mapping)) in sources.iter().zip(&mappings).enumerate() {
+        let new_handles = if index == 0 {
+            first_handles.clone()
+        } else {
+            let mut new_handles = vec![None; source.geometry.len()];
+            for (path, handle) in source.var_paths() {
+                let first_handle = *paths
+                    .get(path.as_str())
+                    .ok_or_else(|| TransformError::HierarchyMismatch(path.clone()))?;
+                if source.geometry.get(handle) != first.geometry.get(first_handle) {
+                    return Err(TransformError::HierarchyMismatch(path));
+                }
+                if let Some(new_handle) = handle.index().and_then(|i| new_handles.get_mut(i)) {
+                    *new_handle = first_handle
+                        .index()
+                        .and_then(|i| first_handles.get(i))
+                        .copied()
+                        .flatten();
+                }
+            }
+            new_handles
+        };
+        let start_time = mapping.map(source.header.start_time)?;
+        let end_time = mapping.map(source.header.end_time)?;
+        if segments
+            .last()
+            .is_some_and(|(_, previous_start, _, _)| start_time < *previous_start)
+        {
+            return Err(TransformError::SegmentOrder(index));
+        }
+        segments.push((source, start_time, end_time, new_handles));
+    }
+
+    let mut active = true;
+    // value of every signal written so far, a segment only changes the ones that differ
+    let mut last_values = HashMap::new();
+    for (index, (source, start_time, end_time, new_handles)) in segments.iter().enumerate() {
+        let _span = debug_span!("copy segment", index).entered();
+        let mapping = mappings[index];
+        // the next segment takes over at its start time
+        let cut = segments.get(index + 1).map(|(_, start, _, _)| *start);
+        let before_cut = |time: u64| cut.is_none_or(|cut| time < cut);
+
+        let mut stream = ChangeStream::new(source, new_handles.clone(), mapping)?;
+        if index == 0 {
+            stream.emit_initial(&mut writer)?;
+            last_values.extend(stream.initial.iter().cloned());
+            writer.emit_time_change(*start_time)?;
+        } else {
+            writer.emit_time_change(*start_time)?;
+            for (handle, value) in stream.take_values_at(*start_time)? {
+                if last_values.get(&handle) != Some(&value) {
+                    writer.emit_value_change(handle, &value)?;
+                    last_values.insert(handle, value);
+                }
+            }
+        }
+        while let Some(time) = stream.next_time()? {
+            if !before_cut(time) {
+                break;
+            }
+            let Some((time, handle, value)) = stream.pop() else {
+                break;
+            };
+            writer.emit_time_change(time)?;
+            writer.emit_value_change(handle, &value)?;
+            last_values.insert(handle, value);
+        }
+        if before_cut(*end_time) && writer.current_time().is_some_and(|time| time < *end_time) {
+            writer.emit_time_change(*end_time)?;
+        }
+
+        let mut segment_active = true;
+        let mut dump_changes = Vec::new();
+        for (dump_active, time) in &source.dump_changes {
+            let time = mapping.map(*time)?;
+            if time <= *start_time {
+                segment_active = *dump_active;
+            } else if before_cut(time) {
+                dump_changes.push((*dump_active, time));
+            }
+        }
+        if segment_active != active {
+            active = segment_active;
+            writer.push_dump_change(active, *start_time);
+        }
+        for (dump_active, time) in dump_changes {
+            if dump_active != active {
+                active = dump_active;
+                writer.push_dump_change(active, time);
+            }
+        }
+    }
+    Ok(writer.finish()?)
+}
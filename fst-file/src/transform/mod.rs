@@ -0,0 +1,534 @@
+use std::{
+    collections::HashMap,
+    io::{Seek, Write},
+    iter::Peekable,
+    slice,
+};
+
+use nom::error::VerboseErrorKind;
+use thiserror::Error;
+use tracing::debug_span;
+
+use crate::{
+    block_parsers::{
+        blackout::BlackoutParseError,
+        geometry::{Geometry, GeometryParseError},
+        header::{HeaderBlockContent, HeaderParseError},
+        hierarchy::{
+            Attribute, AttributeType, EnumTable, HierarchyBlockConvertError, HierarchyToken,
+            MiscType,
+        },
+        value_change_data::{ValueChangeDataError, ValueChanges},
+        DecompressError,
+    },
+    data_types::Handle,
+    error::PositionError,
+    writer::{FstWriter, WriterError, WriterOptions},
+    FstFileContent,
+};
+
+mod crop;
+mod extract;
+mod merge;
+mod repack;
+
+pub use crop::*;
+pub use extract::*;
+pub use merge::*;
+pub use repack::*;
+
+#[derive(Debug, Error)]
+pub enum TransformError {
+    #[error("parse error: {0}")]
+    Parse(#[from] PositionError<VerboseErrorKind>),
+    #[error("the input has no {0} block")]
+    MissingBlock(&'static str),
+    #[error("header error: {0}")]
+    Header(#[from] HeaderParseError),
+    #[error("geometry error: {0}")]
+    Geometry(#[from] GeometryParseError),
+    #[error("hierarchy error: {0}")]
+    Hierarchy(#[from] HierarchyBlockConvertError),
+    #[error("blackout error: {0}")]
+    Blackout(#[from] BlackoutParseError),
+    #[error("value change data error: {0}")]
+    ValueChangeData(#[from] ValueChangeDataError),
+    #[error("decompress error: {0}")]
+    Decompress(#[from] DecompressError),
+    #[error("writer error: {0}")]
+    Writer(#[from] WriterError),
+    #[error("no signal was selected")]
+    NoSignalSelected,
+    #[error("the time range from {from} to {to} is empty")]
+    EmptyTimeRange { from: u64, to: u64 },
+    #[error("no input was given")]
+    NoInput,
+    #[error("time {0} does not fit into the timescale of the output")]
+    TimeOverflow(u64),
+    #[error("the variable {0} does not match the hierarchy of the first input")]
+    HierarchyMismatch(String),
+    #[error("input {0} starts before the previous input")]
+    SegmentOrder(usize),
+}
+
+/// Decoded parts of an input file that are needed to write a transformed copy
+pub(crate) struct Source<'a> {
+    content: &'a FstFileContent,
+    header: HeaderBlockContent,
+    geometry: Geometry,
+    tokens: Vec<HierarchyToken>,
+    /// dump activity changes with absolute time
+    dump_changes: Vec<(bool, u64)>,
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(content: &'a FstFileContent) -> Result<Self, TransformError> {
+        let _span = debug_span!("read transform source").entered();
+        let header = content
+            .header
+            .as_ref()
+            .ok_or(TransformError::MissingBlock("header"))?
+            .get_content()?;
+        let geometry = content
+            .geometry
+            .as_ref()
+            .ok_or(TransformError::MissingBlock("geometry"))?
+            .get_content()?;
+        let tokens = content
+            .hierarchy
+            .as_ref()
+            .ok_or(TransformError::MissingBlock("hierarchy"))?
+            .get_tokens()?
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect();
+        let dump_changes = match &content.blackout {
+            Some(blackout) => blackout.get_content()?.dump_changes().collect(),
+            None => Vec::new(),
+        };
+        Ok(Self {
+            content,
+            header,
+            geometry,
+            tokens,
+            dump_changes,
+        })
+    }
+
+    /// Full path (scope names joined with `.`) and handle of every variable in hierarchy order
+    pub(crate) fn var_paths(&self) -> Vec<(String, Handle)> {
+        let mut vars = Vec::new();
+        let mut path = Vec::new();
+        let mut next_handle = 0;
+        for token in &self.tokens {
+            match token {
+                HierarchyToken::ScopeBegin(scope) => path.push(scope.name()),
+                HierarchyToken::ScopeEnd => {
+                    path.pop();
+                }
+                HierarchyToken::Vcd(var) => {
+                    let handle = var.alias().unwrap_or_else(|| {
+                        next_handle += 1;
+                        Handle(next_handle)
+                    });
+                    let full_path = path
+                        .iter()
+                        .copied()
+                        .chain([var.name()])
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    vars.push((full_path, handle));
+                }
+                _ => {}
+            }
+        }
+        vars
+    }
+
+    /// Take over the settings of the input that describe the waveform
+    pub(crate) fn writer_options(&self, options: WriterOptions) -> WriterOptions {
+        WriterOptions {
+            timescale: self.header.timescale,
+            timezero: self.header.timezero,
+            filetype: self.header.filetype,
+            date: Some(self.header.date.clone()),
+            ..options
+        }
+    }
+}
+
+enum PendingAttribute<'a> {
+    Begin(&'a Attribute),
+    End,
+}
+
+/// Whether `attribute` defines an enum table or a source path for later entries
+/// instead of annotating the entry that follows it
+fn is_definition(attribute: &Attribute) -> bool {
+    match (attribute.attr_type(), attribute.misc_type()) {
+        (AttributeType::Misc, MiscType::EnumTable) => !attribute.name().is_empty(),
+        (AttributeType::Misc, MiscType::PathName) => true,
+        _ => false,
+    }
+}
+
+/// Attributes apply to the scope or variable that follows them,
+/// so they are held back until it is known whether that entry is kept.
+/// Definitions are written either way, kept entries after them can refer to them.
+///
+/// The ids of enum tables and source paths are given anew by the writer,
+/// so the hierarchies of several inputs can be copied into one output.
+#[derive(Default)]
+struct AttributeFilter<'a> {
+    pending: Vec<PendingAttribute<'a>>,
+    /// whether each open attribute was written
+    open: Vec<bool>,
+    /// source paths by their id in the input, the writer defines them again when they are used
+    source_paths: HashMap<u64, String>,
+    /// ids of the enum tables in the output by their id in the input
+    enum_tables: HashMap<u64, u64>,
+}
+
+impl<'a> AttributeFilter<'a> {
+    fn begin(&mut self, attribute: &'a Attribute) {
+        self.pending.push(PendingAttribute::Begin(attribute));
+    }
+
+    fn end<W: Write + Seek>(&mut self, writer: &mut FstWriter<W>) -> Result<(), WriterError> {
+        if self.unmatched_pending().next().is_some() {
+            self.pending.push(PendingAttribute::End);
+        } else if self.open.pop() == Some(true) {
+            writer.set_attr_end()?;
+        }
+        Ok(())
+    }
+
+    /// Indices of pending attribute begins that are not closed yet
+    fn unmatched_pending(&self) -> impl Iterator<Item = usize> {
+        let mut stack = Vec::new();
+        for (i, pending) in self.pending.iter().enumerate() {
+            match pending {
+                PendingAttribute::Begin(_) => stack.push(i),
+                PendingAttribute::End => {
+                    stack.pop();
+                }
+            }
+        }
+        stack.into_iter()
+    }
+
+    /// Write the pending attributes because the following entry is kept
+    fn flush<W: Write + Seek>(&mut self, writer: &mut FstWriter<W>) -> Result<(), WriterError> {
+        for pending in std::mem::take(&mut self.pending) {
+            match pending {
+                PendingAttribute::Begin(attribute) => {
+                    let opened = self.copy(writer, attribute)?;
+                    self.open.push(opened);
+                }
+                PendingAttribute::End => {
+                    if self.open.pop() == Some(true) {
+                        writer.set_attr_end()?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the attributes that apply to an entry that is not kept and write the definitions.
+    /// Attributes that were already closed do not apply to it and stay pending.
+    fn drop_unmatched<W: Write + Seek>(
+        &mut self,
+        writer: &mut FstWriter<W>,
+    ) -> Result<(), WriterError> {
+        let unmatched: Vec<_> = self.unmatched_pending().collect();
+        for &i in &unmatched {
+            if let PendingAttribute::Begin(attribute) = self.pending[i] {
+                let opened = is_definition(attribute) && self.copy(writer, attribute)?;
+                self.open.push(opened);
+            }
+        }
+        for i in unmatched.into_iter().rev() {
+            self.pending.remove(i);
+        }
+        Ok(())
+    }
+
+    /// Write `attribute` with the ids of the output, returns whether it has to be ended.
+    /// Stems and enum table references with an unknown id are dropped.
+    fn copy<W: Write + Seek>(
+        &mut self,
+        writer: &mut FstWriter<W>,
+        attribute: &Attribute,
+    ) -> Result<bool, WriterError> {
+        match (attribute.attr_type(), attribute.misc_type()) {
+            (AttributeType::Misc, MiscType::PathName) => {
+                self.source_paths
+                    .insert(attribute.value(), attribute.name().to_string());
+            }
+            (AttributeType::Misc, misc_type @ (MiscType::SourceStem | MiscType::SourceIStem)) => {
+                let path = attribute
+                    .path_id()
+                    .and_then(|id| self.source_paths.get(&id));
+                if let Some(path) = path {
+                    let instantiation = misc_type == MiscType::SourceIStem;
+                    writer.set_source_stem(path, attribute.value(), instantiation)?;
+                }
+            }
+            (AttributeType::Misc, MiscType::EnumTable) if attribute.name().is_empty() => {
+                if let Some(id) = self.enum_tables.get(&attribute.value()) {
+                    writer.emit_enum_table_ref(*id)?;
+                }
+            }
+            (AttributeType::Misc, MiscType::EnumTable) => {
+                if let Ok(table) = EnumTable::parse(attribute.name()) {
+                    let id = writer.create_enum_table(&table)?;
+                    self.enum_tables.insert(attribute.value(), id);
+                }
+            }
+            _ => {
+                writer.copy_attr_begin(attribute)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Copy the whole hierarchy into `writer`, including the scopes without variables.
+/// Returns the new handle of every handle, indexed by the [Handle::index] of the input.
+pub(crate) fn copy_hierarchy<W: Write + Seek>(
+    source: &Source,
+    writer: &mut FstWriter<W>,
+) -> Result<Vec<Option<Handle>>, TransformError> {
+    copy_hierarchy_with(source, writer, |_, _| true, false)
+}
+
+/// Copy the hierarchy into `writer`, keeping only the variables for which `keep` returns true.
+///
+/// `keep` gets the full path of the variable (scope names joined with `.`) and its handle.
+/// Scopes that do not contain any kept variable are dropped.
+/// Returns the new handle of every kept handle, indexed by the [Handle::index] of the input.
+pub(crate) fn copy_selected_hierarchy<W: Write + Seek>(
+    source: &Source,
+    writer: &mut FstWriter<W>,
+    keep: impl FnMut(&str, Handle) -> bool,
+) -> Result<Vec<Option<Handle>>, TransformError> {
+    copy_hierarchy_with(source, writer, keep, true)
+}
+
+fn copy_hierarchy_with<W: Write + Seek>(
+    source: &Source,
+    writer: &mut FstWriter<W>,
+    mut keep: impl FnMut(&str, Handle) -> bool,
+    prune_scopes: bool,
+) -> Result<Vec<Option<Handle>>, TransformError> {
+    let _span = debug_span!("copy hierarchy", prune_scopes).entered();
+    let tokens = &source.tokens;
+
+    // find the kept variables and the scopes that contain them
+    let mut kept = vec![false; tokens.len()];
+    let mut scopes = Vec::new();
+    let var_paths = source.var_paths();
+    let mut vars = var_paths.iter();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            HierarchyToken::ScopeBegin(_) => {
+                kept[i] = !prune_scopes;
+                scopes.push(i);
+            }
+            HierarchyToken::ScopeEnd => {
+                scopes.pop();
+            }
+            HierarchyToken::Vcd(_) => {
+                let Some((full_path, handle)) = vars.next() else {
+                    continue;
+                };
+                if keep(full_path, *handle) {
+                    kept[i] = true;
+                    for scope in &scopes {
+                        kept[*scope] = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut new_handles = vec![None; source.geometry.len()];
+    let mut handles = var_paths.iter().map(|(_, handle)| *handle);
+    let mut attributes = AttributeFilter::default();
+    let mut skip_depth = 0;
+    let mut open_scopes = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if skip_depth > 0 {
+            match token {
+                HierarchyToken::Attribute(attribute) if is_definition(attribute) => {
+                    attributes.copy(writer, attribute)?;
+                }
+                HierarchyToken::ScopeBegin(_) => skip_depth += 1,
+                HierarchyToken::ScopeEnd => skip_depth -= 1,
+                HierarchyToken::Vcd(_) => {
+                    handles.next();
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match token {
+            HierarchyToken::Attribute(attribute) => attributes.begin(attribute),
+            HierarchyToken::AttributeEnd => attributes.end(writer)?,
+            HierarchyToken::ScopeBegin(scope) => {
+                if kept[i] {
+                    attributes.flush(writer)?;
+                    writer.set_scope(scope.scope_type(), scope.name(), scope.component())?;
+                    open_scopes += 1;
+                } else {
+                    attributes.drop_unmatched(writer)?;
+                    skip_depth = 1;
+                }
+            }
+            HierarchyToken::ScopeEnd => {
+                attributes.drop_unmatched(writer)?;
+                attributes.flush(writer)?;
+                if open_scopes > 0 {
+                    writer.set_upscope()?;
+                    open_scopes -= 1;
+                }
+            }
+            HierarchyToken::Vcd(var) => {
+                let index = handles.next().and_then(Handle::index);
+                let Some(index) = index.filter(|&index| kept[i] && index < new_handles.len())
+                else {
+                    attributes.drop_unmatched(writer)?;
+                    continue;
+                };
+                attributes.flush(writer)?;
+                let alias = new_handles[index];
+                let new_handle = writer.create_var(
+                    var.var_type(),
+                    var.direction(),
+                    var.length() as u32,
+                    var.name(),
+                    alias,
+                )?;
+                new_handles[index] = Some(new_handle);
+            }
+            HierarchyToken::Unknown(_) => {}
+        }
+    }
+    Ok(new_handles)
+}
+
+/// Value changes of all signals that have a new handle, ordered by time
+pub(crate) fn changes_by_time<'a>(
+    value_changes: &'a ValueChanges,
+    new_handles: &[Option<Handle>],
+) -> Vec<(u64, Handle, &'a [u8])> {
+    let mut changes: Vec<_> = value_changes
+        .signals
+        .iter()
+        .enumerate()
+        .filter_map(|(i, signal)| Some((new_handles.get(i).copied()??, signal.as_ref()?)))
+        .flat_map(|(handle, signal)| {
+            signal
+                .changes
+                .iter()
+                .map(move |(time, value)| (*time, handle, &value[..]))
+        })
+        .collect();
+    changes.sort_by_key(|(time, _, _)| *time);
+    changes
+}
+
+/// Write the value changes of the input for the signals that have a new handle.
+///
+/// The start and end time of the input are kept even when no selected signal changes at that time.
+pub(crate) fn copy_value_changes<W: Write + Seek>(
+    source: &Source,
+    writer: &mut FstWriter<W>,
+    new_handles: &[Option<Handle>],
+) -> Result<(), TransformError> {
+    let _span = debug_span!("copy value changes").entered();
+    let mut cursor = TimeCursor::new(&source.dump_changes);
+    let selected = |handle: Handle| {
+        handle
+            .index()
+            .and_then(|index| new_handles.get(index))
+            .is_some_and(Option::is_some)
+    };
+    let mut end_time = None;
+    for (index, block) in source.content.value_change_data.iter().enumerate() {
+        let _span = debug_span!("copy block", index).entered();
+        let value_changes = block.get_value_changes(&source.geometry, selected)?;
+        if index == 0 {
+            for (i, signal) in value_changes.signals.iter().enumerate() {
+                if let (Some(signal), Some(Some(handle))) = (signal, new_handles.get(i)) {
+                    if !signal.initial.is_empty() {
+                        writer.emit_value_change(*handle, &signal.initial)?;
+                    }
+                }
+            }
+            cursor.advance(writer, value_changes.start_time)?;
+        }
+
+        for (time, handle, value) in changes_by_time(&value_changes, new_handles) {
+            cursor.advance(writer, time)?;
+            writer.emit_value_change(handle, value)?;
+        }
+        end_time = Some(value_changes.end_time);
+    }
+    if let Some(end_time) = end_time {
+        cursor.advance(writer, end_time)?;
+    }
+    cursor.finish(writer)?;
+    Ok(())
+}
+
+/// Moves the writer forward in time and writes the dump activity changes on the way
+struct TimeCursor<'a> {
+    dump_changes: Peekable<slice::Iter<'a, (bool, u64)>>,
+    current: Option<u64>,
+}
+
+impl<'a> TimeCursor<'a> {
+    fn new(dump_changes: &'a [(bool, u64)]) -> Self {
+        Self {
+            dump_changes: dump_changes.iter().peekable(),
+            current: None,
+        }
+    }
+
+    fn move_to<W: Write + Seek>(
+        &mut self,
+        writer: &mut FstWriter<W>,
+        time: u64,
+    ) -> Result<(), WriterError> {
+        // time never goes backwards in the output
+        let time = self.current.map_or(time, |current| current.max(time));
+        writer.emit_time_change(time)?;
+        self.current = Some(time);
+        Ok(())
+    }
+
+    fn advance<W: Write + Seek>(
+        &mut self,
+        writer: &mut FstWriter<W>,
+        time: u64,
+    ) -> Result<(), WriterError> {
+        while let Some((active, dump_time)) = self.dump_changes.next_if(|(_, t)| *t <= time) {
+            self.move_to(writer, *dump_time)?;
+            writer.emit_dump_active(*active)?;
+        }
+        self.move_to(writer, time)
+    }
+
+    /// Write the dump activity changes after the last value change
+    fn finish<W: Write + Seek>(mut self, writer: &mut FstWriter<W>) -> Result<(), WriterError> {
+        while let Some((active, dump_time)) = self.dump_changes.next() {
+            self.move_to(writer, *dump_time)?;
+            writer.emit_dump_active(*active)?;
+        }
+        Ok(())
+    }
+}
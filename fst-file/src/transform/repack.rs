@@ -0,0 +1,103 @@
+use std::{
+    borrow::Cow,
+    io::{Seek, Write},
+};
+
+use nom::Finish;
+use tracing::debug_span;
+
+use crate::{
+    block_parsers::Block,
+    data_types::{BlockType, Handle, WriterPackType},
+    error::PositionError,
+    writer::{CopiedHierarchy, FstWriter, WriterOptions},
+    FstFileContent,
+};
+
+use super::{copy_hierarchy, copy_value_changes, Source, TransformError};
+
+/// Write a copy of `content` to `output` with the compression settings of `options`.
+///
+/// Every signal keeps its handle and all value changes are encoded again,
+/// so the hierarchy compression, pack type and block size of the output can differ from the input.
+pub fn repack<W: Write + Seek>(
+    content: &FstFileContent,
+    output: W,
+    options: WriterOptions,
+) -> Result<W, TransformError> {
+    let _span = debug_span!("repack").entered();
+    let source = Source::new(content)?;
+    let mut writer = FstWriter::new(output, source.writer_options(options))?;
+    let new_handles = copy_hierarchy(&source, &mut writer)?;
+    copy_value_changes(&source, &mut writer, &new_handles)?;
+    Ok(writer.finish()?)
+}
+
+/// Write a copy of `content` to `output` with value change data blocks
+/// of the size and time span set in `options`.
+///
+/// Unlike [repack] the geometry and hierarchy blocks are copied as they are,
+/// so only the value change data blocks change.
+/// The initial values of every block are the values at its start time.
+pub fn rechunk<W: Write + Seek>(
+    content: &FstFileContent,
+    output: W,
+    options: WriterOptions,
+) -> Result<W, TransformError> {
+    let _span = debug_span!("rechunk", block_size = options.block_size).entered();
+    let source = Source::new(content)?;
+    let geometry = content
+        .geometry
+        .as_ref()
+        .ok_or(TransformError::MissingBlock("geometry"))?
+        .get_block();
+    let hierarchy = content
+        .hierarchy
+        .as_ref()
+        .ok_or(TransformError::MissingBlock("hierarchy"))?
+        .get_block();
+    let mut writer = FstWriter::new(output, source.writer_options(options))?;
+    writer.copy_encoded_hierarchy(
+        &source.header,
+        &source.geometry,
+        CopiedHierarchy {
+            geometry: geometry.get_data_raw().to_vec(),
+            hierarchy_type: hierarchy.block_type,
+            hierarchy: hierarchy.get_data_raw().to_vec(),
+        },
+    )?;
+    let new_handles: Vec<_> = (0..source.geometry.len())
+        .map(|index| Some(Handle::from_index(index)))
+        .collect();
+    copy_value_changes(&source, &mut writer, &new_handles)?;
+    Ok(writer.finish()?)
+}
+
+/// Compression of the value changes in the first value change data block of `content`,
+/// [None] when there is no such block
+pub fn pack_type(content: &FstFileContent) -> Result<Option<WriterPackType>, TransformError> {
+    let Some(block) = content.value_change_data.first() else {
+        return Ok(None);
+    };
+    let header = content
+        .header
+        .as_ref()
+        .ok_or(TransformError::MissingBlock("header"))?
+        .get_content()?;
+    Ok(Some(
+        block.get_intermediate_content(&header)?.waves_pack_type(),
+    ))
+}
+
+/// The FST file inside of a [BlockType::GZippedWrapper] block,
+/// or `input` itself when it is not wrapped
+pub fn unwrap_gzip_wrapper(input: &[u8]) -> Result<Cow<'_, [u8]>, TransformError> {
+    if input.first() != Some(&(BlockType::GZippedWrapper as u8)) {
+        return Ok(Cow::Borrowed(input));
+    }
+    let _span = debug_span!("unwrap gzip wrapper").entered();
+    let (_, (_, block)) = Block::parse_block_with_position(input)
+        .finish()
+        .map_err(|e| PositionError::from_verbose_parse_error(e, input).in_file(input))?;
+    Ok(Cow::Owned(block.extract_data()?))
+}
@@ -0,0 +1,267 @@
+use std::io::{self, Write};
+
+use flate2::{
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+
+use crate::{
+    block_parsers::{geometry::SignalGeometry, header::HeaderBlockContent},
+    data_types::{BlockType, SVarInt, VarInt, WriterPackType},
+    fastlz,
+};
+
+use super::HierarchyCompression;
+
+/// Write a whole block including the block type and length
+pub(super) fn write_block<W: Write>(
+    output: &mut W,
+    block_type: BlockType,
+    data: &[u8],
+) -> io::Result<()> {
+    output.write_all(&[block_type as u8])?;
+    output.write_all(&(data.len() as u64 + 8).to_be_bytes())?;
+    output.write_all(data)
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .expect("writing to a Vec never fails");
+    encoder.finish().expect("writing to a Vec never fails")
+}
+
+/// Compress with zlib only when it makes the data smaller.
+/// Readers treat data with equal compressed and uncompressed length as uncompressed.
+fn zlib_if_smaller(data: &[u8]) -> Vec<u8> {
+    let compressed = zlib(data);
+    if compressed.len() < data.len() {
+        compressed
+    } else {
+        data.to_vec()
+    }
+}
+
+fn c_str(buf: &mut Vec<u8>, s: &str, size: usize) {
+    let bytes = s.as_bytes();
+    let len = bytes.len().min(size - 1);
+    buf.extend_from_slice(&bytes[..len]);
+    buf.resize(buf.len() + size - len, 0);
+}
+
+pub(super) fn encode_header(header: &HeaderBlockContent) -> Vec<u8> {
+    let mut data = Vec::with_capacity(321);
+    data.extend_from_slice(&header.start_time.to_be_bytes());
+    data.extend_from_slice(&header.end_time.to_be_bytes());
+    data.extend_from_slice(&header.real_endianness.to_le_bytes());
+    data.extend_from_slice(&header.writer_memory_use.to_be_bytes());
+    data.extend_from_slice(&header.num_scopes.to_be_bytes());
+    data.extend_from_slice(&header.num_hierarchy_vars.to_be_bytes());
+    data.extend_from_slice(&(header.num_vars as u64).to_be_bytes());
+    data.extend_from_slice(&header.num_vc_blocks.to_be_bytes());
+    data.push(header.timescale.0 as u8);
+    c_str(&mut data, &header.writer, 128);
+    c_str(&mut data, &header.date, 26);
+    data.resize(data.len() + 93, 0);
+    data.push(header.filetype as u8);
+    data.extend_from_slice(&header.timezero.to_be_bytes());
+    data
+}
+
+pub(super) fn encode_geometry(signals: &[SignalGeometry]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    for signal in signals {
+        VarInt(signal.to_raw()).write_to(&mut raw);
+    }
+    let mut data = Vec::new();
+    data.extend_from_slice(&(raw.len() as u64).to_be_bytes());
+    data.extend_from_slice(&(signals.len() as u64).to_be_bytes());
+    data.extend_from_slice(&zlib_if_smaller(&raw));
+    data
+}
+
+pub(super) fn encode_hierarchy(compression: HierarchyCompression, raw: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(raw.len() as u64).to_be_bytes());
+    match compression {
+        HierarchyCompression::Gz => {
+            let mut encoder = GzEncoder::new(data, Compression::default());
+            encoder
+                .write_all(raw)
+                .expect("writing to a Vec never fails");
+            data = encoder.finish().expect("writing to a Vec never fails");
+        }
+        HierarchyCompression::Lz4 => {
+            data.extend_from_slice(&lz4_flex::block::compress(raw));
+        }
+        HierarchyCompression::Lz4Duo => {
+            let once = lz4_flex::block::compress(raw);
+            VarInt(once.len() as u64).write_to(&mut data);
+            data.extend_from_slice(&lz4_flex::block::compress(&once));
+        }
+    }
+    data
+}
+
+pub(super) fn encode_blackout(records: &[(bool, u64)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    VarInt(records.len() as u64).write_to(&mut data);
+    let mut previous_time = 0;
+    for (active, time) in records {
+        data.push(*active as u8);
+        VarInt(time - previous_time).write_to(&mut data);
+        previous_time = *time;
+    }
+    data
+}
+
+/// Append one value change of a signal to its wave data.
+///
+/// `time_delta` is the difference of the index in the time table from the previous change of the signal.
+pub(super) fn encode_value_change(
+    geometry: SignalGeometry,
+    time_delta: u64,
+    value: &[u8],
+    out: &mut Vec<u8>,
+) {
+    match geometry {
+        SignalGeometry::Bits(1) => match value[0] {
+            b @ (b'0' | b'1') => VarInt((time_delta << 2) | (((b & 1) as u64) << 1)).write_to(out),
+            b => {
+                let code = match b {
+                    b'x' | b'X' => 0,
+                    b'z' | b'Z' => 1,
+                    b'h' | b'H' => 2,
+                    b'u' | b'U' => 3,
+                    b'w' | b'W' => 4,
+                    b'l' | b'L' => 5,
+                    b'-' => 6,
+                    _ => 7,
+                };
+                VarInt((time_delta << 4) | (code << 1) | 1).write_to(out)
+            }
+        },
+        SignalGeometry::Bits(_) => {
+            if value.iter().all(|b| matches!(b, b'0' | b'1')) {
+                VarInt(time_delta << 1).write_to(out);
+                for chunk in value.chunks(8) {
+                    let mut byte = 0;
+                    for (i, b) in chunk.iter().enumerate() {
+                        byte |= (b & 1) << (7 - i);
+                    }
+                    out.push(byte);
+                }
+            } else {
+                VarInt((time_delta << 1) | 1).write_to(out);
+                out.extend_from_slice(value);
+            }
+        }
+        SignalGeometry::Real => {
+            VarInt((time_delta << 1) | 1).write_to(out);
+            out.extend_from_slice(value);
+        }
+        SignalGeometry::VariableLength => {
+            VarInt(time_delta << 1).write_to(out);
+            VarInt(value.len() as u64).write_to(out);
+            out.extend_from_slice(value);
+        }
+    }
+}
+
+/// Compress the wave data of one signal when it makes it smaller
+fn pack_wave(pack_type: WriterPackType, data: &[u8]) -> Option<Vec<u8>> {
+    let packed = match pack_type {
+        WriterPackType::Zlib => zlib(data),
+        WriterPackType::FaslLz => fastlz::compress(data),
+        WriterPackType::Lz4 => lz4_flex::block::compress(data),
+    };
+    (packed.len() < data.len()).then_some(packed)
+}
+
+/// Data needed to write one value change data block
+pub(super) struct ValueChangeBlock<'a> {
+    pub time_table: &'a [u64],
+    /// values of all signals at the start of the block
+    pub frame: &'a [u8],
+    /// encoded value changes of each signal
+    pub waves: Vec<&'a [u8]>,
+    pub pack_type: WriterPackType,
+}
+
+/// Encode a [BlockType::ValueChangeDataAlias2] block
+pub(super) fn encode_value_change_block(block: &ValueChangeBlock) -> Vec<u8> {
+    let start_time = block.time_table.first().copied().unwrap_or_default();
+    let end_time = block.time_table.last().copied().unwrap_or_default();
+    let memory_required: usize = block.waves.iter().map(|w| w.len()).sum();
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&start_time.to_be_bytes());
+    data.extend_from_slice(&end_time.to_be_bytes());
+    data.extend_from_slice(&(memory_required as u64).to_be_bytes());
+
+    let bits = zlib_if_smaller(block.frame);
+    VarInt(block.frame.len() as u64).write_to(&mut data);
+    VarInt(bits.len() as u64).write_to(&mut data);
+    VarInt(block.waves.len() as u64).write_to(&mut data);
+    data.extend_from_slice(&bits);
+
+    VarInt(block.waves.len() as u64).write_to(&mut data);
+    data.push(block.pack_type.as_byte());
+
+    // positions are 1 based offsets from the pack type byte
+    let waves_start = data.len() - 1;
+    let mut positions = Vec::with_capacity(block.waves.len());
+    for wave in &block.waves {
+        if wave.is_empty() {
+            positions.push(None);
+            continue;
+        }
+        positions.push(Some((data.len() - waves_start) as i64));
+        match pack_wave(block.pack_type, wave) {
+            Some(packed) => {
+                VarInt(wave.len() as u64).write_to(&mut data);
+                data.extend_from_slice(&packed);
+            }
+            None => {
+                VarInt(0).write_to(&mut data);
+                data.extend_from_slice(wave);
+            }
+        }
+    }
+
+    let position_start = data.len();
+    let mut previous_position = 0;
+    let mut zeros = 0u64;
+    for position in positions {
+        match position {
+            Some(position) => {
+                if zeros != 0 {
+                    VarInt(zeros << 1).write_to(&mut data);
+                    zeros = 0;
+                }
+                SVarInt(((position - previous_position) << 1) | 1).write_to(&mut data);
+                previous_position = position;
+            }
+            None => zeros += 1,
+        }
+    }
+    if zeros != 0 {
+        VarInt(zeros << 1).write_to(&mut data);
+    }
+    let position_length = data.len() - position_start;
+    data.extend_from_slice(&(position_length as u64).to_be_bytes());
+
+    let mut time_raw = Vec::new();
+    let mut previous_time = 0;
+    for time in block.time_table {
+        VarInt(time - previous_time).write_to(&mut time_raw);
+        previous_time = *time;
+    }
+    let time_packed = zlib_if_smaller(&time_raw);
+    data.extend_from_slice(&time_packed);
+    data.extend_from_slice(&(time_raw.len() as u64).to_be_bytes());
+    data.extend_from_slice(&(time_packed.len() as u64).to_be_bytes());
+    data.extend_from_slice(&(block.time_table.len() as u64).to_be_bytes());
+    data
+}
@@ -0,0 +1,704 @@
+use std::{
+    collections::HashMap,
+    io::{self, Seek, SeekFrom, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+use tracing::{debug, debug_span};
+
+use crate::{
+    block_parsers::{
+        geometry::{Geometry, SignalGeometry},
+        header::HeaderBlockContent,
+        hierarchy::{
+            Attribute, AttributeType, EnumTable, MiscType, ScopeType, SupplementalType, VarDir,
+            VarType,
+        },
+    },
+    data_types::{BlockType, FileType, Handle, TimeScale, VarInt, WriterPackType},
+};
+
+mod encode;
+
+use encode::{
+    encode_blackout, encode_geometry, encode_header, encode_hierarchy, encode_value_change,
+    encode_value_change_block, write_block, ValueChangeBlock,
+};
+
+/// Compression used for the hierarchy block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HierarchyCompression {
+    /// [BlockType::HierarchyGz]
+    #[default]
+    Gz,
+    /// [BlockType::HierarchyLz4]
+    Lz4,
+    /// [BlockType::HierarchyLz4Duo]
+    Lz4Duo,
+}
+
+impl HierarchyCompression {
+    /// The compression of a hierarchy block of type `block_type`
+    pub fn from_block_type(block_type: BlockType) -> Option<Self> {
+        match block_type {
+            BlockType::HierarchyGz => Some(HierarchyCompression::Gz),
+            BlockType::HierarchyLz4 => Some(HierarchyCompression::Lz4),
+            BlockType::HierarchyLz4Duo => Some(HierarchyCompression::Lz4Duo),
+            _ => None,
+        }
+    }
+
+    pub fn block_type(self) -> BlockType {
+        match self {
+            HierarchyCompression::Gz => BlockType::HierarchyGz,
+            HierarchyCompression::Lz4 => BlockType::HierarchyLz4,
+            HierarchyCompression::Lz4Duo => BlockType::HierarchyLz4Duo,
+        }
+    }
+}
+
+/// Settings for [FstWriter]
+#[derive(Debug, Clone)]
+pub struct WriterOptions {
+    /// Amount of encoded value changes in bytes to collect before a value change data block is written
+    pub block_size: usize,
+    /// Longest time a value change data block covers before the next one is started.
+    /// Blocks are only limited by [WriterOptions::block_size] when [None].
+    pub block_time_span: Option<u64>,
+    pub hierarchy_compression: HierarchyCompression,
+    /// Compression of the value changes of each signal
+    pub pack_type: WriterPackType,
+    pub timescale: TimeScale,
+    pub timezero: i64,
+    pub filetype: FileType,
+    /// Name of the program that wrote the file
+    pub writer: String,
+    /// Date in the format of `asctime`. The current time is used when [None].
+    pub date: Option<String>,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self {
+            block_size: 1 << 27,
+            block_time_span: None,
+            hierarchy_compression: HierarchyCompression::default(),
+            pack_type: WriterPackType::Lz4,
+            timescale: TimeScale(-9),
+            timezero: 0,
+            filetype: FileType::Verilog,
+            writer: "fst-file".to_string(),
+            date: None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WriterError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("time went backwards from {previous} to {time}")]
+    TimeWentBackwards { previous: u64, time: u64 },
+    #[error("unknown handle {0:?}")]
+    UnknownHandle(Handle),
+    #[error("value of {handle:?} has length {actual} but {expected} was expected")]
+    WrongValueLength {
+        handle: Handle,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("variables can not be created after the first time change")]
+    VariableAfterTimeChange,
+    #[error("there is no open scope to close")]
+    NoOpenScope,
+    #[error("there is no open attribute to close")]
+    NoOpenAttribute,
+}
+
+struct Signal {
+    geometry: SignalGeometry,
+    /// offset of the value in the frame
+    offset: usize,
+    /// encoded value changes in the current block
+    wave: Vec<u8>,
+    /// index in the time table of the last change
+    last_time_index: usize,
+}
+
+/// Encoded geometry and hierarchy blocks of another file, written as they are
+pub(crate) struct CopiedHierarchy {
+    pub(crate) geometry: Vec<u8>,
+    pub(crate) hierarchy_type: BlockType,
+    pub(crate) hierarchy: Vec<u8>,
+}
+
+/// Value change data block that is being collected
+struct OpenBlock {
+    frame: Vec<u8>,
+    time_table: Vec<u64>,
+}
+
+/// Writes FST files.
+///
+/// The hierarchy has to be declared before the first call to [FstWriter::emit_time_change].
+/// Value changes are collected in memory and written as a value change data block
+/// once [WriterOptions::block_size] is reached.
+/// The header, geometry, hierarchy and blackout are written by [FstWriter::finish].
+pub struct FstWriter<W: Write + Seek> {
+    output: W,
+    options: WriterOptions,
+    hierarchy: Vec<u8>,
+    /// written instead of the declared hierarchy
+    copied_hierarchy: Option<CopiedHierarchy>,
+    num_scopes: u64,
+    num_hierarchy_vars: u64,
+    open_scopes: usize,
+    open_attributes: usize,
+    num_enum_tables: u64,
+    /// ids of the source paths given so far
+    source_paths: HashMap<String, u64>,
+    signals: Vec<Signal>,
+    /// current values of all signals
+    values: Vec<u8>,
+    block: Option<OpenBlock>,
+    pending_size: usize,
+    start_time: Option<u64>,
+    current_time: Option<u64>,
+    num_vc_blocks: u64,
+    blackout: Vec<(bool, u64)>,
+}
+
+impl<W: Write + Seek> FstWriter<W> {
+    pub fn new(mut output: W, options: WriterOptions) -> Result<Self, WriterError> {
+        let start = output.stream_position()?;
+        if start != 0 {
+            debug!(start, "writing fst not from the start of the output");
+        }
+        let mut writer = Self {
+            output,
+            options,
+            hierarchy: Vec::new(),
+            copied_hierarchy: None,
+            num_scopes: 0,
+            num_hierarchy_vars: 0,
+            open_scopes: 0,
+            open_attributes: 0,
+            num_enum_tables: 0,
+            source_paths: HashMap::new(),
+            signals: Vec::new(),
+            values: Vec::new(),
+            block: None,
+            pending_size: 0,
+            start_time: None,
+            current_time: None,
+            num_vc_blocks: 0,
+            blackout: Vec::new(),
+        };
+        // placeholder, rewritten by finish
+        let header = writer.header();
+        write_block(
+            &mut writer.output,
+            BlockType::Header,
+            &encode_header(&header),
+        )?;
+        Ok(writer)
+    }
+
+    fn header(&self) -> HeaderBlockContent {
+        HeaderBlockContent {
+            start_time: self.start_time.unwrap_or_default(),
+            end_time: self.current_time.unwrap_or_default(),
+            real_endianness: std::f64::consts::E,
+            writer_memory_use: self.options.block_size as u64,
+            num_scopes: self.num_scopes,
+            num_hierarchy_vars: self.num_hierarchy_vars,
+            num_vars: self.signals.len(),
+            num_vc_blocks: self.num_vc_blocks,
+            timescale: self.options.timescale,
+            writer: self.options.writer.clone(),
+            date: self
+                .options
+                .date
+                .clone()
+                .unwrap_or_else(|| asctime(SystemTime::now())),
+            filetype: self.options.filetype,
+            timezero: self.options.timezero,
+        }
+    }
+
+    fn push_c_str(&mut self, s: &str) {
+        self.hierarchy.extend_from_slice(s.as_bytes());
+        self.hierarchy.push(0);
+    }
+
+    /// Open a new scope inside the current scope
+    pub fn set_scope(
+        &mut self,
+        scope_type: ScopeType,
+        name: &str,
+        component: &str,
+    ) -> Result<(), WriterError> {
+        self.hierarchy
+            .extend_from_slice(&[ScopeType::VcdScope.to_u8(), scope_type.to_u8()]);
+        self.push_c_str(name);
+        self.push_c_str(component);
+        self.num_scopes += 1;
+        self.open_scopes += 1;
+        Ok(())
+    }
+
+    /// Close the current scope
+    pub fn set_upscope(&mut self) -> Result<(), WriterError> {
+        if self.open_scopes == 0 {
+            return Err(WriterError::NoOpenScope);
+        }
+        self.hierarchy.push(ScopeType::VcdUnScope.to_u8());
+        self.open_scopes -= 1;
+        Ok(())
+    }
+
+    /// Start an attribute that applies to the following hierarchy entries.
+    /// Misc attributes only annotate the next scope or variable and are usually not ended.
+    pub fn set_attr_begin(
+        &mut self,
+        attr_type: AttributeType,
+        misc_type: MiscType,
+        name: &str,
+        value: u64,
+    ) -> Result<(), WriterError> {
+        self.push_attr(attr_type, misc_type, name, value);
+        self.open_attributes += 1;
+        Ok(())
+    }
+
+    fn push_attr(&mut self, attr_type: AttributeType, misc_type: MiscType, name: &str, value: u64) {
+        self.hierarchy.extend_from_slice(&[
+            ScopeType::GenAttrBegin.to_u8(),
+            attr_type.to_u8(),
+            misc_type as u8,
+        ]);
+        self.push_c_str(name);
+        VarInt(value).write_to(&mut self.hierarchy);
+    }
+
+    /// Start a copy of `attribute`, which can come from another file
+    pub fn copy_attr_begin(&mut self, attribute: &Attribute) -> Result<(), WriterError> {
+        match attribute.path_id() {
+            Some(path_id) => {
+                self.push_source_stem(attribute.misc_type(), path_id, attribute.value())
+            }
+            None => self.push_attr(
+                attribute.attr_type(),
+                attribute.misc_type(),
+                attribute.name(),
+                attribute.value(),
+            ),
+        }
+        self.open_attributes += 1;
+        Ok(())
+    }
+
+    /// Record the source file and line of the next scope or variable,
+    /// where it was instantiated when `instantiation` is set and where it was declared otherwise
+    pub fn set_source_stem(
+        &mut self,
+        path: &str,
+        line: u64,
+        instantiation: bool,
+    ) -> Result<(), WriterError> {
+        let path_id = match self.source_paths.get(path) {
+            Some(path_id) => *path_id,
+            None => {
+                let path_id = self.source_paths.len() as u64 + 1;
+                self.source_paths.insert(path.to_string(), path_id);
+                self.push_attr(AttributeType::Misc, MiscType::PathName, path, path_id);
+                path_id
+            }
+        };
+        let misc_type = if instantiation {
+            MiscType::SourceIStem
+        } else {
+            MiscType::SourceStem
+        };
+        self.push_source_stem(misc_type, path_id, line);
+        Ok(())
+    }
+
+    /// Source stems store the path id in place of the name
+    fn push_source_stem(&mut self, misc_type: MiscType, path_id: u64, line: u64) {
+        self.hierarchy.extend_from_slice(&[
+            ScopeType::GenAttrBegin.to_u8(),
+            AttributeType::Misc.to_u8(),
+            misc_type as u8,
+        ]);
+        VarInt(path_id).write_to(&mut self.hierarchy);
+        self.hierarchy.push(0);
+        VarInt(line).write_to(&mut self.hierarchy);
+    }
+
+    /// Give the type in the source language of the next variable
+    pub fn set_supplemental_type(
+        &mut self,
+        supplemental_type: &SupplementalType,
+    ) -> Result<(), WriterError> {
+        self.push_attr(
+            AttributeType::Misc,
+            MiscType::SupVar,
+            &supplemental_type.type_name,
+            supplemental_type.attribute_value(),
+        );
+        Ok(())
+    }
+
+    /// Define an enum table and return its id for [FstWriter::emit_enum_table_ref]
+    pub fn create_enum_table(&mut self, table: &EnumTable) -> Result<u64, WriterError> {
+        self.num_enum_tables += 1;
+        let id = self.num_enum_tables;
+        self.push_attr(
+            AttributeType::Misc,
+            MiscType::EnumTable,
+            &table.to_attribute_name(),
+            id,
+        );
+        Ok(id)
+    }
+
+    /// Name the values of the next variable with the enum table `id`
+    pub fn emit_enum_table_ref(&mut self, id: u64) -> Result<(), WriterError> {
+        self.push_attr(AttributeType::Misc, MiscType::EnumTable, "", id);
+        Ok(())
+    }
+
+    pub fn set_attr_end(&mut self) -> Result<(), WriterError> {
+        if self.open_attributes == 0 {
+            return Err(WriterError::NoOpenAttribute);
+        }
+        self.hierarchy.push(ScopeType::GenAttrEnd.to_u8());
+        self.open_attributes -= 1;
+        Ok(())
+    }
+
+    /// Declare a variable in the current scope.
+    ///
+    /// When `alias` is given the variable shares the value changes of that handle.
+    /// Real variables always have a length of 64 bits and strings are variable length.
+    pub fn create_var(
+        &mut self,
+        var_type: VarType,
+        direction: VarDir,
+        length: u32,
+        name: &str,
+        alias: Option<Handle>,
+    ) -> Result<Handle, WriterError> {
+        if self.block.is_some() {
+            return Err(WriterError::VariableAfterTimeChange);
+        }
+        if let Some(alias) = alias {
+            if alias
+                .index()
+                .is_none_or(|index| index >= self.signals.len())
+            {
+                return Err(WriterError::UnknownHandle(alias));
+            }
+        }
+        let (length, geometry) = match var_type {
+            VarType::VcdReal
+            | VarType::VcdRealParameter
+            | VarType::VcdRealtime
+            | VarType::SvShortReal => (8, SignalGeometry::Real),
+            VarType::GenString => (0, SignalGeometry::VariableLength),
+            _ if length == 0 => (0, SignalGeometry::VariableLength),
+            _ => (length, SignalGeometry::Bits(length)),
+        };
+
+        self.hierarchy
+            .extend_from_slice(&[var_type.to_u8(), direction.to_u8()]);
+        self.push_c_str(name);
+        VarInt(length as u64).write_to(&mut self.hierarchy);
+        VarInt(alias.map_or(0, |a| a.0 as u64)).write_to(&mut self.hierarchy);
+        self.num_hierarchy_vars += 1;
+
+        if let Some(alias) = alias {
+            return Ok(alias);
+        }
+        Ok(self.push_signal(geometry))
+    }
+
+    /// Take over the geometry and hierarchy blocks of another file instead of declaring
+    /// the hierarchy. Every signal of `geometry`, the decoded geometry block, keeps its handle.
+    pub(crate) fn copy_encoded_hierarchy(
+        &mut self,
+        header: &HeaderBlockContent,
+        geometry: &Geometry,
+        blocks: CopiedHierarchy,
+    ) -> Result<(), WriterError> {
+        if self.block.is_some() {
+            return Err(WriterError::VariableAfterTimeChange);
+        }
+        for signal in geometry.iter() {
+            self.push_signal(signal);
+        }
+        self.num_scopes = header.num_scopes;
+        self.num_hierarchy_vars = header.num_hierarchy_vars;
+        self.copied_hierarchy = Some(blocks);
+        Ok(())
+    }
+
+    fn push_signal(&mut self, geometry: SignalGeometry) -> Handle {
+        let offset = self.values.len();
+        match geometry {
+            SignalGeometry::Bits(n) => self.values.resize(offset + n as usize, b'x'),
+            SignalGeometry::Real => self.values.extend_from_slice(&f64::NAN.to_le_bytes()),
+            SignalGeometry::VariableLength => {}
+        }
+        self.signals.push(Signal {
+            geometry,
+            offset,
+            wave: Vec::new(),
+            last_time_index: 0,
+        });
+        Handle::from_index(self.signals.len() - 1)
+    }
+
+    /// Move the current time forward.
+    /// Value changes emitted before the first time change become the initial values.
+    pub fn emit_time_change(&mut self, time: u64) -> Result<(), WriterError> {
+        if let Some(previous) = self.current_time {
+            if time < previous {
+                return Err(WriterError::TimeWentBackwards { previous, time });
+            }
+            if time == previous && self.block.is_some() {
+                return Ok(());
+            }
+        }
+        let span_reached = match (&self.block, self.options.block_time_span) {
+            (Some(block), Some(span)) => time - block.time_table[0] >= span,
+            _ => false,
+        };
+        if span_reached || (self.pending_size > 0 && self.pending_size >= self.options.block_size) {
+            self.flush_block()?;
+        }
+        match &mut self.block {
+            Some(block) => block.time_table.push(time),
+            None => {
+                self.block = Some(OpenBlock {
+                    frame: self.values.clone(),
+                    time_table: vec![time],
+                })
+            }
+        }
+        self.start_time.get_or_insert(time);
+        self.current_time = Some(time);
+        Ok(())
+    }
+
+    /// Change the value of a signal at the current time.
+    ///
+    /// Bit vectors take one character (`0`, `1`, `x`, `z`, ...) per bit,
+    /// reals take the 8 bytes of a little endian [f64]
+    /// and variable length signals take any bytes.
+    pub fn emit_value_change(&mut self, handle: Handle, value: &[u8]) -> Result<(), WriterError> {
+        let signal = handle
+            .0
+            .checked_sub(1)
+            .and_then(|i| self.signals.get_mut(i as usize))
+            .ok_or(WriterError::UnknownHandle(handle))?;
+        let expected = match signal.geometry {
+            SignalGeometry::VariableLength => value.len(),
+            geometry => geometry.frame_size(),
+        };
+        if value.len() != expected {
+            return Err(WriterError::WrongValueLength {
+                handle,
+                expected,
+                actual: value.len(),
+            });
+        }
+        self.values[signal.offset..signal.offset + signal.geometry.frame_size()]
+            .copy_from_slice(&value[..signal.geometry.frame_size()]);
+
+        if let Some(block) = &self.block {
+            let time_index = block.time_table.len() - 1;
+            let before = signal.wave.len();
+            encode_value_change(
+                signal.geometry,
+                (time_index - signal.last_time_index) as u64,
+                value,
+                &mut signal.wave,
+            );
+            signal.last_time_index = time_index;
+            self.pending_size += signal.wave.len() - before;
+        }
+        Ok(())
+    }
+
+    /// Record whether dumping is active from the current time on.
+    /// Inactive ranges are written to the blackout block.
+    pub fn emit_dump_active(&mut self, active: bool) -> Result<(), WriterError> {
+        self.blackout
+            .push((active, self.current_time.unwrap_or_default()));
+        Ok(())
+    }
+
+    /// Record a dump activity change at an explicit time.
+    /// Changes have to be recorded in the order of time.
+    pub(crate) fn push_dump_change(&mut self, active: bool, time: u64) {
+        self.blackout.push((active, time));
+    }
+
+    /// Time of the last time change
+    pub fn current_time(&self) -> Option<u64> {
+        self.current_time
+    }
+
+    /// Write a value change data block that is already encoded,
+    /// e.g. one taken from another file with the same signals.
+    ///
+    /// The writer does not know the values of the signals after this block,
+    /// so the current value of every signal has to be emitted again before the next time change.
+    pub(crate) fn write_encoded_block(
+        &mut self,
+        block_type: BlockType,
+        data: &[u8],
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<(), WriterError> {
+        if let Some(previous) = self.current_time {
+            if start_time < previous {
+                return Err(WriterError::TimeWentBackwards {
+                    previous,
+                    time: start_time,
+                });
+            }
+        }
+        self.flush_block()?;
+        write_block(&mut self.output, block_type, data)?;
+        self.num_vc_blocks += 1;
+        self.start_time.get_or_insert(start_time);
+        self.current_time = Some(end_time);
+        Ok(())
+    }
+
+    /// Write the collected value changes as a value change data block
+    fn flush_block(&mut self) -> Result<(), WriterError> {
+        let Some(block) = self.block.take() else {
+            return Ok(());
+        };
+        let _span = debug_span!("flush block", index = self.num_vc_blocks).entered();
+        let data = encode_value_change_block(&ValueChangeBlock {
+            time_table: &block.time_table,
+            frame: &block.frame,
+            waves: self.signals.iter().map(|s| &s.wave[..]).collect(),
+            pack_type: self.options.pack_type,
+        });
+        write_block(&mut self.output, BlockType::ValueChangeDataAlias2, &data)?;
+        for signal in &mut self.signals {
+            signal.wave.clear();
+            signal.last_time_index = 0;
+        }
+        self.pending_size = 0;
+        self.num_vc_blocks += 1;
+        Ok(())
+    }
+
+    /// Write all remaining blocks and rewrite the header.
+    /// Returns the underlying output.
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        let _span = debug_span!("finish fst").entered();
+        self.flush_block()?;
+
+        if let Some(copied) = &self.copied_hierarchy {
+            write_block(&mut self.output, BlockType::Geometry, &copied.geometry)?;
+            write_block(&mut self.output, copied.hierarchy_type, &copied.hierarchy)?;
+        } else {
+            let geometry: Vec<_> = self.signals.iter().map(|s| s.geometry).collect();
+            write_block(
+                &mut self.output,
+                BlockType::Geometry,
+                &encode_geometry(&geometry),
+            )?;
+            write_block(
+                &mut self.output,
+                self.options.hierarchy_compression.block_type(),
+                &encode_hierarchy(self.options.hierarchy_compression, &self.hierarchy),
+            )?;
+        }
+        if !self.blackout.is_empty() {
+            write_block(
+                &mut self.output,
+                BlockType::Blackout,
+                &encode_blackout(&self.blackout),
+            )?;
+        }
+
+        let header = self.header();
+        let end = self.output.stream_position()?;
+        self.output.seek(SeekFrom::Start(0))?;
+        write_block(&mut self.output, BlockType::Header, &encode_header(&header))?;
+        self.output.seek(SeekFrom::Start(end))?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+/// Write `file`, a complete FST file, compressed inside of a [BlockType::GZippedWrapper] block.
+///
+/// Readers have to decompress the whole file before they can read any of it.
+pub fn write_gzip_wrapper<W: Write>(mut output: W, file: &[u8]) -> Result<W, WriterError> {
+    let _span = debug_span!("write gzip wrapper", size = file.len()).entered();
+    // the wrapper has the same layout as a gzip compressed hierarchy
+    let data = encode_hierarchy(HierarchyCompression::Gz, file);
+    write_block(&mut output, BlockType::GZippedWrapper, &data)?;
+    output.flush()?;
+    Ok(output)
+}
+
+/// Format a time like `asctime` does (e.g. `Tue May 30 17:18:08 2023\n`) in UTC
+fn asctime(time: SystemTime) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let days = secs / 86400;
+    let rem = secs % 86400;
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{} {} {:2} {:02}:{:02}:{:02} {}\n",
+        DAYS[(days % 7) as usize],
+        MONTHS[(month - 1) as usize],
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        year
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::asctime;
+
+    #[test]
+    fn asctime_format() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_685_467_088);
+        assert_eq!(asctime(time), "Tue May 30 17:18:08 2023\n");
+        let time = UNIX_EPOCH + Duration::from_secs(1_686_087_032);
+        assert_eq!(asctime(time), "Tue Jun  6 21:30:32 2023\n");
+    }
+}
@@ -0,0 +1,67 @@
+use fst_file::{
+    check::{check_file, check_file_with_options, CheckReport, CheckStatus},
+    options::ParseOptions,
+};
+
+fn status(report: &CheckReport, name: &str) -> CheckStatus {
+    report
+        .checks
+        .iter()
+        .find(|check| check.name == name)
+        .unwrap()
+        .status
+}
+
+#[test]
+fn samples_pass() {
+    for path in ["tests/sample.fst", "tests/sample2.fst"] {
+        let report = check_file(&std::fs::read(path).unwrap());
+        assert!(report.passed(), "{path}: {report:?}");
+        assert!(report.readable());
+        assert!(report
+            .checks
+            .iter()
+            .all(|check| check.status == CheckStatus::Pass));
+    }
+}
+
+#[test]
+fn wrong_header_counts() {
+    let mut content = std::fs::read("tests/sample2.fst").unwrap();
+    // last byte of num_vc_blocks in the header
+    content[72] = 7;
+    let report = check_file(&content);
+    assert!(!report.passed());
+    assert!(report.readable());
+    assert_eq!(status(&report, "num_vc_blocks"), CheckStatus::Fail);
+    assert_eq!(status(&report, "num_vars"), CheckStatus::Pass);
+}
+
+#[test]
+fn block_outside_of_header_time_range() {
+    let mut content = std::fs::read("tests/sample2.fst").unwrap();
+    // last byte of the end time in the header
+    content[24] = 0;
+    let report = check_file(&content);
+    assert_eq!(status(&report, "time_ranges"), CheckStatus::Fail);
+}
+
+#[test]
+fn strict_check() {
+    let mut content = std::fs::read("tests/sample2.fst").unwrap();
+    // first byte of the writer in the header
+    content[74] = 0xff;
+    let report = check_file_with_options(&content, &ParseOptions::lenient());
+    assert_eq!(status(&report, "header"), CheckStatus::Pass);
+    let report = check_file_with_options(&content, &ParseOptions::strict());
+    assert!(report.readable());
+    assert_eq!(status(&report, "header"), CheckStatus::Fail);
+}
+
+#[test]
+fn unreadable_file() {
+    let report = check_file(b"not a fst file");
+    assert!(!report.readable());
+    assert_eq!(report.checks.len(), 1);
+    assert_eq!(status(&report, "blocks"), CheckStatus::Fail);
+}
@@ -0,0 +1,57 @@
+use fst_file::{block_parsers::geometry::GeometryParseError, data_types::BlockType};
+
+/// sample2.fst with one byte changed
+fn corrupted_sample(offset: usize, byte: u8) -> Vec<u8> {
+    let mut content = std::fs::read("tests/sample2.fst").unwrap();
+    content[offset] = byte;
+    content
+}
+
+#[test]
+fn blocks_know_their_location() {
+    let content = std::fs::read("tests/sample2.fst").unwrap();
+    let blocks = fst_file::parse_raw_block_information(&content).unwrap();
+    for (index, info) in blocks.iter().enumerate() {
+        let location = info.get_block().location();
+        assert_eq!(location.index, index);
+        assert_eq!(location.offset, info.get_block_start_offset());
+        assert_eq!(location.data_offset(), info.get_data_start_offset());
+    }
+}
+
+#[test]
+fn unknown_block_type() {
+    // the type byte of the geometry block
+    let content = corrupted_sample(443, 250);
+    let error = fst_file::parse(&content).unwrap_err();
+    assert_eq!(error.file_offset(), Some(443));
+    let block = error.block().unwrap();
+    assert_eq!(
+        (block.index, block.offset, block.block_type),
+        (2, 443, None)
+    );
+    assert_eq!(error.contexts(), ["block type", "parse block"]);
+    let excerpt = error.excerpt();
+    assert_eq!(excerpt.bytes[443 - excerpt.start], 250);
+    assert!(error.to_string().ends_with(
+        "at file offset 443 in block #2 (unknown type) while parsing block type → parse block"
+    ));
+}
+
+#[test]
+fn error_inside_block() {
+    // the number of signals in the geometry block
+    let content = corrupted_sample(460, 0x7f);
+    let blocks = fst_file::parse(&content).unwrap();
+    let GeometryParseError::ParseError(error) = blocks.geometry.unwrap().get_content().unwrap_err()
+    else {
+        panic!("expected a parse error");
+    };
+    let block = error.block().unwrap();
+    assert_eq!(block.index, 2);
+    assert_eq!(block.block_type, Some(BlockType::Geometry));
+    assert_eq!(error.data_offset(), Some(452));
+    // the signal data after the two lengths
+    assert_eq!(error.offset(), Some(16));
+    assert_eq!(error.file_offset(), Some(468));
+}
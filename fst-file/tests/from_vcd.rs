@@ -0,0 +1,166 @@
+use std::io::Cursor;
+
+use fst_file::{
+    block_parsers::geometry::SignalGeometry,
+    convert::vcd_to_fst,
+    data_types::{Handle, WriterPackType},
+    writer::{HierarchyCompression, WriterOptions},
+};
+
+const VCD: &str = r#"$date
+	Tue May 30 17:18:08 2023
+$end
+$version Icarus $end
+$timescale 10ps $end
+$scope module top $end
+$var wire 1 ! clk $end
+$var reg 8 " data [7:0] $end
+$var real 64 # r $end
+$scope module sub $end
+$var wire 1 ! clk $end
+$var integer 32 $ count $end
+$var string 1 % s $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+bx "
+r0 #
+b0 $
+$end
+#5
+1!
+b101 "
+r1.5 #
+shello %
+#10
+0!
+$dumpoff
+#12
+$dumpon
+b1z "
+#15
+1!
+b11111111111111111111111111111111 $
+"#;
+
+fn convert(options: WriterOptions) -> Vec<u8> {
+    vcd_to_fst(VCD.as_bytes(), Cursor::new(Vec::new()), options)
+        .unwrap()
+        .into_inner()
+}
+
+#[test]
+fn from_vcd_header() {
+    let content = convert(WriterOptions::default());
+    let blocks = fst_file::parse(&content).unwrap();
+    let header = blocks.header.unwrap().get_content().unwrap();
+    assert_eq!(header.start_time, 0);
+    assert_eq!(header.end_time, 15);
+    assert_eq!(header.timescale.0, -11);
+    assert_eq!(header.num_scopes, 2);
+    assert_eq!(header.num_hierarchy_vars, 6);
+    assert_eq!(header.num_vars, 5);
+    assert_eq!(header.date, "Tue May 30 17:18:08 2023");
+}
+
+#[test]
+fn from_vcd_blocks() {
+    let content = convert(WriterOptions::default());
+    let blocks = fst_file::parse(&content).unwrap();
+
+    let geometry = blocks.geometry.unwrap().get_content().unwrap();
+    assert_eq!(geometry.len(), 5);
+    assert_eq!(geometry.get(Handle(1)), Some(SignalGeometry::Bits(1)));
+    assert_eq!(geometry.get(Handle(2)), Some(SignalGeometry::Bits(8)));
+    assert_eq!(geometry.get(Handle(3)), Some(SignalGeometry::Real));
+    assert_eq!(geometry.get(Handle(4)), Some(SignalGeometry::Bits(32)));
+    assert_eq!(
+        geometry.get(Handle(5)),
+        Some(SignalGeometry::VariableLength)
+    );
+
+    blocks.hierarchy.unwrap().get_content().unwrap();
+    blocks.blackout.unwrap().get_content().unwrap();
+    assert_eq!(blocks.value_change_data.len(), 1);
+}
+
+#[test]
+fn from_vcd_small_blocks() {
+    for hierarchy_compression in [
+        HierarchyCompression::Gz,
+        HierarchyCompression::Lz4,
+        HierarchyCompression::Lz4Duo,
+    ] {
+        for pack_type in [
+            WriterPackType::Zlib,
+            WriterPackType::FaslLz,
+            WriterPackType::Lz4,
+        ] {
+            let content = convert(WriterOptions {
+                block_size: 1,
+                hierarchy_compression,
+                pack_type,
+                ..Default::default()
+            });
+            let blocks = fst_file::parse(&content).unwrap();
+            let header = blocks.header.unwrap().get_content().unwrap();
+            assert_eq!(header.num_vc_blocks, 5);
+            assert_eq!(blocks.value_change_data.len(), 5);
+            blocks.hierarchy.unwrap().get_content().unwrap();
+        }
+    }
+}
+
+/// All value changes of a signal in the written file
+fn value_changes(content: &[u8], handle: Handle) -> Vec<(u64, Vec<u8>)> {
+    let blocks = fst_file::parse(content).unwrap();
+    let geometry = blocks.geometry.unwrap().get_content().unwrap();
+    let mut result = Vec::new();
+    for block in blocks.value_change_data.iter() {
+        let value_changes = block.get_value_changes(&geometry, |h| h == handle).unwrap();
+        result.extend(value_changes.get(handle).unwrap().changes.iter().cloned());
+    }
+    result
+}
+
+#[test]
+fn from_vcd_value_changes() {
+    for block_size in [1, WriterOptions::default().block_size] {
+        let content = convert(WriterOptions {
+            block_size,
+            ..Default::default()
+        });
+        assert_eq!(
+            value_changes(&content, Handle(1)),
+            [
+                (0, b"0".to_vec()),
+                (5, b"1".to_vec()),
+                (10, b"0".to_vec()),
+                (15, b"1".to_vec())
+            ]
+        );
+        assert_eq!(
+            value_changes(&content, Handle(2)),
+            [
+                (0, b"xxxxxxxx".to_vec()),
+                (5, b"00000101".to_vec()),
+                (12, b"0000001z".to_vec())
+            ]
+        );
+        assert_eq!(
+            value_changes(&content, Handle(3)),
+            [
+                (0, 0f64.to_le_bytes().to_vec()),
+                (5, 1.5f64.to_le_bytes().to_vec())
+            ]
+        );
+        assert_eq!(
+            value_changes(&content, Handle(4)),
+            [(0, vec![b'0'; 32]), (15, vec![b'1'; 32])]
+        );
+        assert_eq!(value_changes(&content, Handle(5)), [(5, b"hello".to_vec())]);
+    }
+}
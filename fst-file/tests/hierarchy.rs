@@ -0,0 +1,993 @@
+use std::io::Cursor;
+
+use fst_file::{
+    block_parsers::hierarchy::{
+        diff::{diff, HierarchyChange},
+        export::{self, TreeOptions},
+        index::{IndexedVariable, PathIndex, Regex},
+        source::{SourceLocation, SourceLocations},
+        Attribute, AttributeType, BitRange, EnumLiteral, EnumTable, HierarchyContent,
+        HierarchyHandler, HierarchyNode, HierarchyVisitor, Interner, MiscType, Name, Scope,
+        ScopeType, SupplementalDataType, SupplementalType, SupplementalVarType, VarDir, VarType,
+        Variable, VirtualBit,
+    },
+    convert::vcd_to_fst,
+    data_types::Handle,
+    writer::{FstWriter, WriterOptions},
+};
+
+const VCD: &str = r#"$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! clk $end
+$scope module dut $end
+$scope module u_core $end
+$var reg 32 " pc [31:0] $end
+$var wire 1 ! clk $end
+$upscope $end
+$var wire 1 # valid $end
+$upscope $end
+$var wire 1 $ valid $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+b0 "
+0#
+0$
+"#;
+
+fn hierarchy() -> HierarchyContent {
+    let content = vcd_to_fst(
+        VCD.as_bytes(),
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    content.hierarchy.unwrap().get_content().unwrap()
+}
+
+fn paths_and_handles(found: Vec<&IndexedVariable>) -> Vec<(String, u32)> {
+    found
+        .into_iter()
+        .map(|variable| (variable.path.clone(), variable.handle().0))
+        .collect()
+}
+
+#[test]
+fn exact_lookup() {
+    let hierarchy = hierarchy();
+    let index = PathIndex::new(&hierarchy);
+    assert_eq!(index.len(), 5);
+
+    // handles follow the order of declaration, also across scopes
+    let pc = index.get("top.dut.u_core.pc [31:0]");
+    assert_eq!(pc.len(), 1);
+    assert_eq!(pc[0].handle(), Handle(2));
+    assert_eq!(pc[0].variable.vcd().length(), 32);
+    assert_eq!(
+        paths_and_handles(index.get("top.dut.valid")),
+        [("top.dut.valid".to_string(), 3)]
+    );
+    assert_eq!(
+        paths_and_handles(index.get("top.valid")),
+        [("top.valid".to_string(), 4)]
+    );
+
+    // the bit range can be written without the space or left out
+    assert_eq!(index.get("top.dut.u_core.pc[31:0]")[0].handle(), Handle(2));
+    assert_eq!(index.get("top.dut.u_core.pc")[0].handle(), Handle(2));
+    assert!(index.get("top.dut.u_core.pc[7:0]").is_empty());
+    assert!(index.get("top.missing").is_empty());
+
+    // aliases share the handle
+    assert_eq!(index.get("top.dut.u_core.clk")[0].handle(), Handle(1));
+}
+
+#[test]
+fn glob_lookup() {
+    let hierarchy = hierarchy();
+    let index = PathIndex::new(&hierarchy);
+    assert_eq!(
+        paths_and_handles(index.glob("top.*")),
+        [("top.clk".to_string(), 1), ("top.valid".to_string(), 4)]
+    );
+    assert_eq!(
+        paths_and_handles(index.find("**.clk")),
+        [
+            ("top.clk".to_string(), 1),
+            ("top.dut.u_core.clk".to_string(), 1)
+        ]
+    );
+    assert_eq!(
+        paths_and_handles(index.find("top.*.u_core.pc[*]")),
+        [("top.dut.u_core.pc [31:0]".to_string(), 2)]
+    );
+}
+
+#[test]
+fn regex_lookup() {
+    let hierarchy = hierarchy();
+    let index = PathIndex::new(&hierarchy);
+    let regex = Regex::new(r"dut\..*valid$").unwrap();
+    assert_eq!(
+        paths_and_handles(index.regex(&regex)),
+        [("top.dut.valid".to_string(), 3)]
+    );
+}
+
+#[test]
+fn separator() {
+    let hierarchy = hierarchy();
+    let index = PathIndex::with_separator(&hierarchy, '/');
+    assert_eq!(index.get("top/dut/u_core/pc")[0].handle(), Handle(2));
+    assert_eq!(index.glob("top/*").len(), 2);
+    assert_eq!(index.glob("top/**").len(), 5);
+}
+
+#[test]
+fn accessors() {
+    let hierarchy = hierarchy();
+    assert_eq!(hierarchy.roots().len(), 1);
+    assert!(hierarchy.variables().is_empty());
+    let root = &hierarchy.roots()[0];
+    assert_eq!(root.name(), "top");
+    assert_eq!(root.scope_type(), ScopeType::VcdModule);
+    assert_eq!(root.variables().len(), 2);
+    assert_eq!(root.scopes().len(), 1);
+    let core = &root.scopes()[0].scopes()[0];
+    assert_eq!(core.name(), "u_core");
+    let pc = &core.variables()[0];
+    assert_eq!(pc.vcd().name(), "pc [31:0]");
+    assert_eq!(pc.vcd().var_type(), VarType::VcdReg);
+    assert_eq!(pc.vcd().length(), 32);
+    assert_eq!(pc.vcd().alias(), None);
+    assert_eq!(pc.vcd().parsed_name().base, "pc");
+    assert_eq!(pc.vcd().bit_range(), BitRange::new(31, 0));
+    assert_eq!(core.variables()[1].vcd().bit_range(), BitRange::new(0, 0));
+    assert_eq!(core.variables()[1].vcd().alias(), Some(Handle(1)));
+}
+
+#[test]
+fn depth_first_iterator() {
+    let hierarchy = hierarchy();
+    let nodes: Vec<_> = hierarchy
+        .iter()
+        .map(|(path, node)| (path, matches!(node, HierarchyNode::Scope(_))))
+        .collect();
+    assert_eq!(
+        nodes,
+        [
+            ("top".to_string(), true),
+            ("top.clk".to_string(), false),
+            ("top.valid".to_string(), false),
+            ("top.dut".to_string(), true),
+            ("top.dut.valid".to_string(), false),
+            ("top.dut.u_core".to_string(), true),
+            ("top.dut.u_core.pc [31:0]".to_string(), false),
+            ("top.dut.u_core.clk".to_string(), false),
+        ]
+    );
+    assert_eq!(
+        hierarchy.iter_with_separator('/').last().unwrap().0,
+        "top/dut/u_core/clk"
+    );
+}
+
+#[test]
+fn visitor() {
+    #[derive(Default)]
+    struct Outline {
+        depth: usize,
+        lines: Vec<String>,
+    }
+
+    impl<'a> HierarchyVisitor<'a> for Outline {
+        fn enter_scope(&mut self, scope: &'a Scope) {
+            self.lines
+                .push(format!("{}{}", "  ".repeat(self.depth), scope.name()));
+            self.depth += 1;
+        }
+
+        fn leave_scope(&mut self, _scope: &'a Scope) {
+            self.depth -= 1;
+        }
+
+        fn variable(&mut self, variable: &'a Variable) {
+            self.lines.push(format!(
+                "{}{} {:?}",
+                "  ".repeat(self.depth),
+                variable.vcd().name(),
+                variable.handle()
+            ));
+        }
+    }
+
+    let hierarchy = hierarchy();
+    let mut outline = Outline::default();
+    hierarchy.visit(&mut outline);
+    assert_eq!(outline.depth, 0);
+    assert_eq!(
+        outline.lines,
+        [
+            "top",
+            "  clk #1",
+            "  valid #4",
+            "  dut",
+            "    valid #3",
+            "    u_core",
+            "      pc [31:0] #2",
+            "      clk #1",
+        ]
+    );
+}
+
+#[test]
+fn parent_lookup() {
+    let hierarchy = hierarchy();
+    let index = PathIndex::new(&hierarchy);
+    let pc = index.get("top.dut.u_core.pc")[0].variable;
+    let ancestors: Vec<_> = hierarchy
+        .ancestors(pc)
+        .into_iter()
+        .map(Scope::name)
+        .collect();
+    assert_eq!(ancestors, ["top", "dut", "u_core"]);
+
+    let core = hierarchy.parent(pc).unwrap();
+    assert_eq!(core.name(), "u_core");
+    assert_eq!(hierarchy.parent_scope(core).unwrap().name(), "dut");
+    assert!(hierarchy.parent_scope(&hierarchy.roots()[0]).is_none());
+
+    // variables of another hierarchy have no parent here
+    let other = self::hierarchy();
+    assert!(hierarchy.parent(&other.roots()[0].variables()[0]).is_none());
+}
+
+#[test]
+fn several_top_level_scopes() {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    let reset = writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "reset", None)
+        .unwrap();
+    writer.set_scope(ScopeType::VcdModule, "tb", "").unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "clk", None)
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.set_scope(ScopeType::VcdModule, "dut", "").unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "reset", Some(reset))
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.emit_time_change(0).unwrap();
+    let content = writer.finish().unwrap().into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+
+    let roots: Vec<_> = hierarchy.roots().iter().map(Scope::name).collect();
+    assert_eq!(roots, ["tb", "dut"]);
+    assert_eq!(hierarchy.variables().len(), 1);
+    assert_eq!(hierarchy.variables()[0].handle(), Handle(1));
+
+    let paths: Vec<_> = hierarchy.iter().map(|(path, _)| path).collect();
+    assert_eq!(paths, ["reset", "tb", "tb.clk", "dut", "dut.reset"]);
+
+    let index = PathIndex::new(&hierarchy);
+    assert_eq!(
+        paths_and_handles(index.find("**reset")),
+        [("reset".to_string(), 1), ("dut.reset".to_string(), 1)]
+    );
+    assert_eq!(index.get("tb.clk")[0].handle(), Handle(2));
+
+    let dut = &hierarchy.roots()[1];
+    assert!(hierarchy.parent_scope(dut).is_none());
+    assert_eq!(hierarchy.parent(&dut.variables()[0]).unwrap().name(), "dut");
+    assert!(hierarchy.parent(&hierarchy.variables()[0]).is_none());
+}
+
+#[test]
+fn attributes_annotate_declarations() {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    writer
+        .set_attr_begin(AttributeType::Misc, MiscType::Comment, "generated", 0)
+        .unwrap();
+    writer
+        .set_attr_begin(AttributeType::Array, MiscType::Comment, "lanes", 4)
+        .unwrap();
+    writer.set_scope(ScopeType::VcdModule, "top", "").unwrap();
+    writer
+        .set_attr_begin(AttributeType::Pack, MiscType::Comment, "packed", 0)
+        .unwrap();
+    writer.set_source_stem("top.v", 12, false).unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "a", None)
+        .unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "b", None)
+        .unwrap();
+    writer.set_attr_end().unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "c", None)
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.set_attr_end().unwrap();
+    writer.set_scope(ScopeType::VcdModule, "other", "").unwrap();
+    writer.set_upscope().unwrap();
+    writer.emit_time_change(0).unwrap();
+    let content = writer.finish().unwrap().into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+
+    let names = |attributes: &[Attribute]| {
+        attributes
+            .iter()
+            .map(|attribute| attribute.name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(hierarchy.attributes()), ["generated", "lanes"]);
+    let top = &hierarchy.roots()[0];
+    assert_eq!(names(top.attributes()), ["lanes", "generated"]);
+    let variables: Vec<_> = top
+        .variables()
+        .iter()
+        .map(|variable| (variable.vcd().name(), names(variable.attributes())))
+        .collect();
+    assert_eq!(
+        variables,
+        [
+            (
+                "a",
+                vec!["lanes".to_string(), "packed".to_string(), String::new()]
+            ),
+            ("b", vec!["lanes".to_string(), "packed".to_string()]),
+            ("c", vec!["lanes".to_string()]),
+        ]
+    );
+    assert_eq!(
+        top.variables()[0].attributes()[2].misc_type(),
+        MiscType::SourceStem
+    );
+    assert_eq!(top.variables()[0].attributes()[2].value(), 12);
+    let path_id = top.variables()[0].attributes()[2].path_id().unwrap();
+    assert_eq!(hierarchy.source_path(path_id), Some("top.v"));
+    assert!(hierarchy.roots()[1].attributes().is_empty());
+    // open attributes annotate every declaration with the same interned name
+    assert!(std::ptr::eq(
+        top.variables()[0].attributes()[0].name(),
+        top.variables()[2].attributes()[0].name()
+    ));
+}
+
+fn enum_file() -> Vec<u8> {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    let literals = [("IDLE", "00"), ("BUSY", "01"), ("DONE", "10")]
+        .into_iter()
+        .map(|(name, value)| EnumLiteral {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .collect();
+    let table = writer
+        .create_enum_table(&EnumTable::new("state_t".to_string(), literals))
+        .unwrap();
+    writer.set_scope(ScopeType::VcdModule, "top", "").unwrap();
+    writer.emit_enum_table_ref(table).unwrap();
+    let state = writer
+        .create_var(VarType::SvEnum, VarDir::Implicit, 2, "state", None)
+        .unwrap();
+    let raw = writer
+        .create_var(VarType::VcdReg, VarDir::Implicit, 2, "raw", None)
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.emit_value_change(state, b"00").unwrap();
+    writer.emit_value_change(raw, b"00").unwrap();
+    writer.emit_time_change(0).unwrap();
+    writer.emit_time_change(5).unwrap();
+    writer.emit_value_change(state, b"01").unwrap();
+    writer.emit_value_change(raw, b"01").unwrap();
+    writer.emit_time_change(10).unwrap();
+    writer.emit_value_change(state, b"11").unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn enum_tables() {
+    let content = enum_file();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+    assert_eq!(hierarchy.enum_tables().len(), 1);
+
+    let top = &hierarchy.roots()[0];
+    let state = &top.variables()[0];
+    let raw = &top.variables()[1];
+    let table = hierarchy.enum_table(state).unwrap();
+    assert_eq!(table.name(), "state_t");
+    assert_eq!(table.literals()[2].name, "DONE");
+    assert!(hierarchy.enum_table(raw).is_none());
+
+    assert_eq!(hierarchy.display_value(state, b"01"), "BUSY");
+    assert_eq!(hierarchy.display_value(state, b"11"), "11");
+    assert_eq!(hierarchy.display_value(raw, b"01"), "01");
+    // the table definition is not an annotation of the variable
+    assert_eq!(state.attributes().len(), 1);
+    assert!(raw.attributes().is_empty());
+}
+
+fn source_file() -> Vec<u8> {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    writer.set_source_stem("rtl/top.v", 1, false).unwrap();
+    writer.set_scope(ScopeType::VcdModule, "top", "").unwrap();
+    writer.set_source_stem("rtl/dut.v", 3, false).unwrap();
+    writer.set_source_stem("rtl/top.v", 20, true).unwrap();
+    writer
+        .set_scope(ScopeType::VcdModule, "dut", "dut")
+        .unwrap();
+    writer.set_source_stem("rtl/dut.v", 7, false).unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 8, "sig [7:0]", None)
+        .unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "other", None)
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.set_upscope().unwrap();
+    writer.emit_time_change(0).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn source_locations() {
+    let content = source_file();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+    let locations = SourceLocations::new(&hierarchy);
+    assert_eq!(locations.len(), 3);
+
+    let location = |file: &str, line, instantiation| SourceLocation {
+        file: file.to_string(),
+        line,
+        instantiation,
+    };
+    assert_eq!(locations.get("top"), [location("rtl/top.v", 1, false)]);
+    assert_eq!(
+        locations.get("top.dut"),
+        [
+            location("rtl/dut.v", 3, false),
+            location("rtl/top.v", 20, true)
+        ]
+    );
+    assert_eq!(
+        locations.get("top.dut.sig[7:0]"),
+        [location("rtl/dut.v", 7, false)]
+    );
+    assert!(locations.get("top.dut.other").is_empty());
+
+    // the file names are given once
+    assert_eq!(hierarchy.source_path(1), Some("rtl/top.v"));
+    assert_eq!(hierarchy.source_path(2), Some("rtl/dut.v"));
+    assert_eq!(hierarchy.source_path(3), None);
+}
+
+#[test]
+fn supplemental_types() {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    let types = [
+        ("boolean", SupplementalDataType::VhdlBoolean, 1, "ready"),
+        ("integer", SupplementalDataType::VhdlInteger, 8, "count"),
+        (
+            "character",
+            SupplementalDataType::VhdlCharacter,
+            8,
+            "letter",
+        ),
+        (
+            "word_t",
+            SupplementalDataType::VhdlStdLogicVector,
+            4,
+            "word",
+        ),
+    ];
+    writer
+        .set_scope(ScopeType::VhdlArchitecture, "top", "")
+        .unwrap();
+    for (type_name, data_type, length, name) in types {
+        writer
+            .set_supplemental_type(&SupplementalType {
+                type_name: type_name.into(),
+                var_type: SupplementalVarType::VhdlSignal,
+                data_type,
+            })
+            .unwrap();
+        writer
+            .create_var(VarType::VcdReg, VarDir::Implicit, length, name, None)
+            .unwrap();
+    }
+    writer
+        .create_var(VarType::VcdReg, VarDir::Implicit, 1, "plain", None)
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.emit_time_change(0).unwrap();
+    let content = writer.finish().unwrap().into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+
+    let variables = hierarchy.roots()[0].variables();
+    let word = variables[3].supplemental_type().unwrap();
+    assert_eq!(word.type_name.as_str(), "word_t");
+    // the type name is the interned name of its attribute
+    assert!(std::ptr::eq(
+        word.type_name.as_str(),
+        variables[3].attributes()[0].name()
+    ));
+    assert_eq!(word.var_type, SupplementalVarType::VhdlSignal);
+    assert_eq!(word.data_type, SupplementalDataType::VhdlStdLogicVector);
+    assert!(variables[4].supplemental_type().is_none());
+
+    let values = [
+        &b"1"[..],
+        &b"11111101"[..],
+        &b"01111010"[..],
+        &b"01xz"[..],
+        &b"1"[..],
+    ];
+    let shown: Vec<_> = variables
+        .iter()
+        .zip(values)
+        .map(|(variable, value)| hierarchy.display_value(variable, value))
+        .collect();
+    assert_eq!(shown, ["true", "-3", "'z'", "01xz", "1"]);
+}
+
+const NETLIST_VCD: &str = r#"$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! a[0] $end
+$var wire 1 " a[1] $end
+$var wire 1 # a[2] $end
+$var wire 1 $ a[3] $end
+$var wire 1 % c[5] $end
+$var wire 1 & d[0] $end
+$var wire 1 ' d[2] $end
+$var wire 4 ( b [3:0] $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+0"
+0#
+0$
+0%
+0&
+0'
+b0000 (
+#5
+1!
+1$
+b0100 (
+#7
+0$
+#9
+0$
+b0110 (
+"#;
+
+#[test]
+fn virtual_buses_and_bits() {
+    let content = vcd_to_fst(
+        NETLIST_VCD.as_bytes(),
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.as_ref().unwrap().get_content().unwrap();
+    let geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    let value_changes = content.value_change_data[0]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap();
+    let top = &hierarchy.roots()[0];
+
+    // c has only one bit and d has a gap
+    let buses = top.virtual_buses();
+    assert_eq!(buses.len(), 1);
+    let bus = &buses[0];
+    assert_eq!(bus.name, "a[3:0]");
+    assert_eq!(bus.width(), 4);
+    let members: Vec<_> = bus.members.iter().map(|m| m.vcd().name()).collect();
+    assert_eq!(members, ["a[3]", "a[2]", "a[1]", "a[0]"]);
+    let changes = bus.value_changes(&value_changes).unwrap();
+    // the values at time 0 are changes after the initial frame
+    assert_eq!(changes.initial, b"xxxx");
+    assert_eq!(
+        changes.changes,
+        [
+            (0, b"0000".to_vec()),
+            (5, b"1001".to_vec()),
+            (7, b"0001".to_vec())
+        ]
+    );
+
+    let b = &top.variables()[7];
+    assert!(VirtualBit::new(b, 4).is_none());
+    let bit = VirtualBit::new(b, 2).unwrap();
+    assert_eq!(bit.name, "b[2]");
+    let changes = bit.value_changes(&value_changes).unwrap();
+    assert_eq!(changes.initial, b"x");
+    assert_eq!(changes.changes, [(0, b"0".to_vec()), (5, b"1".to_vec())]);
+    let bit = VirtualBit::new(b, 1).unwrap();
+    let changes = bit.value_changes(&value_changes).unwrap();
+    assert_eq!(changes.changes, [(0, b"0".to_vec()), (9, b"1".to_vec())]);
+}
+
+#[test]
+fn deep_hierarchy_streaming() {
+    const DEPTH: usize = 5000;
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    for level in 0..DEPTH {
+        writer
+            .set_scope(ScopeType::VcdGenerate, &format!("g{level}"), "")
+            .unwrap();
+        writer
+            .create_var(VarType::VcdWire, VarDir::Implicit, 1, "v", None)
+            .unwrap();
+    }
+    for _ in 0..DEPTH {
+        writer.set_upscope().unwrap();
+    }
+    writer.emit_time_change(0).unwrap();
+    let content = writer.finish().unwrap().into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let block = content.hierarchy.unwrap();
+
+    #[derive(Default)]
+    struct Counter {
+        depth: usize,
+        max_depth: usize,
+        variables: usize,
+        last_handle: Option<Handle>,
+    }
+    impl HierarchyHandler for Counter {
+        fn scope_begin(&mut self, _scope: Scope) {
+            self.depth += 1;
+            self.max_depth = self.max_depth.max(self.depth);
+        }
+        fn scope_end(&mut self) {
+            self.depth -= 1;
+        }
+        fn variable(&mut self, variable: Variable) {
+            self.variables += 1;
+            self.last_handle = Some(variable.handle());
+        }
+    }
+    let mut counter = Counter::default();
+    block.stream(&mut counter).unwrap();
+    assert_eq!(counter.depth, 0);
+    assert_eq!(counter.max_depth, DEPTH);
+    assert_eq!(counter.variables, DEPTH);
+    assert_eq!(counter.last_handle, Some(Handle(DEPTH as u32)));
+
+    let hierarchy = block.get_content().unwrap();
+    assert_eq!(hierarchy.roots().len(), 1);
+    assert_eq!(hierarchy.iter().count(), 2 * DEPTH);
+    let mut scope = &hierarchy.roots()[0];
+    while let Some(child) = scope.scopes().first() {
+        scope = child;
+    }
+    assert_eq!(scope.name(), format!("g{}", DEPTH - 1));
+    assert_eq!(block.get_tokens().unwrap().len(), 3 * DEPTH);
+    drop(hierarchy);
+
+    // deep enough to overflow the stack with one frame per level,
+    // with a single variable because every path holds the names of all levels
+    const DEEPER: usize = 200_000;
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    for _ in 0..DEEPER {
+        writer.set_scope(ScopeType::VcdGenerate, "g", "").unwrap();
+    }
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "v", None)
+        .unwrap();
+    for _ in 0..DEEPER {
+        writer.set_upscope().unwrap();
+    }
+    writer.emit_time_change(0).unwrap();
+    let content = writer.finish().unwrap().into_inner();
+    assert!(fst_file::check::check_file(&content).passed());
+    let blocks = fst_file::parse(&content).unwrap();
+    let hierarchy = blocks.hierarchy.unwrap().get_content().unwrap();
+
+    #[derive(Default)]
+    struct Visits {
+        scopes: usize,
+        left: usize,
+        variables: usize,
+    }
+    impl<'a> HierarchyVisitor<'a> for Visits {
+        fn enter_scope(&mut self, _scope: &'a Scope) {
+            self.scopes += 1;
+        }
+        fn leave_scope(&mut self, _scope: &'a Scope) {
+            self.left += 1;
+        }
+        fn variable(&mut self, _variable: &'a Variable) {
+            self.variables += 1;
+        }
+    }
+    let mut visits = Visits::default();
+    hierarchy.visit(&mut visits);
+    assert_eq!(
+        (visits.scopes, visits.left, visits.variables),
+        (DEEPER, DEEPER, 1)
+    );
+
+    let index = PathIndex::new(&hierarchy);
+    assert_eq!(index.len(), 1);
+    let variable = index.find("**.v")[0].variable;
+    assert_eq!(hierarchy.ancestors(variable).len(), DEEPER);
+    let parent = hierarchy.parent(variable).unwrap();
+    assert!(hierarchy.parent_scope(parent).is_some());
+
+    let options = TreeOptions {
+        max_depth: Some(2),
+        ..Default::default()
+    };
+    let mut tree = Vec::new();
+    export::write_tree(&hierarchy, &options, &mut tree).unwrap();
+    assert_eq!(String::from_utf8(tree).unwrap().lines().count(), 2);
+    drop(hierarchy);
+}
+
+#[test]
+fn names_are_interned() {
+    let hierarchy = hierarchy();
+    let top = &hierarchy.roots()[0];
+    let core = &top.scopes()[0].scopes()[0];
+    let clk = top.variables()[0].vcd().name();
+    let core_clk = core.variables()[1].vcd().name();
+    assert_eq!(clk, core_clk);
+    assert!(std::ptr::eq(clk, core_clk));
+    assert!(std::ptr::eq(top.component(), core.component()));
+
+    let mut interner = Interner::default();
+    let a = interner.intern("genblk1");
+    let b = interner.intern(&String::from("genblk1"));
+    assert!(Name::ptr_eq(&a, &b));
+    assert!(!Name::ptr_eq(&a, &Name::from("genblk1")));
+    assert_eq!(interner.len(), 1);
+    assert_eq!(serde_json::to_string(&a).unwrap(), "\"genblk1\"");
+}
+
+#[test]
+fn unknown_codes_are_kept() {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    writer
+        .set_attr_begin(AttributeType::Unknown(9), MiscType::Comment, "future", 1)
+        .unwrap();
+    writer.set_scope(ScopeType::Unknown(40), "top", "").unwrap();
+    writer
+        .create_var(VarType::Unknown(60), VarDir::Unknown(12), 8, "new", None)
+        .unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Input, 1, "clk", None)
+        .unwrap();
+    writer.set_upscope().unwrap();
+    writer.set_attr_end().unwrap();
+    writer.emit_time_change(0).unwrap();
+    let content = writer.finish().unwrap().into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+
+    assert_eq!(
+        hierarchy.attributes()[0].attr_type(),
+        AttributeType::Unknown(9)
+    );
+    let top = &hierarchy.roots()[0];
+    assert_eq!(top.scope_type(), ScopeType::Unknown(40));
+    assert_eq!(top.attributes()[0].name(), "future");
+    let new = top.variables()[0].vcd();
+    assert_eq!(new.var_type(), VarType::Unknown(60));
+    assert_eq!(new.direction(), VarDir::Unknown(12));
+    assert_eq!(new.length(), 8);
+    let clk = &top.variables()[1];
+    assert_eq!(clk.vcd().name(), "clk");
+    assert_eq!(clk.handle(), Handle(2));
+
+    assert_eq!(VarType::from_u8(16), VarType::VcdWire);
+    assert_eq!(VarType::Unknown(60).to_u8(), 60);
+    assert_eq!(ScopeType::from_u8(254), ScopeType::VcdScope);
+}
+
+#[test]
+fn hierarchy_diff() {
+    fn build(refactored: bool) -> HierarchyContent {
+        let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+        writer
+            .set_scope(
+                ScopeType::VcdModule,
+                "top",
+                if refactored { "top_v2" } else { "top" },
+            )
+            .unwrap();
+        writer
+            .create_var(VarType::VcdWire, VarDir::Input, 1, "clk", None)
+            .unwrap();
+        let (name, width) = if refactored {
+            ("data [15:0]", 16)
+        } else {
+            ("data [7:0]", 8)
+        };
+        writer
+            .create_var(VarType::VcdWire, VarDir::Output, width, name, None)
+            .unwrap();
+        let direction = if refactored {
+            VarDir::Inout
+        } else {
+            VarDir::Input
+        };
+        writer
+            .create_var(VarType::VcdWire, direction, 1, "en", None)
+            .unwrap();
+        writer
+            .set_scope(
+                ScopeType::VcdModule,
+                if refactored { "u_new" } else { "u_old" },
+                "m",
+            )
+            .unwrap();
+        writer
+            .create_var(VarType::VcdReg, VarDir::Implicit, 1, "q", None)
+            .unwrap();
+        writer.set_upscope().unwrap();
+        writer.set_upscope().unwrap();
+        writer.emit_time_change(0).unwrap();
+        let content = writer.finish().unwrap().into_inner();
+        let content = fst_file::parse(&content).unwrap();
+        content.hierarchy.unwrap().get_content().unwrap()
+    }
+    let old = build(false);
+    let new = build(true);
+
+    assert!(diff(&old, &old).is_empty());
+    let changes = diff(&old, &new);
+    assert_eq!(
+        changes,
+        [
+            HierarchyChange::Component {
+                path: "top".to_string(),
+                old: "top".to_string(),
+                new: "top_v2".to_string(),
+            },
+            HierarchyChange::Width {
+                path: "top.data [15:0]".to_string(),
+                old: 8,
+                new: 16,
+            },
+            HierarchyChange::Direction {
+                path: "top.en".to_string(),
+                old: VarDir::Input,
+                new: VarDir::Inout,
+            },
+            HierarchyChange::AddedScope {
+                path: "top.u_new".to_string(),
+            },
+            HierarchyChange::AddedVariable {
+                path: "top.u_new.q".to_string(),
+            },
+            HierarchyChange::RemovedScope {
+                path: "top.u_old".to_string(),
+            },
+            HierarchyChange::RemovedVariable {
+                path: "top.u_old.q".to_string(),
+            },
+        ]
+    );
+    assert_eq!(changes[1].to_string(), "~ top.data [15:0] width 8 -> 16");
+    assert_eq!(
+        serde_json::to_value(&changes[3]).unwrap(),
+        serde_json::json!({"change": "added_scope", "path": "top.u_new"})
+    );
+}
+
+#[test]
+fn hierarchy_diff_duplicate_paths() {
+    fn build(widths: &[u32]) -> HierarchyContent {
+        let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+        writer.set_scope(ScopeType::VcdModule, "top", "").unwrap();
+        for &width in widths {
+            writer
+                .set_scope(ScopeType::VcdGenerate, "genblk", "")
+                .unwrap();
+            writer
+                .create_var(VarType::VcdReg, VarDir::Implicit, width, "q", None)
+                .unwrap();
+            writer.set_upscope().unwrap();
+        }
+        writer.set_upscope().unwrap();
+        writer.emit_time_change(0).unwrap();
+        let content = writer.finish().unwrap().into_inner();
+        let content = fst_file::parse(&content).unwrap();
+        content.hierarchy.unwrap().get_content().unwrap()
+    }
+    let old = build(&[1, 1]);
+    let new = build(&[1, 2, 4]);
+
+    assert!(diff(&old, &old).is_empty());
+    assert_eq!(
+        diff(&old, &new),
+        [
+            HierarchyChange::AddedScope {
+                path: "top.genblk".to_string(),
+            },
+            HierarchyChange::Width {
+                path: "top.genblk.q".to_string(),
+                old: 1,
+                new: 2,
+            },
+            HierarchyChange::AddedVariable {
+                path: "top.genblk.q".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        diff(&new, &old),
+        [
+            HierarchyChange::RemovedScope {
+                path: "top.genblk".to_string(),
+            },
+            HierarchyChange::Width {
+                path: "top.genblk.q".to_string(),
+                old: 2,
+                new: 1,
+            },
+            HierarchyChange::RemovedVariable {
+                path: "top.genblk.q".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn export_formats() {
+    let hierarchy = hierarchy();
+
+    let mut csv = Vec::new();
+    export::write_csv(&hierarchy, &mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "path,type,direction,width,handle,alias_of
+top.clk,VcdWire,Implicit,1,1,
+top.valid,VcdWire,Implicit,1,4,
+top.dut.valid,VcdWire,Implicit,1,3,
+top.dut.u_core.pc [31:0],VcdReg,Implicit,32,2,
+top.dut.u_core.clk,VcdWire,Implicit,1,1,top.clk
+"
+    );
+
+    let mut dot = Vec::new();
+    export::write_dot(&hierarchy, &mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph hierarchy {"));
+    assert!(dot.contains("s2 [label=\"u_core\"];\n    s1 -> s2;"));
+
+    let tree = |max_depth, filter: Option<&str>| {
+        let options = TreeOptions {
+            max_depth,
+            filter: filter.map(String::from),
+            ..Default::default()
+        };
+        let mut tree = Vec::new();
+        export::write_tree(&hierarchy, &options, &mut tree).unwrap();
+        String::from_utf8(tree).unwrap()
+    };
+    assert_eq!(
+        tree(Some(2), None),
+        "top\n  clk VcdWire Implicit 1\n  valid VcdWire Implicit 1\n  dut\n"
+    );
+    assert_eq!(
+        tree(None, Some("**.u_core")),
+        "top\n  dut\n    u_core\n      pc [31:0] VcdReg Implicit 32\n      clk VcdWire Implicit 1\n"
+    );
+    assert_eq!(tree(None, Some("nothing")), "");
+}
@@ -0,0 +1,232 @@
+use std::io::{Cursor, Write};
+
+use fst_file::{
+    block_parsers::{
+        geometry::GeometryParseError,
+        hierarchy::{HierarchyBlockConvertError, VarDir, VarType},
+        value_change_data::ValueChangeDataError,
+        DecompressError,
+    },
+    data_types::BlockType,
+    options::{LimitError, Limits, ParseOptions},
+    writer::{FstWriter, HierarchyCompression, WriterOptions},
+};
+
+fn limited(limits: Limits) -> ParseOptions {
+    let mut options = ParseOptions::default();
+    options.limits = limits;
+    options
+}
+
+fn hierarchy_limit_error(content: &[u8], options: &ParseOptions) -> LimitError {
+    let error = fst_file::parse_with_options(content, options)
+        .unwrap()
+        .hierarchy
+        .unwrap()
+        .get_content()
+        .unwrap_err();
+    match error {
+        HierarchyBlockConvertError::DataDecompressError(DecompressError::LimitExceeded(e)) => e,
+        e => panic!("unexpected error {e}"),
+    }
+}
+
+#[test]
+fn samples_are_within_the_default_limits() {
+    for path in ["tests/sample.fst", "tests/sample2.fst"] {
+        for options in [ParseOptions::default(), limited(Limits::unlimited())] {
+            let content =
+                fst_file::parse_with_options(&std::fs::read(path).unwrap(), &options).unwrap();
+            let geometry = content.geometry.unwrap().get_content().unwrap();
+            content.hierarchy.unwrap().get_content().unwrap();
+            content.value_change_data[0]
+                .get_value_changes(&geometry, |_| true)
+                .unwrap();
+        }
+    }
+}
+
+#[test]
+fn decompressed_size_limit() {
+    let content = std::fs::read("tests/sample2.fst").unwrap();
+    let options = limited(Limits {
+        max_decompressed_size: 16,
+        ..Limits::default()
+    });
+    assert!(matches!(
+        hierarchy_limit_error(&content, &options),
+        LimitError::DecompressedSize { limit: 16, .. }
+    ));
+}
+
+#[test]
+fn element_limit() {
+    let content = std::fs::read("tests/sample2.fst").unwrap();
+    let geometry = fst_file::parse(&content)
+        .unwrap()
+        .geometry
+        .unwrap()
+        .get_content()
+        .unwrap();
+    let content = fst_file::parse_with_options(
+        &content,
+        &limited(Limits {
+            max_elements: 1,
+            ..Limits::default()
+        }),
+    )
+    .unwrap();
+
+    let error = content.geometry.unwrap().get_content().unwrap_err();
+    let GeometryParseError::LimitExceeded(LimitError::Elements { what, count, limit }) = error
+    else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!((what, count, limit), ("signals", geometry.len(), 1));
+
+    let error = content.value_change_data[0]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ValueChangeDataError::LimitExceeded(LimitError::Elements { .. })
+    ));
+}
+
+#[test]
+fn total_allocation_limit() {
+    let content = std::fs::read("tests/sample2.fst").unwrap();
+    let geometry = fst_file::parse(&content)
+        .unwrap()
+        .geometry
+        .unwrap()
+        .get_content()
+        .unwrap();
+    let content = fst_file::parse_with_options(
+        &content,
+        &limited(Limits {
+            max_total_allocation: 32,
+            ..Limits::default()
+        }),
+    )
+    .unwrap();
+    let error = content.value_change_data[0]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ValueChangeDataError::LimitExceeded(LimitError::TotalAllocation { limit: 32 })
+    ));
+}
+
+#[test]
+fn declared_lz4_size_over_the_limit() {
+    let options = WriterOptions {
+        hierarchy_compression: HierarchyCompression::Lz4,
+        ..WriterOptions::default()
+    };
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), options).unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "a", None)
+        .unwrap();
+    writer.emit_time_change(0).unwrap();
+    let mut content = writer.finish().unwrap().into_inner();
+    let hierarchy = fst_file::parse_raw_block_information(&content)
+        .unwrap()
+        .into_iter()
+        .find(|block| block.get_block().block_type == BlockType::HierarchyLz4)
+        .unwrap();
+    // the uncompressed size in front of the compressed hierarchy
+    let start = hierarchy.get_data_start_offset();
+    content[start..start + 8].copy_from_slice(&(1u64 << 40).to_be_bytes());
+
+    assert_eq!(
+        hierarchy_limit_error(&content, &ParseOptions::default()),
+        LimitError::DecompressedSize {
+            what: "block data",
+            limit: Limits::default().max_decompressed_size,
+        }
+    );
+}
+
+#[test]
+fn gzip_bomb() {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&vec![0; 16 << 20]).unwrap();
+    let compressed = encoder.finish().unwrap();
+    // a file with only a hierarchy block that claims a small size
+    let mut content = vec![BlockType::HierarchyGz as u8];
+    content.extend_from_slice(&(compressed.len() as u64 + 16).to_be_bytes());
+    content.extend_from_slice(&64u64.to_be_bytes());
+    content.extend_from_slice(&compressed);
+
+    let options = limited(Limits {
+        max_decompressed_size: 1 << 20,
+        ..Limits::default()
+    });
+    assert!(matches!(
+        hierarchy_limit_error(&content, &options),
+        LimitError::DecompressedSize {
+            what: "block data",
+            ..
+        }
+    ));
+}
+
+/// A block with `count` identical changes of a 64 bit signal, its wave data compresses well
+fn compressible_wave(count: usize) -> Vec<u8> {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    let handle = writer
+        .create_var(VarType::VcdReg, VarDir::Implicit, 64, "r", None)
+        .unwrap();
+    writer.emit_time_change(0).unwrap();
+    for _ in 0..count {
+        writer.emit_value_change(handle, &[b'1'; 64]).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn decoded_value_changes_are_limited() {
+    let content = compressible_wave(10_000);
+    let parsed = fst_file::parse(&content).unwrap();
+    let geometry = parsed.geometry.unwrap().get_content().unwrap();
+    let header = parsed.header.unwrap().get_content().unwrap();
+
+    let limited_elements = fst_file::parse_with_options(
+        &content,
+        &limited(Limits {
+            max_elements: 1000,
+            ..Limits::default()
+        }),
+    )
+    .unwrap();
+    let error = limited_elements.value_change_data[0]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap_err();
+    let ValueChangeDataError::LimitExceeded(LimitError::Elements { what, count, limit }) = error
+    else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!((what, count, limit), ("value changes", 1001, 1000));
+
+    // the block itself is small, the decoded changes are not
+    let limited_allocation = fst_file::parse_with_options(
+        &content,
+        &limited(Limits {
+            max_total_allocation: 256 << 10,
+            ..Limits::default()
+        }),
+    )
+    .unwrap();
+    limited_allocation.value_change_data[0]
+        .get_intermediate_content(&header)
+        .unwrap();
+    let error = limited_allocation.value_change_data[0]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ValueChangeDataError::LimitExceeded(LimitError::TotalAllocation { limit: 262144 })
+    ));
+}
@@ -0,0 +1,220 @@
+use std::{
+    fs,
+    io::{Cursor, Write},
+};
+
+use fst_file::{
+    block_parsers::hierarchy::{
+        AttributeType, HierarchyContent, HierarchyVisitor, MiscType, Scope, ScopeType, VarDir,
+        VarType, Variable, VirtualBit,
+    },
+    data_types::{BlockType, VarInt},
+    error::FstResult,
+    writer::{FstWriter, WriterOptions},
+};
+
+/// Calls the helpers that interpret the names and lengths of the variables
+struct Walk;
+
+impl HierarchyVisitor<'_> for Walk {
+    fn enter_scope(&mut self, scope: &Scope) {
+        for bus in scope.virtual_buses() {
+            let _ = bus.width();
+        }
+    }
+
+    fn variable(&mut self, variable: &Variable) {
+        let vcd = variable.vcd();
+        let _ = vcd.parsed_name();
+        let range = vcd.bit_range();
+        let _ = range.bit(range.width() - 1);
+        for bit in [range.msb, range.lsb, range.lsb.wrapping_sub(1)] {
+            let _ = VirtualBit::new(variable, bit);
+        }
+    }
+}
+
+/// Walk the hierarchy without building paths, they are long in very deep hierarchies
+fn walk(hierarchy: &HierarchyContent) {
+    hierarchy.visit(&mut Walk);
+}
+
+/// Run every block content decoder on the first `max_value_change_blocks` value change blocks.
+/// Errors are fine, but panics are not.
+fn decode(data: &[u8], max_value_change_blocks: usize) -> FstResult<()> {
+    let content = fst_file::parse(data)?;
+    let header = content.header.as_ref().map(|header| header.get_content());
+    if let Some(hierarchy) = &content.hierarchy {
+        let _ = hierarchy.get_tokens();
+        if let Ok(hierarchy) = hierarchy.get_content() {
+            walk(&hierarchy);
+        }
+    }
+    if let Some(blackout) = &content.blackout {
+        let _ = blackout.get_content();
+    }
+    let geometry = content
+        .geometry
+        .as_ref()
+        .map(|geometry| geometry.get_content());
+    for block in content
+        .value_change_data
+        .iter()
+        .take(max_value_change_blocks)
+    {
+        let _ = block.get_time_range();
+        if let Some(Ok(header)) = &header {
+            let _ = block.get_intermediate_content(header);
+            let _ = block.get_content(header);
+        }
+        if let Some(Ok(geometry)) = &geometry {
+            let _ = block.get_value_changes(geometry, |_| true);
+        }
+    }
+    Ok(())
+}
+
+/// Small deterministic generator, so failures can be reproduced
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Decode copies of the file with a few random bytes changed and truncated copies
+fn mutations(path: &str, seed: u64, rounds: usize, max_value_change_blocks: usize) {
+    let original = fs::read(path).unwrap();
+    decode(&original, max_value_change_blocks).unwrap();
+    let mut rng = Lcg(seed);
+    for _ in 0..rounds {
+        let mut data = original.clone();
+        for _ in 0..1 + rng.below(4) {
+            let i = rng.below(data.len());
+            data[i] = rng.next() as u8;
+        }
+        let _ = decode(&data, max_value_change_blocks);
+    }
+    for _ in 0..rounds / 4 {
+        let len = rng.below(original.len());
+        let _ = decode(&original[..len], max_value_change_blocks);
+    }
+}
+
+#[test]
+fn mutated_sample_does_not_panic() {
+    // the sample has hundreds of value change blocks, only the first ones are decoded
+    mutations("tests/sample.fst", 1, 40, 2);
+}
+
+#[test]
+fn mutated_sample2_does_not_panic() {
+    mutations("tests/sample2.fst", 2, 3000, usize::MAX);
+}
+
+/// A file that the writer builds with `declare`
+fn crafted(declare: impl FnOnce(&mut FstWriter<Cursor<Vec<u8>>>)) -> Vec<u8> {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    declare(&mut writer);
+    writer.emit_time_change(0).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn enum_table_with_huge_literal_count() {
+    // twice the count overflows to the number of fields after it
+    let data = crafted(|writer| {
+        writer
+            .set_attr_begin(
+                AttributeType::Misc,
+                MiscType::EnumTable,
+                "t 9223372036854775808",
+                1,
+            )
+            .unwrap();
+        writer.set_scope(ScopeType::VcdModule, "top", "").unwrap();
+        writer
+            .create_var(VarType::SvEnum, VarDir::Implicit, 1, "state", None)
+            .unwrap();
+        writer.set_upscope().unwrap();
+    });
+    let _ = decode(&data, usize::MAX);
+}
+
+#[test]
+fn very_deep_hierarchy() {
+    // the scopes are nested too deep to walk or drop them recursively
+    const DEPTH: usize = 200_000;
+    let data = crafted(|writer| {
+        for _ in 0..DEPTH {
+            writer.set_scope(ScopeType::VcdModule, "m", "").unwrap();
+        }
+        writer
+            .create_var(VarType::VcdWire, VarDir::Implicit, 1, "v", None)
+            .unwrap();
+        for _ in 0..DEPTH {
+            writer.set_upscope().unwrap();
+        }
+    });
+    decode(&data, usize::MAX).unwrap();
+}
+
+/// A file with only a hierarchy block that holds `hierarchy` uncompressed
+fn hierarchy_file(hierarchy: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(hierarchy).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let mut data = vec![BlockType::HierarchyGz as u8];
+    data.extend_from_slice(&(compressed.len() as u64 + 16).to_be_bytes());
+    data.extend_from_slice(&(hierarchy.len() as u64).to_be_bytes());
+    data.extend_from_slice(&compressed);
+    data
+}
+
+/// The hierarchy entry of a wire, which the writer can only give 32 bit lengths
+fn wire(name: &str, length: u64) -> Vec<u8> {
+    let mut data = vec![VarType::VcdWire.to_u8(), VarDir::Implicit.to_u8()];
+    data.extend_from_slice(name.as_bytes());
+    data.push(0);
+    VarInt(length).write_to(&mut data);
+    VarInt(0).write_to(&mut data);
+    data
+}
+
+#[test]
+fn huge_variable_length() {
+    decode(&hierarchy_file(&wire("a", 1 << 63)), 0).unwrap();
+    decode(&hierarchy_file(&wire("a", u64::MAX)), 0).unwrap();
+}
+
+#[test]
+fn huge_bit_range() {
+    let name = "a[9223372036854775807:-9223372036854775808]";
+    decode(&hierarchy_file(&wire(name, 1)), 0).unwrap();
+}
+
+#[test]
+fn bit_range_longer_than_the_variable() {
+    // only the first bit of the range has a value
+    let data = hierarchy_file(&wire("a[40000000000:0]", 1));
+    decode(&data, 0).unwrap();
+    let hierarchy = fst_file::parse(&data)
+        .unwrap()
+        .hierarchy
+        .unwrap()
+        .get_content()
+        .unwrap();
+    let variable = &hierarchy.variables()[0];
+    assert!(VirtualBit::new(variable, 40000000000).is_some());
+    assert!(VirtualBit::new(variable, 0).is_none());
+    assert!(VirtualBit::new(variable, -1).is_none());
+}
@@ -0,0 +1,153 @@
+use std::io::Cursor;
+
+use fst_file::{
+    block_parsers::{
+        blackout::BlackoutParseError,
+        header::HeaderParseError,
+        hierarchy::{HierarchyBlockConvertError, ScopeType, VarDir, VarType},
+    },
+    data_types::BlockType,
+    options::{Anomaly, AnomalyKind, ParseOptions},
+    writer::{FstWriter, WriterOptions},
+};
+
+fn unknown_code(anomaly: &Anomaly) -> (&'static str, u8) {
+    match anomaly.kind {
+        AnomalyKind::UnknownCode { what, code, .. } => (what, code),
+        ref kind => panic!("unexpected anomaly {kind:?}"),
+    }
+}
+
+#[test]
+fn samples_pass_strict_parsing() {
+    for (path, value_change_blocks) in [("tests/sample.fst", 2), ("tests/sample2.fst", usize::MAX)]
+    {
+        let options = ParseOptions::strict();
+        let content =
+            fst_file::parse_with_options(&std::fs::read(path).unwrap(), &options).unwrap();
+        let header = content.header.unwrap().get_content().unwrap();
+        let geometry = content.geometry.unwrap().get_content().unwrap();
+        content.hierarchy.unwrap().get_content().unwrap();
+        if let Some(blackout) = content.blackout {
+            blackout.get_content().unwrap();
+        }
+        for block in content.value_change_data.iter().take(value_change_blocks) {
+            block.get_content(&header).unwrap();
+            block.get_value_changes(&geometry, |_| true).unwrap();
+        }
+        assert!(options.diagnostics().is_empty());
+    }
+}
+
+/// A hierarchy with an unknown scope type and direction whose last scope is not closed
+fn imperfect_hierarchy() -> Vec<u8> {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    writer.set_scope(ScopeType::Unknown(60), "top", "").unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Unknown(9), 1, "a", None)
+        .unwrap();
+    writer.emit_time_change(0).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn lenient_parsing_reports_anomalies() {
+    let options = ParseOptions::lenient();
+    let content = fst_file::parse_with_options(&imperfect_hierarchy(), &options).unwrap();
+    let hierarchy = content.hierarchy.unwrap().get_content().unwrap();
+    assert_eq!(hierarchy.roots()[0].name(), "top");
+    assert_eq!(hierarchy.roots()[0].variables().len(), 1);
+
+    let anomalies = options.take_diagnostics();
+    assert_eq!(anomalies.len(), 3, "{anomalies:?}");
+    assert_eq!(unknown_code(&anomalies[0]), ("scope type", 60));
+    assert_eq!(unknown_code(&anomalies[1]), ("variable direction", 9));
+    assert_eq!(anomalies[2].kind, AnomalyKind::UnclosedScopes { count: 1 });
+    assert!(anomalies
+        .iter()
+        .all(|anomaly| anomaly.block.block_type == Some(BlockType::HierarchyGz)));
+    assert!(options.diagnostics().is_empty());
+}
+
+#[test]
+fn strict_parsing_fails_on_the_first_anomaly() {
+    let options = ParseOptions::strict();
+    let content = fst_file::parse_with_options(&imperfect_hierarchy(), &options).unwrap();
+    let error = content.hierarchy.unwrap().get_content().unwrap_err();
+    let HierarchyBlockConvertError::Anomaly(anomaly) = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(unknown_code(&anomaly), ("scope type", 60));
+    assert_eq!(
+        anomaly.to_string(),
+        "unknown scope type 60 at offset 0 in block #3 (Hierarchy (Gzip))"
+    );
+}
+
+#[test]
+fn header_text_that_is_not_utf8() {
+    let mut content = std::fs::read("tests/sample2.fst").unwrap();
+    // first byte of the writer in the header
+    content[74] = 0xff;
+
+    let options = ParseOptions::lenient();
+    let header = fst_file::parse_with_options(&content, &options)
+        .unwrap()
+        .header
+        .unwrap()
+        .get_content()
+        .unwrap();
+    assert!(header.writer.starts_with('\u{fffd}'));
+    let expected = AnomalyKind::InvalidText {
+        what: "writer",
+        offset: 65,
+    };
+    assert_eq!(options.diagnostics()[0].kind, expected);
+
+    let error = fst_file::parse_with_options(&content, &ParseOptions::strict())
+        .unwrap()
+        .header
+        .unwrap()
+        .get_content()
+        .unwrap_err();
+    assert!(matches!(error, HeaderParseError::Anomaly(anomaly) if anomaly.kind == expected));
+}
+
+#[test]
+fn blackout_activity_that_is_not_a_bool() {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    writer
+        .create_var(VarType::VcdWire, VarDir::Implicit, 1, "a", None)
+        .unwrap();
+    writer.emit_time_change(0).unwrap();
+    writer.emit_dump_active(false).unwrap();
+    let mut content = writer.finish().unwrap().into_inner();
+    let blackout = fst_file::parse_raw_block_information(&content)
+        .unwrap()
+        .into_iter()
+        .find(|block| block.get_block().block_type == BlockType::Blackout)
+        .unwrap();
+    // the activity of the first record, after the record count
+    content[blackout.get_data_start_offset() + 1] = 7;
+
+    let error = fst_file::parse_with_options(&content, &ParseOptions::strict())
+        .unwrap()
+        .blackout
+        .unwrap()
+        .get_content()
+        .unwrap_err();
+    let BlackoutParseError::Anomaly(anomaly) = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(unknown_code(&anomaly), ("blackout activity", 7));
+
+    let options = ParseOptions::lenient();
+    let blackout = fst_file::parse_with_options(&content, &options)
+        .unwrap()
+        .blackout
+        .unwrap()
+        .get_content()
+        .unwrap();
+    assert_eq!(blackout.dump_changes().collect::<Vec<_>>(), [(false, 0)]);
+    assert_eq!(options.diagnostics().len(), 1);
+}
@@ -1,5 +1,10 @@
 use std::{fs::File, io::Read};
 
+use fst_file::{
+    block_parsers::hierarchy::diff::diff,
+    data_types::{BlockType, Handle},
+};
+
 fn get_test_file_content() -> Vec<u8> {
     let mut v = Vec::new();
     let mut file = File::open("tests/sample.fst").unwrap();
@@ -47,3 +52,54 @@ fn parse_blackout() {
     let blocks = fst_file::parse(&content).unwrap();
     blocks.blackout.unwrap().get_content().unwrap();
 }
+
+#[test]
+fn parse_lz4_duo_hierarchy() {
+    let content = std::fs::read("tests/sample2.fst").unwrap();
+    let expected = fst_file::parse(&content).unwrap().hierarchy.unwrap();
+    let data = expected.get_block().extract_data().unwrap();
+    let expected = expected.get_content().unwrap();
+
+    // laid out like fstapi writes it: uncompressed size, varint size after the first
+    // pass, then the data compressed twice
+    let once = lz4_flex::block::compress(&data);
+    let twice = lz4_flex::block::compress(&once);
+    let mut payload = (data.len() as u64).to_be_bytes().to_vec();
+    let mut size = once.len();
+    while size >= 0x80 {
+        payload.push(size as u8 | 0x80);
+        size >>= 7;
+    }
+    payload.push(size as u8);
+    payload.extend_from_slice(&twice);
+    let mut file = vec![BlockType::HierarchyLz4Duo as u8];
+    file.extend_from_slice(&(payload.len() as u64 + 8).to_be_bytes());
+    file.extend_from_slice(&payload);
+
+    let hierarchy = fst_file::parse(&file)
+        .unwrap()
+        .hierarchy
+        .unwrap()
+        .get_content()
+        .unwrap();
+    assert!(diff(&expected, &hierarchy).is_empty());
+}
+
+#[test]
+fn value_change_data_aliases() {
+    // sample.fst was written by fstapi, the fourth and fifth signal of the first block
+    // are dynamic aliases of the third and the first signal
+    let content = get_test_file_content();
+    let blocks = fst_file::parse(&content).unwrap();
+    let header = blocks.header.unwrap().get_content().unwrap();
+    let geometry = blocks.geometry.unwrap().get_content().unwrap();
+    let block = &blocks.value_change_data[0];
+
+    let data = serde_json::to_value(block.get_content(&header).unwrap()).unwrap();
+    assert_eq!(data["chain_table_lengths"][3], 2);
+    assert_eq!(data["chain_table_lengths"][4], 0);
+
+    let value_changes = block.get_value_changes(&geometry, |_| true).unwrap();
+    assert_eq!(value_changes.get(Handle(4)), value_changes.get(Handle(3)));
+    assert_eq!(value_changes.get(Handle(5)), value_changes.get(Handle(1)));
+}
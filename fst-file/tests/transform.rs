@@ -0,0 +1,741 @@
+use std::io::Cursor;
+
+use fst_file::{
+    block_parsers::{
+        geometry::{Geometry, SignalGeometry},
+        hierarchy::{
+            diff::diff,
+            source::{SourceLocation, SourceLocations},
+            EnumLiteral, EnumTable, HierarchyContent, HierarchyNode, ScopeType, VarDir, VarType,
+        },
+    },
+    convert::vcd_to_fst,
+    data_types::{BlockType, Handle, WriterPackType},
+    transform::{
+        crop, extract_signals, merge_back_to_back, merge_side_by_side, pack_type, rechunk, repack,
+        unwrap_gzip_wrapper, SignalSelector,
+    },
+    writer::{write_gzip_wrapper, FstWriter, HierarchyCompression, WriterOptions},
+    FstFileContent,
+};
+
+const VCD: &str = r#"$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! clk $end
+$var reg 4 " count [3:0] $end
+$scope module a $end
+$var wire 1 ! clk $end
+$var reg 8 # data [7:0] $end
+$upscope $end
+$scope module b $end
+$var real 64 $ r $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+b0 "
+bx #
+r0 $
+#10
+1!
+b1 "
+b10101010 #
+#20
+0!
+r2.5 $
+#30
+1!
+b10 "
+b1z #
+"#;
+
+fn small_blocks() -> WriterOptions {
+    WriterOptions {
+        block_size: 1,
+        ..Default::default()
+    }
+}
+
+fn input() -> Vec<u8> {
+    vcd_to_fst(VCD.as_bytes(), Cursor::new(Vec::new()), small_blocks())
+        .unwrap()
+        .into_inner()
+}
+
+/// All value changes of a signal, starting with the initial value
+fn changes(content: &FstFileContent, geometry: &Geometry, handle: Handle) -> Vec<(u64, Vec<u8>)> {
+    let mut result = Vec::new();
+    for (i, block) in content.value_change_data.iter().enumerate() {
+        let value_changes = block.get_value_changes(geometry, |h| h == handle).unwrap();
+        let signal = value_changes.get(handle).unwrap();
+        if i == 0 {
+            result.push((0, signal.initial.clone()));
+        }
+        result.extend(signal.changes.iter().cloned());
+    }
+    result
+}
+
+/// Number of value changes of all signals at `time`
+fn changes_at(content: &FstFileContent, time: u64) -> usize {
+    let geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    content
+        .value_change_data
+        .iter()
+        .map(|block| {
+            let value_changes = block.get_value_changes(&geometry, |_| true).unwrap();
+            value_changes
+                .signals
+                .iter()
+                .flatten()
+                .flat_map(|signal| &signal.changes)
+                .filter(|(t, _)| *t == time)
+                .count()
+        })
+        .sum()
+}
+
+#[test]
+fn decode_value_changes() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(
+        changes(&content, &geometry, Handle(1)),
+        [
+            (0, b"x".to_vec()),
+            (0, b"0".to_vec()),
+            (10, b"1".to_vec()),
+            (20, b"0".to_vec()),
+            (30, b"1".to_vec())
+        ]
+    );
+    assert_eq!(
+        changes(&content, &geometry, Handle(3)),
+        [
+            (0, b"xxxxxxxx".to_vec()),
+            (0, b"xxxxxxxx".to_vec()),
+            (10, b"10101010".to_vec()),
+            (30, b"0000001z".to_vec())
+        ]
+    );
+    assert_eq!(
+        changes(&content, &geometry, Handle(4)),
+        [
+            (0, f64::NAN.to_le_bytes().to_vec()),
+            (0, 0f64.to_le_bytes().to_vec()),
+            (20, 2.5f64.to_le_bytes().to_vec())
+        ]
+    );
+}
+
+#[test]
+fn extract() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let selector = SignalSelector::new(["top.a", "top.count"]);
+    let output = extract_signals(
+        &content,
+        &selector,
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+
+    let extracted = fst_file::parse(&output).unwrap();
+    let header = extracted.header.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(header.start_time, 0);
+    assert_eq!(header.end_time, 30);
+    assert_eq!(header.timescale.0, -9);
+    assert_eq!(header.num_scopes, 2);
+    assert_eq!(header.num_hierarchy_vars, 3);
+
+    // count is the first selected variable in the hierarchy, top.a.clk the second and top.a.data the third
+    let geometry = extracted.geometry.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(geometry.len(), 3);
+    assert_eq!(geometry.get(Handle(1)), Some(SignalGeometry::Bits(4)));
+    assert_eq!(geometry.get(Handle(3)), Some(SignalGeometry::Bits(8)));
+
+    let original_geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    for (old, new) in [(2, 1), (1, 2), (3, 3)] {
+        assert_eq!(
+            changes(&content, &original_geometry, Handle(old)),
+            changes(&extracted, &geometry, Handle(new))
+        );
+    }
+}
+
+#[test]
+fn extract_glob() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let selector = SignalSelector::new(["**.clk", "top.b.*"]);
+    let output = extract_signals(
+        &content,
+        &selector,
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+    let extracted = fst_file::parse(&output).unwrap();
+    let header = extracted.header.as_ref().unwrap().get_content().unwrap();
+    // both clk variables share one handle
+    assert_eq!(header.num_hierarchy_vars, 3);
+    assert_eq!(header.num_vars, 2);
+}
+
+/// Two enum variables `d0` and `k0` in `top`, with the enum table `table`
+/// and the source file `file` defined in front of the first one
+fn annotated_input(table: &str, file: &str) -> Vec<u8> {
+    let mut writer = FstWriter::new(Cursor::new(Vec::new()), WriterOptions::default()).unwrap();
+    writer.set_scope(ScopeType::VcdModule, "top", "").unwrap();
+    let literals = [("IDLE", "0"), ("BUSY", "1")]
+        .into_iter()
+        .map(|(name, value)| EnumLiteral {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .collect();
+    let table = writer
+        .create_enum_table(&EnumTable::new(table.to_string(), literals))
+        .unwrap();
+    for (name, line) in [("d0", 3), ("k0", 7)] {
+        writer.emit_enum_table_ref(table).unwrap();
+        writer.set_source_stem(file, line, false).unwrap();
+        writer
+            .create_var(VarType::SvEnum, VarDir::Implicit, 1, name, None)
+            .unwrap();
+    }
+    writer.set_upscope().unwrap();
+    writer.emit_time_change(0).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+/// Name of the enum table and the source locations of the variable at `path`
+fn annotations(hierarchy: &HierarchyContent, path: &str) -> (Option<String>, Vec<SourceLocation>) {
+    let (_, variable) = hierarchy
+        .iter()
+        .find(|(variable_path, _)| variable_path == path)
+        .unwrap();
+    let HierarchyNode::Variable(variable) = variable else {
+        panic!("{path} is not a variable");
+    };
+    let table = hierarchy
+        .enum_table(variable)
+        .map(|table| table.name().to_string());
+    (table, SourceLocations::new(hierarchy).get(path).to_vec())
+}
+
+#[test]
+fn extract_keeps_definitions() {
+    let content = annotated_input("color_t", "a.sv");
+    let content = fst_file::parse(&content).unwrap();
+    let output = extract_signals(
+        &content,
+        &SignalSelector::new(["top.k0"]),
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+
+    let extracted = fst_file::parse(&output).unwrap();
+    let hierarchy = extracted.hierarchy.unwrap().get_content().unwrap();
+    assert_eq!(hierarchy.iter().count(), 2);
+    assert_eq!(
+        annotations(&hierarchy, "top.k0"),
+        (
+            Some("color_t".to_string()),
+            vec![SourceLocation {
+                file: "a.sv".to_string(),
+                line: 7,
+                instantiation: false,
+            }]
+        )
+    );
+}
+
+#[test]
+fn extract_nothing() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let selector = SignalSelector::new(["top.missing"]);
+    assert!(extract_signals(
+        &content,
+        &selector,
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .is_err());
+}
+
+fn crop_input(content: &FstFileContent, from: u64, to: u64) -> Vec<u8> {
+    crop(
+        content,
+        from,
+        to,
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner()
+}
+
+#[test]
+fn crop_window() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let output = crop_input(&content, 15, 25);
+
+    let cropped = fst_file::parse(&output).unwrap();
+    let header = cropped.header.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(header.start_time, 15);
+    assert_eq!(header.end_time, 25);
+    assert_eq!(header.num_vars, 4);
+    assert_eq!(cropped.value_change_data.len(), 1);
+
+    // the value at the start of the window becomes the initial value
+    let geometry = cropped.geometry.as_ref().unwrap().get_content().unwrap();
+    let first = cropped.value_change_data[0]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap();
+    assert_eq!(first.get(Handle(1)).unwrap().initial, b"1");
+    assert_eq!(first.get(Handle(3)).unwrap().initial, b"10101010");
+    assert_eq!(
+        changes(&cropped, &geometry, Handle(4)),
+        [
+            (0, 0f64.to_le_bytes().to_vec()),
+            (20, 2.5f64.to_le_bytes().to_vec())
+        ]
+    );
+    assert_eq!(
+        changes(&cropped, &geometry, Handle(2)),
+        [(0, b"0001".to_vec())]
+    );
+    // and is not repeated as a change at the start
+    assert_eq!(changes_at(&cropped, 15), 0);
+}
+
+#[test]
+fn crop_copies_inner_blocks() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let output = crop_input(&content, 5, u64::MAX);
+    let cropped = fst_file::parse(&output).unwrap();
+    let header = cropped.header.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(header.start_time, 5);
+    assert_eq!(header.end_time, 30);
+    // the block crossing the start is encoded again, the later ones are copied
+    let time_ranges: Vec<_> = cropped
+        .value_change_data
+        .iter()
+        .map(|block| block.get_time_range().unwrap())
+        .collect();
+    assert_eq!(time_ranges, [(5, 10), (20, 20), (30, 30)]);
+
+    let original_geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    let geometry = cropped.geometry.as_ref().unwrap().get_content().unwrap();
+    for handle in 1..=4 {
+        let original: Vec<_> = changes(&content, &original_geometry, Handle(handle))
+            .into_iter()
+            .filter(|(time, _)| *time > 5)
+            .collect();
+        let cropped: Vec<_> = changes(&cropped, &geometry, Handle(handle))
+            .into_iter()
+            .filter(|(time, _)| *time > 5)
+            .collect();
+        assert_eq!(original, cropped);
+    }
+}
+
+#[test]
+fn crop_blackout() {
+    let vcd = VCD
+        .replace("#20\n", "#20\n$dumpoff $end\n")
+        .replace("#30\n", "#30\n$dumpon $end\n");
+    let content = vcd_to_fst(vcd.as_bytes(), Cursor::new(Vec::new()), small_blocks())
+        .unwrap()
+        .into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    let output = crop_input(&content, 25, 30);
+    let cropped = fst_file::parse(&output).unwrap();
+    let blackout = cropped.blackout.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(
+        blackout.dump_changes().collect::<Vec<_>>(),
+        [(false, 25), (true, 30)]
+    );
+}
+
+#[test]
+fn crop_empty_window() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    assert!(crop(
+        &content,
+        40,
+        50,
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .is_err());
+}
+
+/// A later run of the design in [VCD] with a finer timescale and a timezero
+const LATER_VCD: &str = r#"$timescale 100ps $end
+$timezero 300 $end
+$scope module top $end
+$var wire 1 ! clk $end
+$var reg 4 " count [3:0] $end
+$scope module a $end
+$var wire 1 ! clk $end
+$var reg 8 # data [7:0] $end
+$upscope $end
+$scope module b $end
+$var real 64 $ r $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+b11 "
+b0 #
+r1 $
+#50
+1!
+#100
+0!
+b100 "
+"#;
+
+fn later_input() -> Vec<u8> {
+    vcd_to_fst(
+        LATER_VCD.as_bytes(),
+        Cursor::new(Vec::new()),
+        small_blocks(),
+    )
+    .unwrap()
+    .into_inner()
+}
+
+#[test]
+fn merge_side_by_side_inputs() {
+    let first = input();
+    let first = fst_file::parse(&first).unwrap();
+    let second = later_input();
+    let second = fst_file::parse(&second).unwrap();
+    let output = merge_side_by_side(
+        &[("first", &first), ("second", &second)],
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+
+    let merged = fst_file::parse(&output).unwrap();
+    let header = merged.header.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(header.timescale.0, -10);
+    assert_eq!(header.timezero, 0);
+    assert_eq!(header.start_time, 0);
+    assert_eq!(header.end_time, 400);
+    assert_eq!(header.num_scopes, 8);
+    assert_eq!(header.num_vars, 8);
+
+    // the times of the first input are scaled to the finer timescale
+    let geometry = merged.geometry.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(
+        changes(&merged, &geometry, Handle(1))
+            .into_iter()
+            .skip(1)
+            .collect::<Vec<_>>(),
+        [
+            (0, b"0".to_vec()),
+            (100, b"1".to_vec()),
+            (200, b"0".to_vec()),
+            (300, b"1".to_vec())
+        ]
+    );
+    // and the ones of the second input are shifted by its timezero
+    assert_eq!(
+        changes(&merged, &geometry, Handle(5))
+            .into_iter()
+            .filter(|(time, _)| *time > 0)
+            .collect::<Vec<_>>(),
+        [
+            (300, b"0".to_vec()),
+            (350, b"1".to_vec()),
+            (400, b"0".to_vec())
+        ]
+    );
+    // only the changes of the first input at its start, the initial values are not repeated
+    assert_eq!(changes_at(&merged, 0), 4);
+}
+
+#[test]
+fn merge_side_by_side_definitions() {
+    // both inputs define enum table 1 and source path 1
+    let first = annotated_input("color_t", "a.sv");
+    let first = fst_file::parse(&first).unwrap();
+    let second = annotated_input("state_t", "b.sv");
+    let second = fst_file::parse(&second).unwrap();
+    let output = merge_side_by_side(
+        &[("A", &first), ("B", &second)],
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+
+    let merged = fst_file::parse(&output).unwrap();
+    let hierarchy = merged.hierarchy.unwrap().get_content().unwrap();
+    assert_eq!(hierarchy.enum_tables().len(), 2);
+    let location = |file: &str, line| SourceLocation {
+        file: file.to_string(),
+        line,
+        instantiation: false,
+    };
+    for (path, table, file, line) in [
+        ("A.top.d0", "color_t", "a.sv", 3),
+        ("A.top.k0", "color_t", "a.sv", 7),
+        ("B.top.d0", "state_t", "b.sv", 3),
+        ("B.top.k0", "state_t", "b.sv", 7),
+    ] {
+        assert_eq!(
+            annotations(&hierarchy, path),
+            (Some(table.to_string()), vec![location(file, line)]),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn merge_back_to_back_inputs() {
+    let first = input();
+    let first = fst_file::parse(&first).unwrap();
+    let second = later_input();
+    let second = fst_file::parse(&second).unwrap();
+    let output = merge_back_to_back(
+        &[&first, &second],
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .unwrap()
+    .into_inner();
+
+    let merged = fst_file::parse(&output).unwrap();
+    let header = merged.header.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(header.timescale.0, -10);
+    assert_eq!(header.start_time, 0);
+    assert_eq!(header.end_time, 400);
+    assert_eq!(header.num_vars, 4);
+
+    // the second input takes over at its start time with its own values
+    let geometry = merged.geometry.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(
+        changes(&merged, &geometry, Handle(2))
+            .into_iter()
+            .skip(1)
+            .collect::<Vec<_>>(),
+        [
+            (0, b"0000".to_vec()),
+            (100, b"0001".to_vec()),
+            (300, b"0011".to_vec()),
+            (400, b"0100".to_vec())
+        ]
+    );
+    // only the signals whose value differs change at the boundary
+    assert_eq!(changes_at(&merged, 300), 3);
+
+    // the segments have to be in order
+    assert!(merge_back_to_back(
+        &[&second, &first],
+        Cursor::new(Vec::new()),
+        WriterOptions::default(),
+    )
+    .is_err());
+}
+
+#[test]
+fn repack_compression() {
+    let content = input();
+    let content = fst_file::parse(&content).unwrap();
+    let output = repack(
+        &content,
+        Cursor::new(Vec::new()),
+        WriterOptions {
+            hierarchy_compression: HierarchyCompression::Lz4Duo,
+            pack_type: WriterPackType::Zlib,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .into_inner();
+
+    let blocks = fst_file::parse_raw_block_information(&output).unwrap();
+    assert!(blocks
+        .iter()
+        .any(|block| block.get_block().block_type == BlockType::HierarchyLz4Duo));
+    let repacked = fst_file::parse(&output).unwrap();
+    // all value changes end up in one block
+    assert_eq!(repacked.value_change_data.len(), 1);
+
+    let original_geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    let geometry = repacked.geometry.as_ref().unwrap().get_content().unwrap();
+    for handle in 1..=4 {
+        assert_eq!(
+            changes(&content, &original_geometry, Handle(handle)),
+            changes(&repacked, &geometry, Handle(handle))
+        );
+    }
+}
+
+/// Hierarchy of `content` and of the copies of it made by repack and crop
+fn copied_hierarchies(content: &[u8]) -> Vec<HierarchyContent> {
+    let content = fst_file::parse(content).unwrap();
+    let repacked = repack(&content, Cursor::new(Vec::new()), WriterOptions::default())
+        .unwrap()
+        .into_inner();
+    let cropped = crop_input(&content, 0, u64::MAX);
+    [
+        content,
+        fst_file::parse(&repacked).unwrap(),
+        fst_file::parse(&cropped).unwrap(),
+    ]
+    .iter()
+    .map(|content| content.hierarchy.as_ref().unwrap().get_content().unwrap())
+    .collect()
+}
+
+#[test]
+fn copies_keep_empty_scopes() {
+    let vcd = VCD.replace(
+        "$scope module b $end",
+        "$scope module empty $end\n$upscope $end\n$scope module b $end",
+    );
+    let content = vcd_to_fst(vcd.as_bytes(), Cursor::new(Vec::new()), small_blocks())
+        .unwrap()
+        .into_inner();
+    let hierarchies = copied_hierarchies(&content);
+    for copy in &hierarchies[1..] {
+        assert_eq!(diff(&hierarchies[0], copy), []);
+    }
+}
+
+#[test]
+fn gzip_wrapper() {
+    let content = input();
+    let wrapped = write_gzip_wrapper(Vec::new(), &content).unwrap();
+    assert_eq!(wrapped[0], BlockType::GZippedWrapper as u8);
+    assert_eq!(unwrap_gzip_wrapper(&wrapped).unwrap(), &content[..]);
+    // files without a wrapper are returned as they are
+    assert_eq!(unwrap_gzip_wrapper(&content).unwrap(), &content[..]);
+}
+
+#[test]
+fn rechunk_time_span() {
+    let content = vcd_to_fst(
+        VCD.as_bytes(),
+        Cursor::new(Vec::new()),
+        WriterOptions {
+            hierarchy_compression: HierarchyCompression::Lz4,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .into_inner();
+    let content = fst_file::parse(&content).unwrap();
+    assert_eq!(content.value_change_data.len(), 1);
+    let output = rechunk(
+        &content,
+        Cursor::new(Vec::new()),
+        WriterOptions {
+            block_time_span: Some(20),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .into_inner();
+
+    let blocks = fst_file::parse_raw_block_information(&output).unwrap();
+    assert!(blocks
+        .iter()
+        .any(|block| block.get_block().block_type == BlockType::HierarchyLz4));
+    let rechunked = fst_file::parse(&output).unwrap();
+    let time_ranges: Vec<_> = rechunked
+        .value_change_data
+        .iter()
+        .map(|block| block.get_time_range().unwrap())
+        .collect();
+    assert_eq!(time_ranges, [(0, 10), (20, 30)]);
+
+    // the initial values of the second block are the values at its start
+    let geometry = rechunked.geometry.as_ref().unwrap().get_content().unwrap();
+    let second = rechunked.value_change_data[1]
+        .get_value_changes(&geometry, |_| true)
+        .unwrap();
+    assert_eq!(second.get(Handle(1)).unwrap().initial, b"1");
+    assert_eq!(second.get(Handle(2)).unwrap().initial, b"0001");
+    assert_eq!(second.get(Handle(3)).unwrap().initial, b"10101010");
+
+    let original_geometry = content.geometry.as_ref().unwrap().get_content().unwrap();
+    for handle in 1..=4 {
+        assert_eq!(
+            changes(&content, &original_geometry, Handle(handle)),
+            changes(&rechunked, &geometry, Handle(handle))
+        );
+    }
+}
+
+#[test]
+fn rechunk_copies_hierarchy_blocks() {
+    let vcd = VCD.replace(
+        "$scope module b $end",
+        "$scope module empty $end\n$upscope $end\n$scope module b $end",
+    );
+    let content = vcd_to_fst(
+        vcd.as_bytes(),
+        Cursor::new(Vec::new()),
+        WriterOptions {
+            hierarchy_compression: HierarchyCompression::Lz4Duo,
+            pack_type: WriterPackType::FaslLz,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .into_inner();
+    let parsed = fst_file::parse(&content).unwrap();
+    assert_eq!(pack_type(&parsed).unwrap(), Some(WriterPackType::FaslLz));
+    let output = rechunk(&parsed, Cursor::new(Vec::new()), small_blocks())
+        .unwrap()
+        .into_inner();
+
+    // the geometry and hierarchy blocks are the same byte for byte
+    let copied_blocks = |content: &[u8]| {
+        fst_file::parse_raw_block_information(content)
+            .unwrap()
+            .into_iter()
+            .filter(|info| {
+                let block_type = info.get_block().block_type;
+                block_type == BlockType::Geometry || block_type == BlockType::HierarchyLz4Duo
+            })
+            .map(|info| {
+                content[info.get_block_start_offset()..info.get_block_end_offset()].to_vec()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(copied_blocks(&content).len(), 2);
+    assert_eq!(copied_blocks(&content), copied_blocks(&output));
+
+    let rechunked = fst_file::parse(&output).unwrap();
+    assert_eq!(rechunked.value_change_data.len(), 4);
+    let header = parsed.header.as_ref().unwrap().get_content().unwrap();
+    let rechunked_header = rechunked.header.as_ref().unwrap().get_content().unwrap();
+    assert_eq!(rechunked_header.num_scopes, header.num_scopes);
+    assert_eq!(
+        rechunked_header.num_hierarchy_vars,
+        header.num_hierarchy_vars
+    );
+    assert_eq!(rechunked_header.num_vars, header.num_vars);
+}